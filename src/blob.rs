@@ -1,9 +1,21 @@
-use crate::error::{Converter, WRONG_OFFSET};
+#[cfg(any(feature = "read", feature = "write"))]
+use crate::error::Converter;
+#[cfg(feature = "read")]
+use crate::error::WRONG_OFFSET;
+#[cfg(feature = "read")]
 use crate::paged_reader::PagedReader;
+#[cfg(feature = "write")]
 use crate::paged_writer::PagedWriter;
-use crate::{Error, Result};
+#[cfg(feature = "read")]
+use crate::Error;
+#[cfg(any(feature = "read", feature = "write"))]
+use crate::Result;
+#[cfg(feature = "read")]
 use roxmltree::Node;
-use std::io::{copy, Read, Seek, Write};
+#[cfg(feature = "read")]
+use std::io::{copy, Take};
+#[cfg(any(feature = "read", feature = "write"))]
+use std::io::{Read, Seek, Write};
 
 /// Describes a binary data blob stored inside an E57 file.
 #[derive(Clone, Debug)]
@@ -16,6 +28,15 @@ pub struct Blob {
 }
 
 impl Blob {
+    /// Creates a new blob descriptor from the physical offset and the logical length.
+    /// This is useful to access blobs referenced by custom XML elements of extensions,
+    /// where the values are taken from the `fileOffset` and `length` attributes.
+    /// Readers can also look up such blobs with `E57Reader::custom_blob()`.
+    pub fn new(offset: u64, length: u64) -> Self {
+        Self { offset, length }
+    }
+
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         if Some("Blob") != node.attribute("type") {
             Error::invalid("The supplided tag is not a blob")?
@@ -38,6 +59,7 @@ impl Blob {
         Ok(Self { offset, length })
     }
 
+    #[cfg(feature = "read")]
     pub(crate) fn from_parent_node(tag_name: &str, parent_node: &Node) -> Result<Option<Self>> {
         if let Some(node) = &parent_node.children().find(|n| n.has_tag_name(tag_name)) {
             Ok(Some(Self::from_node(node)?))
@@ -46,18 +68,31 @@ impl Blob {
         }
     }
 
-    pub(crate) fn xml_string(&self, tag_name: &str) -> String {
+    /// Generates the XML element for this blob with the given tag name.
+    /// Can be used to reference blobs from custom XML elements of extensions.
+    pub fn xml_string(&self, tag_name: &str) -> String {
         format!(
             "<{tag_name} type=\"Blob\" fileOffset=\"{}\" length=\"{}\"/>\n",
             self.offset, self.length
         )
     }
 
+    #[cfg(feature = "read")]
     pub(crate) fn read<T: Read + Seek>(
         &self,
         reader: &mut PagedReader<T>,
         writer: &mut dyn Write,
     ) -> Result<u64> {
+        let mut limited = self.open(reader)?;
+        copy(&mut limited, writer).read_err("Failed to read binary blob data")
+    }
+
+    /// Seeks to the start of the blob data and returns a reader limited to the blob length.
+    #[cfg(feature = "read")]
+    pub(crate) fn open<'a, T: Read + Seek>(
+        &self,
+        reader: &'a mut PagedReader<T>,
+    ) -> Result<Take<&'a mut PagedReader<T>>> {
         reader
             .seek_physical(self.offset)
             .read_err("Failed to seek to start offset of blob")?;
@@ -65,11 +100,17 @@ impl Blob {
         if self.length > header.section_length + 16 {
             Error::invalid("Blob XML length and blob section header mismatch")?
         }
-
-        let mut limited = reader.take(self.length);
-        copy(&mut limited, writer).read_err("Failed to read binary blob data")
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            offset = self.offset,
+            length = self.length,
+            "Reading blob section"
+        );
+
+        Ok(reader.take(self.length))
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn write<T: Read + Write + Seek>(
         writer: &mut PagedWriter<T>,
         reader: &mut dyn Read,
@@ -100,15 +141,18 @@ impl Blob {
     }
 }
 
+#[cfg(any(feature = "read", feature = "write"))]
 struct BlobSectionHeader {
     section_length: u64,
 }
 
+#[cfg(any(feature = "read", feature = "write"))]
 impl BlobSectionHeader {
+    #[cfg(feature = "read")]
     fn from_array(buffer: &[u8; 16]) -> Result<Self> {
         let section_id = buffer[0];
         if section_id != 0 {
-            Error::invalid("Section ID of the blob section header is not 0")?
+            Error::malformed("Section ID of the blob section header is not 0")?
         }
         Ok(Self {
             section_length: u64::from_le_bytes(
@@ -117,6 +161,7 @@ impl BlobSectionHeader {
         })
     }
 
+    #[cfg(feature = "read")]
     fn from_reader<T: Read + Seek>(reader: &mut PagedReader<T>) -> Result<BlobSectionHeader> {
         let mut buffer = [0_u8; 16];
         reader
@@ -125,6 +170,7 @@ impl BlobSectionHeader {
         BlobSectionHeader::from_array(&buffer)
     }
 
+    #[cfg(feature = "write")]
     fn to_writer<T: Read + Write + Seek>(&self, writer: &mut PagedWriter<T>) -> Result<()> {
         let mut bytes: [u8; 16] = [0; 16];
         let length_bytes = u64::to_le_bytes(self.section_length);
@@ -1,5 +1,8 @@
+#[cfg(any(feature = "read", feature = "write"))]
 use crate::xml;
+#[cfg(feature = "read")]
 use crate::Result;
+#[cfg(feature = "read")]
 use roxmltree::Node;
 
 /// Optional minimum and maximum values for Cartesian X, Y and Z coordinates.
@@ -14,6 +17,7 @@ pub struct CartesianBounds {
 }
 
 impl CartesianBounds {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             x_min: xml::opt_f64(node, "xMinimum")?,
@@ -25,6 +29,24 @@ impl CartesianBounds {
         })
     }
 
+    #[cfg(any(feature = "read", feature = "write"))]
+    pub(crate) fn scale(&mut self, factor: f64) {
+        for v in [
+            &mut self.x_min,
+            &mut self.x_max,
+            &mut self.y_min,
+            &mut self.y_max,
+            &mut self.z_min,
+            &mut self.z_max,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *v *= factor;
+        }
+    }
+
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::from("<cartesianBounds type=\"Structure\">\n");
         if let Some(min) = self.x_min {
@@ -62,6 +84,7 @@ pub struct SphericalBounds {
 }
 
 impl SphericalBounds {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             range_min: xml::opt_f64(node, "rangeMinimum")?,
@@ -73,6 +96,18 @@ impl SphericalBounds {
         })
     }
 
+    /// Only the range is affected, angles are independent of the length unit.
+    #[cfg(any(feature = "read", feature = "write"))]
+    pub(crate) fn scale(&mut self, factor: f64) {
+        for v in [&mut self.range_min, &mut self.range_max]
+            .into_iter()
+            .flatten()
+        {
+            *v *= factor;
+        }
+    }
+
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::from("<sphericalBounds type=\"Structure\">\n");
         if let Some(min) = self.azimuth_start {
@@ -110,6 +145,7 @@ pub struct IndexBounds {
 }
 
 impl IndexBounds {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             row_min: xml::opt_int(node, "rowMinimum")?,
@@ -121,6 +157,7 @@ impl IndexBounds {
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::from("<indexBounds type=\"Structure\">\n");
         if let Some(min) = self.row_min {
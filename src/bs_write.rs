@@ -20,31 +20,34 @@ impl ByteStreamWriteBuffer {
         }
     }
 
+    /// Appends the lowest bits of the little endian data to the buffer.
+    /// Any additional bits of the data are ignored.
     pub fn add_bits(&mut self, data: &[u8], bits: usize) {
-        if self.last_byte_bit == 0 {
-            let to_append = (bits + 7) / 8; // Integer division with rounding up
-            self.buffer.extend_from_slice(&data[..to_append]);
-            self.last_byte_bit = bits % 8;
-        } else {
-            let start_byte = self.buffer.len() - 1;
-            let start_bit = self.last_byte_bit;
-            for b in 0..bits {
-                let source_byte = b / 8;
-                let source_mask = 1 << (b % 8);
-                let source_bit = (data[source_byte] & source_mask) != 0;
-                let target_mask = if source_bit {
-                    1 << self.last_byte_bit
-                } else {
-                    0
-                };
-                let target_byte = start_byte + ((start_bit + b) / 8);
-                if target_byte >= self.buffer.len() {
-                    self.buffer.push(0);
+        let mut remaining = bits;
+        for byte in data {
+            if remaining == 0 {
+                break;
+            }
+            let take = remaining.min(8);
+            let mask = (1_u16 << take) - 1;
+            self.push_bits(*byte & mask as u8, take);
+            remaining -= take;
+        }
+    }
+
+    /// Appends up to eight bits to the buffer, the value must not contain any additional bits.
+    fn push_bits(&mut self, value: u8, bits: usize) {
+        match self.buffer.last_mut() {
+            Some(last) if self.last_byte_bit != 0 => {
+                *last |= value << self.last_byte_bit;
+                let free = 8 - self.last_byte_bit;
+                if bits > free {
+                    self.buffer.push(value >> free);
                 }
-                self.buffer[target_byte] |= target_mask;
-                self.last_byte_bit = (self.last_byte_bit + 1) % 8;
             }
+            _ => self.buffer.push(value),
         }
+        self.last_byte_bit = (self.last_byte_bit + bits) % 8;
     }
 
     pub fn get_full_bytes(&mut self) -> Vec<u8> {
@@ -139,6 +142,18 @@ mod tests {
         assert_eq!(all, [0b11111111]);
     }
 
+    #[test]
+    fn add_bits_ignores_additional_bits() {
+        let mut buffer = ByteStreamWriteBuffer::new();
+        buffer.add_bits(&[0b11111101], 2);
+        buffer.add_bits(&[0xFF, 0xFF], 9);
+        buffer.add_bits(&[0b11111110], 1);
+        assert_eq!(buffer.all_bytes(), 2);
+
+        let all = buffer.get_all_bytes();
+        assert_eq!(all, [0b11111101, 0b00000111]);
+    }
+
     #[test]
     fn add_mixed_bits_and_bytes() {
         let mut buffer = ByteStreamWriteBuffer::new();
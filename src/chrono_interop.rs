@@ -0,0 +1,54 @@
+use crate::error::Converter;
+use crate::{DateTime, Error};
+use chrono::{TimeZone, Utc};
+
+impl<Tz: TimeZone> From<&chrono::DateTime<Tz>> for DateTime {
+    /// Converts a chrono date time into GPS time without atomic clock reference.
+    /// Leap seconds between UTC and GPS time are taken into account.
+    fn from(dt: &chrono::DateTime<Tz>) -> Self {
+        let seconds = dt.timestamp() as f64 + dt.timestamp_subsec_nanos() as f64 / 1e9;
+        Self::from_unix_time(seconds, false)
+    }
+}
+
+impl TryFrom<&DateTime> for chrono::DateTime<Utc> {
+    type Error = Error;
+
+    /// Converts the GPS time into a UTC date time.
+    /// Fails if the GPS time is not finite or out of the range of chrono.
+    fn try_from(dt: &DateTime) -> crate::Result<Self> {
+        let seconds = dt.unix_time();
+        let whole = seconds.floor();
+        if !whole.is_finite() || whole.abs() >= i64::MAX as f64 {
+            Error::invalid(format!("GPS time {} cannot be represented", dt.gps_time))?
+        }
+        let nanos = ((seconds - whole) * 1e9).round().min(999_999_999.0) as u32;
+        chrono::DateTime::from_timestamp(whole as i64, nanos)
+            .invalid_err(format!("GPS time {} is out of range", dt.gps_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chrono_round_trip() {
+        let utc = Utc.with_ymd_and_hms(2020, 9, 13, 12, 26, 40).unwrap();
+        let dt = DateTime::from(&utc);
+        assert_eq!(dt.gps_time, 1600000000.0 - 315964800.0 + 18.0);
+        assert!(!dt.atomic_reference);
+        let back = chrono::DateTime::<Utc>::try_from(&dt).unwrap();
+        assert_eq!(back, utc);
+
+        let offset = chrono::FixedOffset::east_opt(3600).unwrap();
+        let local = utc.with_timezone(&offset);
+        assert_eq!(DateTime::from(&local).gps_time, dt.gps_time);
+
+        let invalid = DateTime {
+            gps_time: f64::INFINITY,
+            atomic_reference: false,
+        };
+        assert!(chrono::DateTime::<Utc>::try_from(&invalid).is_err());
+    }
+}
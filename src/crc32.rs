@@ -53,8 +53,8 @@ mod tests {
     #[test]
     fn full_page() {
         let mut data = [0_u8; 1024];
-        for i in 0..data.len() {
-            data[i] = (i % 256) as u8;
+        for (i, d) in data.iter_mut().enumerate() {
+            *d = (i % 256) as u8;
         }
         let mut crc = Crc32::new();
         let sum = crc.calculate(&data);
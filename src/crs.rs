@@ -0,0 +1,261 @@
+use crate::error::Converter;
+use crate::{Error, Result};
+
+/// Coordinate reference system parsed from the coordinate metadata of an E57 file.
+///
+/// The E57 standard expects the coordinate metadata to be a
+/// well-known text (WKT) string as defined by the Open Geospatial Consortium.
+/// Both WKT 1 (for example `PROJCS[...]`) and WKT 2 (for example `PROJCRS[...]`) are supported.
+/// Only the top level properties are extracted, the original text is kept
+/// for passing it to a projection library like PROJ.
+/// This library does not include any projection library itself,
+/// use [`PointCloudReaderSimple::set_reprojection()`](crate::PointCloudReaderSimple::set_reprojection)
+/// to convert points while reading.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct CoordinateReferenceSystem {
+    /// Original WKT string.
+    pub wkt: String,
+    /// Keyword of the top level WKT element, for example `PROJCS`, `GEOGCRS` or `COMPOUNDCRS`.
+    pub kind: String,
+    /// Name of the coordinate reference system.
+    pub name: Option<String>,
+    /// Authority name and code of the coordinate reference system, for example `("EPSG", "32633")`.
+    pub authority: Option<(String, String)>,
+}
+
+impl CoordinateReferenceSystem {
+    /// Parses a WKT string and extracts the top level properties.
+    pub fn parse(wkt: &str) -> Result<Self> {
+        let mut parser = WktParser {
+            chars: wkt.chars().collect(),
+            pos: 0,
+        };
+        let element = parser.element(0)?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            Error::invalid("Unexpected characters after the end of the WKT string")?
+        }
+
+        let name = element.values.iter().find_map(|v| match v {
+            WktValue::Text(t) => Some(t.clone()),
+            _ => None,
+        });
+        let authority = element
+            .values
+            .iter()
+            .rev()
+            .find_map(|v| match v {
+                WktValue::Element(e) if e.keyword == "AUTHORITY" || e.keyword == "ID" => Some(e),
+                _ => None,
+            })
+            .and_then(|e| match (e.values.first(), e.values.get(1)) {
+                (Some(WktValue::Text(name)), Some(code)) => Some((name.clone(), code.as_string())),
+                _ => None,
+            });
+
+        Ok(Self {
+            wkt: wkt.to_owned(),
+            kind: element.keyword,
+            name,
+            authority,
+        })
+    }
+
+    /// Returns the code if the authority is EPSG.
+    pub fn epsg_code(&self) -> Option<u32> {
+        let (name, code) = self.authority.as_ref()?;
+        if name.eq_ignore_ascii_case("EPSG") {
+            code.parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Maximum nesting depth of WKT elements to prevent stack overflows with malicious input.
+const MAX_DEPTH: usize = 32;
+
+struct WktElement {
+    keyword: String,
+    values: Vec<WktValue>,
+}
+
+enum WktValue {
+    Text(String),
+    Literal(String),
+    Element(WktElement),
+}
+
+impl WktValue {
+    fn as_string(&self) -> String {
+        match self {
+            WktValue::Text(t) | WktValue::Literal(t) => t.clone(),
+            WktValue::Element(e) => e.keyword.clone(),
+        }
+    }
+}
+
+struct WktParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl WktParser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn element(&mut self, depth: usize) -> Result<WktElement> {
+        if depth > MAX_DEPTH {
+            Error::invalid(format!(
+                "WKT elements are nested deeper than {MAX_DEPTH} levels"
+            ))?
+        }
+        self.skip_whitespace();
+        let keyword = self.literal();
+        if keyword.is_empty()
+            || !keyword
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            Error::invalid(format!("Expected WKT keyword at character {}", self.pos))?
+        }
+        self.skip_whitespace();
+        let close = match self.chars.get(self.pos) {
+            Some('[') => ']',
+            Some('(') => ')',
+            _ => Error::invalid(format!(
+                "Expected opening bracket after WKT keyword {keyword}"
+            ))?,
+        };
+        self.pos += 1;
+
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let c = *self
+                .chars
+                .get(self.pos)
+                .invalid_err("Unexpected end of WKT string")?;
+            if c == close && values.is_empty() {
+                self.pos += 1;
+                break;
+            }
+            values.push(self.value(depth)?);
+            self.skip_whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(c) if *c == close => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => Error::invalid(format!(
+                    "Expected separator or closing bracket at character {}",
+                    self.pos
+                ))?,
+            }
+        }
+
+        Ok(WktElement {
+            keyword: keyword.to_ascii_uppercase(),
+            values,
+        })
+    }
+
+    fn value(&mut self, depth: usize) -> Result<WktValue> {
+        if self.chars.get(self.pos) == Some(&'"') {
+            return self.text().map(WktValue::Text);
+        }
+        let start = self.pos;
+        let literal = self.literal();
+        self.skip_whitespace();
+        if matches!(self.chars.get(self.pos), Some('[') | Some('(')) {
+            self.pos = start;
+            self.element(depth + 1).map(WktValue::Element)
+        } else if literal.is_empty() {
+            Error::invalid(format!("Expected WKT value at character {}", self.pos))
+        } else {
+            Ok(WktValue::Literal(literal))
+        }
+    }
+
+    /// Reads a quoted string, two double quotes are an escaped double quote.
+    fn text(&mut self) -> Result<String> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let c = *self
+                .chars
+                .get(self.pos)
+                .invalid_err("Unterminated string in WKT")?;
+            self.pos += 1;
+            if c == '"' {
+                if self.chars.get(self.pos) == Some(&'"') {
+                    self.pos += 1;
+                } else {
+                    return Ok(text);
+                }
+            }
+            text.push(c);
+        }
+    }
+
+    /// Reads a keyword, number or enumeration value.
+    fn literal(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, '[' | ']' | '(' | ')' | ',' | '"'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_wkt1() {
+        let wkt = r#"PROJCS["WGS 84 / UTM zone 33N",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433],AUTHORITY["EPSG","4326"]],PROJECTION["Transverse_Mercator"],PARAMETER["central_meridian",15],UNIT["metre",1],AXIS["Easting",EAST],AXIS["Northing",NORTH],AUTHORITY["EPSG","32633"]]"#;
+        let crs = CoordinateReferenceSystem::parse(wkt).unwrap();
+        assert_eq!(crs.kind, "PROJCS");
+        assert_eq!(crs.name.as_deref(), Some("WGS 84 / UTM zone 33N"));
+        assert_eq!(
+            crs.authority,
+            Some((String::from("EPSG"), String::from("32633")))
+        );
+        assert_eq!(crs.epsg_code(), Some(32633));
+        assert_eq!(crs.wkt, wkt);
+    }
+
+    #[test]
+    fn parse_wkt2() {
+        let wkt = "GEOGCRS[\"WGS 84 \"\"quoted\"\"\",\n  DATUM[\"World Geodetic System 1984\",\n    ELLIPSOID[\"WGS 84\",6378137,298.257223563]],\n  CS[ellipsoidal,2],\n  ID[\"EPSG\",4326]]";
+        let crs = CoordinateReferenceSystem::parse(wkt).unwrap();
+        assert_eq!(crs.kind, "GEOGCRS");
+        assert_eq!(crs.name.as_deref(), Some("WGS 84 \"quoted\""));
+        assert_eq!(crs.epsg_code(), Some(4326));
+
+        let crs = CoordinateReferenceSystem::parse("LOCAL_CS[\"Site\"]").unwrap();
+        assert_eq!(crs.authority, None);
+        assert_eq!(crs.epsg_code(), None);
+    }
+
+    #[test]
+    fn parse_invalid_wkt() {
+        assert!(CoordinateReferenceSystem::parse("").is_err());
+        assert!(CoordinateReferenceSystem::parse("EPSG:4326").is_err());
+        assert!(CoordinateReferenceSystem::parse("GEOGCS[\"WGS 84\"").is_err());
+        assert!(CoordinateReferenceSystem::parse("GEOGCS[\"WGS 84\"]]").is_err());
+        assert!(CoordinateReferenceSystem::parse("GEOGCS[\"WGS 84]").is_err());
+        let nested = "A[".repeat(100) + &"]".repeat(100);
+        assert!(CoordinateReferenceSystem::parse(&nested).is_err());
+    }
+}
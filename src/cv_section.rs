@@ -1,8 +1,12 @@
 use crate::error::Converter;
+#[cfg(feature = "read")]
 use crate::error::WRONG_OFFSET;
+#[cfg(feature = "read")]
 use crate::Error;
 use crate::Result;
+#[cfg(feature = "read")]
 use std::io::Read;
+#[cfg(feature = "write")]
 use std::io::Write;
 
 #[derive(Debug)]
@@ -16,6 +20,7 @@ pub struct CompressedVectorSectionHeader {
 impl CompressedVectorSectionHeader {
     pub const SIZE: u64 = 32;
 
+    #[cfg(feature = "read")]
     pub fn read(reader: &mut dyn Read) -> Result<CompressedVectorSectionHeader> {
         let mut buffer = [0_u8; Self::SIZE as usize];
         reader
@@ -32,15 +37,16 @@ impl CompressedVectorSectionHeader {
         };
 
         if header.section_id != 1 {
-            Error::invalid("Section ID of the compressed vector section header is not 1")?
+            Error::malformed("Section ID of the compressed vector section header is not 1")?
         }
-        if header.section_length % 4 != 0 {
-            Error::invalid("Section length is not aligned and a multiple of four")?
+        if !header.section_length.is_multiple_of(4) {
+            Error::malformed("Section length is not aligned and a multiple of four")?
         }
 
         Ok(header)
     }
 
+    #[cfg(feature = "write")]
     pub fn write(&self, writer: &mut dyn Write) -> Result<()> {
         let mut buffer = [0_u8; Self::SIZE as usize];
         buffer[0] = self.section_id;
@@ -1,8 +1,42 @@
 use crate::error::Converter;
 use crate::Result;
+#[cfg(feature = "read")]
 use roxmltree::Node;
+use std::time::{Duration, SystemTime};
+
+/// Unix time of the GPS start epoch (00:00 UTC on January 6, 1980).
+const GPS_EPOCH_UNIX: f64 = 315964800.0;
+
+/// Unix times of all UTC days that started after a leap second was inserted since the GPS epoch.
+/// The difference between GPS time and UTC grows by one second with each entry.
+/// This table needs to be extended when new leap seconds are announced.
+const LEAP_SECONDS_UNIX: [f64; 18] = [
+    362793600.0,  // 1981-07-01
+    394329600.0,  // 1982-07-01
+    425865600.0,  // 1983-07-01
+    489024000.0,  // 1985-07-01
+    567993600.0,  // 1988-01-01
+    631152000.0,  // 1990-01-01
+    662688000.0,  // 1991-01-01
+    709948800.0,  // 1992-07-01
+    741484800.0,  // 1993-07-01
+    773020800.0,  // 1994-07-01
+    820454400.0,  // 1996-01-01
+    867715200.0,  // 1997-07-01
+    915148800.0,  // 1999-01-01
+    1136073600.0, // 2006-01-01
+    1230768000.0, // 2009-01-01
+    1341100800.0, // 2012-07-01
+    1435708800.0, // 2015-07-01
+    1483228800.0, // 2017-01-01
+];
 
 /// Represents a specific date and time used in E57 files.
+///
+/// E57 files store times as GPS time, which is not adjusted for leap seconds
+/// and is ahead of UTC by the number of leap seconds since the GPS epoch.
+/// Use the Unix time or `SystemTime` conversions to handle this correctly.
+/// With the `chrono` feature there are also conversions from and to `chrono::DateTime`.
 #[derive(Clone, Debug)]
 pub struct DateTime {
     /// Number of seconds since GPS start epoch (00:00 UTC on January 6, 1980).
@@ -12,6 +46,54 @@ pub struct DateTime {
 }
 
 impl DateTime {
+    /// Creates a date time from seconds since the Unix epoch (00:00 UTC on January 1, 1970).
+    /// Leap seconds between UTC and GPS time are taken into account.
+    pub fn from_unix_time(seconds: f64, atomic_reference: bool) -> Self {
+        let leap_seconds = LEAP_SECONDS_UNIX.iter().filter(|l| seconds >= **l).count();
+        Self {
+            gps_time: seconds - GPS_EPOCH_UNIX + leap_seconds as f64,
+            atomic_reference,
+        }
+    }
+
+    /// Returns the number of seconds since the Unix epoch (00:00 UTC on January 1, 1970).
+    /// Leap seconds between UTC and GPS time are taken into account.
+    pub fn unix_time(&self) -> f64 {
+        let leap_seconds = LEAP_SECONDS_UNIX
+            .iter()
+            .enumerate()
+            .filter(|(i, l)| self.gps_time >= **l - GPS_EPOCH_UNIX + (*i + 1) as f64)
+            .count();
+        self.gps_time + GPS_EPOCH_UNIX - leap_seconds as f64
+    }
+
+    /// Creates a date time from a system time.
+    pub fn from_system_time(time: SystemTime, atomic_reference: bool) -> Self {
+        let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        };
+        Self::from_unix_time(seconds, atomic_reference)
+    }
+
+    /// Converts the date time into a system time.
+    /// Fails if the GPS time is not finite or out of the range of the system time.
+    pub fn to_system_time(&self) -> Result<SystemTime> {
+        let seconds = self.unix_time();
+        let duration = Duration::try_from_secs_f64(seconds.abs())
+            .invalid_err(format!("GPS time {} cannot be represented", self.gps_time))?;
+        let time = if seconds >= 0.0 {
+            SystemTime::UNIX_EPOCH.checked_add(duration)
+        } else {
+            SystemTime::UNIX_EPOCH.checked_sub(duration)
+        };
+        time.invalid_err(format!(
+            "GPS time {} is out of range for system time",
+            self.gps_time
+        ))
+    }
+
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Option<Self>> {
         let gps_time_text = node
             .children()
@@ -40,6 +122,7 @@ impl DateTime {
         }))
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self, tag_name: &str) -> String {
         let mut xml = String::new();
         xml += &format!("<{tag_name} type=\"Structure\">\n");
@@ -55,3 +138,44 @@ impl DateTime {
         xml
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_time_conversion() {
+        // 2017-01-01 00:00:00 UTC, after the last leap second
+        let dt = DateTime::from_unix_time(1483228800.0, true);
+        assert_eq!(dt.gps_time, 1167264018.0);
+        assert!(dt.atomic_reference);
+        assert_eq!(dt.unix_time(), 1483228800.0);
+
+        // One second earlier was still 17 leap seconds
+        let dt = DateTime::from_unix_time(1483228799.5, false);
+        assert_eq!(dt.gps_time, 1167264016.5);
+        assert_eq!(dt.unix_time(), 1483228799.5);
+
+        // GPS epoch and times before have no leap seconds
+        assert_eq!(DateTime::from_unix_time(315964800.0, false).gps_time, 0.0);
+        assert_eq!(DateTime::from_unix_time(0.0, false).gps_time, -315964800.0);
+    }
+
+    #[test]
+    fn system_time_conversion() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1600000000);
+        let dt = DateTime::from_system_time(time, false);
+        assert_eq!(dt.gps_time, 1600000000.0 - 315964800.0 + 18.0);
+        assert_eq!(dt.to_system_time().unwrap(), time);
+
+        let before = SystemTime::UNIX_EPOCH - Duration::from_secs(10);
+        let dt = DateTime::from_system_time(before, false);
+        assert_eq!(dt.to_system_time().unwrap(), before);
+
+        let dt = DateTime {
+            gps_time: f64::NAN,
+            atomic_reference: false,
+        };
+        assert!(dt.to_system_time().is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{Error, RawValues, Record, RecordName, Result};
+use std::collections::HashMap;
+
+/// Defines which points are dropped as duplicates when writing a point cloud.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub enum DuplicateRemoval {
+    /// Keep all points.
+    #[default]
+    Disabled,
+    /// Drop points that have exactly the same values as the previously added point.
+    Consecutive,
+    /// Drop points with valid Cartesian coordinates that are within the given distance
+    /// to any previously kept point. Only the coordinates are compared, all other values are ignored.
+    /// The kept points are stored in a hash grid, so the memory usage grows with the number of points.
+    /// The distance must be a positive number and uses the same unit as the added points.
+    Tolerance(f64),
+}
+
+/// Stateful helper to decide which added points are duplicates.
+pub(crate) struct DuplicateFilter {
+    mode: DuplicateRemoval,
+    xyz: [usize; 3],
+    state: Option<usize>,
+    previous: Option<RawValues>,
+    grid: HashMap<(i64, i64, i64), Vec<[f64; 3]>>,
+    dropped: u64,
+}
+
+impl DuplicateFilter {
+    /// The tolerance is expected to be already scaled to the unit of the stored coordinates.
+    pub fn new(mode: DuplicateRemoval, prototype: &[Record]) -> Result<Self> {
+        let find = |name: RecordName| prototype.iter().position(|p| p.name == name);
+        let xyz = [
+            find(RecordName::CartesianX),
+            find(RecordName::CartesianY),
+            find(RecordName::CartesianZ),
+        ];
+        let xyz = match (&mode, xyz) {
+            (_, [Some(x), Some(y), Some(z)]) => [x, y, z],
+            (DuplicateRemoval::Tolerance(_), _) => {
+                Error::invalid("Duplicate removal with a tolerance requires Cartesian coordinates")?
+            }
+            _ => [0; 3],
+        };
+        if let DuplicateRemoval::Tolerance(t) = mode {
+            if !t.is_finite() || t <= 0.0 {
+                Error::invalid(format!(
+                    "Duplicate tolerance must be a positive number, but is {t}"
+                ))?
+            }
+        }
+        Ok(Self {
+            mode,
+            xyz,
+            state: find(RecordName::CartesianInvalidState),
+            previous: None,
+            grid: HashMap::new(),
+            dropped: 0,
+        })
+    }
+
+    /// Returns true if the point should be dropped, kept points are remembered.
+    /// Must be called exactly once for every added point in order.
+    pub fn is_duplicate(&mut self, prototype: &[Record], values: &RawValues) -> Result<bool> {
+        let duplicate = match self.mode {
+            DuplicateRemoval::Disabled => false,
+            DuplicateRemoval::Consecutive => {
+                if self.previous.as_ref() == Some(values) {
+                    true
+                } else {
+                    self.previous = Some(values.clone());
+                    false
+                }
+            }
+            DuplicateRemoval::Tolerance(tolerance) => {
+                self.is_near_duplicate(prototype, values, tolerance)?
+            }
+        };
+        if duplicate {
+            self.dropped += 1;
+        }
+        Ok(duplicate)
+    }
+
+    /// Number of points that were dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn is_near_duplicate(
+        &mut self,
+        prototype: &[Record],
+        values: &RawValues,
+        tolerance: f64,
+    ) -> Result<bool> {
+        if let Some(i) = self.state {
+            if values[i].to_i64(&prototype[i].data_type)? != 0 {
+                return Ok(false);
+            }
+        }
+        let mut p = [0.0; 3];
+        for (c, i) in p.iter_mut().zip(self.xyz) {
+            *c = values[i].to_f64(&prototype[i].data_type)?;
+        }
+        let cell = |v: f64| (v / tolerance).floor() as i64;
+        let key = (cell(p[0]), cell(p[1]), cell(p[2]));
+        let squared = tolerance * tolerance;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    // Cells are saturated for tiny tolerances, so the neighbors must saturate as well
+                    let neighbor = (
+                        key.0.saturating_add(dx),
+                        key.1.saturating_add(dy),
+                        key.2.saturating_add(dz),
+                    );
+                    let Some(points) = self.grid.get(&neighbor) else {
+                        continue;
+                    };
+                    let close = points.iter().any(|q| {
+                        let d = [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+                        d[0] * d[0] + d[1] * d[1] + d[2] * d[2] <= squared
+                    });
+                    if close {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        self.grid.entry(key).or_default().push(p);
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RecordValue;
+
+    fn point(x: f64, y: f64, z: f64) -> RawValues {
+        vec![
+            RecordValue::Double(x),
+            RecordValue::Double(y),
+            RecordValue::Double(z),
+        ]
+    }
+
+    #[test]
+    fn consecutive() {
+        let prototype = [
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mut filter = DuplicateFilter::new(DuplicateRemoval::Consecutive, &prototype).unwrap();
+        let points = [
+            point(1.0, 2.0, 3.0),
+            point(1.0, 2.0, 3.0),
+            point(0.0, 0.0, 0.0),
+        ];
+        let dropped = points
+            .iter()
+            .map(|p| filter.is_duplicate(&prototype, p).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(dropped, [false, true, false]);
+        assert!(!filter.is_duplicate(&prototype, &points[0]).unwrap());
+        assert_eq!(filter.dropped(), 1);
+    }
+
+    #[test]
+    fn tolerance() {
+        let prototype = [
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mode = DuplicateRemoval::Tolerance(0.1);
+        let mut filter = DuplicateFilter::new(mode, &prototype).unwrap();
+        let points = [
+            point(1.0, 2.0, 3.0),
+            point(0.0, 0.0, 0.0),
+            point(1.05, 1.95, 3.0),
+            point(0.0, 0.0, 0.11),
+            point(-0.01, 0.0, 0.0),
+        ];
+        let dropped = points
+            .iter()
+            .map(|p| filter.is_duplicate(&prototype, p).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(dropped, [false, false, true, false, true]);
+        assert_eq!(filter.dropped(), 2);
+
+        // Coordinates far outside of the cell range must not overflow
+        let mode = DuplicateRemoval::Tolerance(1e-20);
+        let mut filter = DuplicateFilter::new(mode, &prototype).unwrap();
+        assert!(!filter
+            .is_duplicate(&prototype, &point(1e10, -1e10, 0.0))
+            .unwrap());
+        assert!(filter
+            .is_duplicate(&prototype, &point(1e10, -1e10, 0.0))
+            .unwrap());
+
+        assert!(DuplicateFilter::new(DuplicateRemoval::Tolerance(0.0), &prototype).is_err());
+        assert!(DuplicateFilter::new(DuplicateRemoval::Tolerance(0.1), &[]).is_err());
+        assert!(DuplicateFilter::new(DuplicateRemoval::Consecutive, &[]).is_ok());
+    }
+}
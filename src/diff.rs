@@ -0,0 +1,502 @@
+use crate::sampling::Sampler;
+use crate::{E57Reader, Error, Extension, PointCloud, RecordName, Result, Subsampling, Transform};
+use std::fmt::Debug;
+use std::io::{Read, Seek};
+
+/// Options to configure how two E57 files are compared by [`diff()`].
+///
+/// Use the default options to compare only the metadata of the files
+/// and then modify the fields you are interested in.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DiffOptions {
+    /// Defines which points of each point cloud are compared.
+    /// The same points are selected in both files, so the subsampling mode
+    /// must select points by their index to get meaningful results.
+    /// Default value is None, meaning the point data is not compared at all.
+    pub points: Option<Subsampling>,
+    /// Maximum absolute difference of two values to be considered equal.
+    /// Used for the point data as well as for the bounds and poses in the point cloud
+    /// and image descriptors.
+    /// Default value is 0.0, meaning the values must be exactly equal.
+    pub tolerance: f64,
+    /// Maximum number of differing point values that are stored in the report of a point cloud.
+    /// All differing points are still counted when this limit is reached.
+    /// Default value is 100.
+    pub max_point_differences: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            points: None,
+            tolerance: 0.0,
+            max_point_differences: 100,
+        }
+    }
+}
+
+/// Single metadata value that differs between two files.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Difference {
+    /// Path of the value in the XML tree of the left file, for example `/data3D/0/name`.
+    /// Point clouds that only exist in the right file use their index in the right file.
+    pub path: String,
+    /// Debug representation of the value in the left file.
+    pub left: String,
+    /// Debug representation of the value in the right file.
+    pub right: String,
+}
+
+/// Single point value that differs between two point clouds.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct PointDifference {
+    /// Index of the point in the point cloud.
+    pub index: u64,
+    /// Name of the record with the differing value.
+    pub record: RecordName,
+    /// Value in the left point cloud, with scaled integers already converted.
+    pub left: f64,
+    /// Value in the right point cloud, with scaled integers already converted.
+    pub right: f64,
+}
+
+/// Differences between two point clouds with the same GUID or position.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct PointCloudDiff {
+    /// Index of the point cloud in the left file.
+    pub left: usize,
+    /// Index of the point cloud in the right file.
+    pub right: usize,
+    /// Differences in the point cloud descriptors, including the prototypes.
+    pub descriptor: Vec<Difference>,
+    /// Number of points that were compared.
+    pub compared_points: u64,
+    /// Number of compared points with at least one differing value.
+    pub differing_points: u64,
+    /// First differing point values, limited by [`DiffOptions::max_point_differences`].
+    pub points: Vec<PointDifference>,
+}
+
+impl PointCloudDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.descriptor.is_empty() && self.differing_points == 0
+    }
+}
+
+/// Structured report with all differences found between two E57 files.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct FileDiff {
+    /// Differences in the file headers.
+    /// The physical layout of the files, like lengths and offsets, is ignored.
+    pub header: Vec<Difference>,
+    /// Differences in the file metadata, extensions, images and the set of point clouds.
+    pub metadata: Vec<Difference>,
+    /// Differences of all point clouds found in both files.
+    pub pointclouds: Vec<PointCloudDiff>,
+}
+
+impl FileDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.metadata.is_empty()
+            && self.pointclouds.iter().all(PointCloudDiff::is_empty)
+    }
+}
+
+/// Compares two E57 files and returns a report with all differences.
+///
+/// Point clouds are matched by their GUID first, the remaining point clouds are matched by their position.
+/// Point clouds that are only found in one of the files are listed in the metadata differences.
+/// The point data is compared record by record for all records that exist in both prototypes,
+/// independent of their position and data type in the prototypes.
+/// This allows to verify that a conversion or merge of files was lossless.
+pub fn diff<L: Read + Seek, R: Read + Seek>(
+    left: &mut E57Reader<L>,
+    right: &mut E57Reader<R>,
+    options: &DiffOptions,
+) -> Result<FileDiff> {
+    if !options.tolerance.is_finite() || options.tolerance < 0.0 {
+        Error::invalid(format!(
+            "Diff tolerance must be a non-negative number, but is {}",
+            options.tolerance
+        ))?
+    }
+    if let Some(subsampling) = &options.points {
+        subsampling.validate()?;
+    }
+
+    let mut report = FileDiff::default();
+    let (lh, rh) = (left.header(), right.header());
+    let header = &mut report.header;
+    check(header, "/header/major", &lh.major, &rh.major);
+    check(header, "/header/minor", &lh.minor, &rh.minor);
+    check(header, "/header/pageSize", &lh.page_size, &rh.page_size);
+
+    let metadata = &mut report.metadata;
+    check(
+        metadata,
+        "/formatName",
+        &left.format_name(),
+        &right.format_name(),
+    );
+    check(metadata, "/guid", &left.guid(), &right.guid());
+    check(
+        metadata,
+        "/libraryVersion",
+        &left.library_version(),
+        &right.library_version(),
+    );
+    check(
+        metadata,
+        "/creationDateTime",
+        &left.creation(),
+        &right.creation(),
+    );
+    check(
+        metadata,
+        "/coordinateMetadata",
+        &left.coordinate_metadata(),
+        &right.coordinate_metadata(),
+    );
+    let (le, re) = (
+        sorted_extensions(left.extensions()),
+        sorted_extensions(right.extensions()),
+    );
+    check(metadata, "/extensions", &le, &re);
+    let (li, ri) = (left.images(), right.images());
+    check(metadata, "/images2D/length", &li.len(), &ri.len());
+    for (i, (l, r)) in li.iter().zip(&ri).enumerate() {
+        check(metadata, &format!("/images2D/{i}/guid"), &l.guid, &r.guid);
+        check(metadata, &format!("/images2D/{i}/name"), &l.name, &r.name);
+        check_pose(
+            metadata,
+            &format!("/images2D/{i}/pose"),
+            &l.transform,
+            &r.transform,
+            options.tolerance,
+        );
+    }
+
+    let (lp, rp) = (left.pointclouds(), right.pointclouds());
+    let pairs = match_pointclouds(&lp, &rp);
+    for (i, pc) in lp.iter().enumerate() {
+        if !pairs.iter().any(|(l, _)| *l == i) {
+            check(metadata, &format!("/data3D/{i}/guid"), &pc.guid, &"missing");
+        }
+    }
+    for (j, pc) in rp.iter().enumerate() {
+        if !pairs.iter().any(|(_, r)| *r == j) {
+            check(metadata, &format!("/data3D/{j}/guid"), &"missing", &pc.guid);
+        }
+    }
+
+    for (i, j) in pairs {
+        let mut pc_diff = PointCloudDiff {
+            left: i,
+            right: j,
+            descriptor: compare_descriptors(i, &lp[i], &rp[j], options.tolerance),
+            ..Default::default()
+        };
+        if let Some(subsampling) = &options.points {
+            compare_points(
+                left,
+                right,
+                (&lp[i], &rp[j]),
+                subsampling,
+                options,
+                &mut pc_diff,
+            )?;
+        }
+        report.pointclouds.push(pc_diff);
+    }
+    Ok(report)
+}
+
+/// Returns the index pairs of matching point clouds, sorted by the index in the left file.
+/// All point clouds are matched by their GUID first, only the remaining ones are matched by position.
+fn match_pointclouds(left: &[PointCloud], right: &[PointCloud]) -> Vec<(usize, usize)> {
+    let mut left_matched = vec![false; left.len()];
+    let mut right_matched = vec![false; right.len()];
+    let mut pairs = Vec::new();
+    for (i, pc) in left.iter().enumerate() {
+        let Some(guid) = &pc.guid else {
+            continue;
+        };
+        let found = right
+            .iter()
+            .enumerate()
+            .position(|(j, r)| !right_matched[j] && r.guid.as_ref() == Some(guid));
+        if let Some(j) = found {
+            left_matched[i] = true;
+            right_matched[j] = true;
+            pairs.push((i, j));
+        }
+    }
+    for i in 0..left.len().min(right.len()) {
+        if !left_matched[i] && !right_matched[i] {
+            pairs.push((i, i));
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+fn sorted_extensions(extensions: Vec<Extension>) -> Vec<(String, String)> {
+    let mut list: Vec<_> = extensions
+        .into_iter()
+        .map(|e| (e.namespace, e.url))
+        .collect();
+    list.sort();
+    list
+}
+
+/// Adds a difference to the list if the Debug representation of the values is not equal.
+fn check<V: Debug + ?Sized, W: Debug + ?Sized>(
+    diffs: &mut Vec<Difference>,
+    path: &str,
+    left: &V,
+    right: &W,
+) {
+    let (left, right) = (format!("{left:?}"), format!("{right:?}"));
+    if left != right {
+        diffs.push(Difference {
+            path: path.to_owned(),
+            left,
+            right,
+        });
+    }
+}
+
+/// Adds a difference to the list if the numbers are not equal within the tolerance.
+fn check_floats(
+    diffs: &mut Vec<Difference>,
+    path: &str,
+    left: &[Option<f64>],
+    right: &[Option<f64>],
+    tolerance: f64,
+) {
+    let equal = left.iter().zip(right).all(|pair| match pair {
+        (Some(l), Some(r)) => equal(*l, *r, tolerance),
+        (None, None) => true,
+        _ => false,
+    });
+    if !equal {
+        diffs.push(Difference {
+            path: path.to_owned(),
+            left: format!("{left:?}"),
+            right: format!("{right:?}"),
+        });
+    }
+}
+
+/// Adds a difference to the list if the quaternion and translation components
+/// of the poses are not equal within the tolerance.
+fn check_pose(
+    diffs: &mut Vec<Difference>,
+    path: &str,
+    left: &Option<Transform>,
+    right: &Option<Transform>,
+    tolerance: f64,
+) {
+    let components = |t: &Option<Transform>| match t {
+        Some(t) => [
+            t.rotation.w,
+            t.rotation.x,
+            t.rotation.y,
+            t.rotation.z,
+            t.translation.x,
+            t.translation.y,
+            t.translation.z,
+        ]
+        .map(Some),
+        None => [None; 7],
+    };
+    check_floats(
+        diffs,
+        path,
+        &components(left),
+        &components(right),
+        tolerance,
+    );
+}
+
+/// Two NaN values are considered equal, since they represent the same stored value.
+fn equal(left: f64, right: f64, tolerance: f64) -> bool {
+    (left - right).abs() <= tolerance || (left.is_nan() && right.is_nan())
+}
+
+fn compare_descriptors(
+    index: usize,
+    left: &PointCloud,
+    right: &PointCloud,
+    tolerance: f64,
+) -> Vec<Difference> {
+    let mut diffs = Vec::new();
+    let d = &mut diffs;
+    let path = |name: &str| format!("/data3D/{index}/{name}");
+    check(d, &path("guid"), &left.guid, &right.guid);
+    check(d, &path("name"), &left.name, &right.name);
+    check(
+        d,
+        &path("description"),
+        &left.description,
+        &right.description,
+    );
+    check(
+        d,
+        &path("sensorModel"),
+        &left.sensor_model,
+        &right.sensor_model,
+    );
+    check(
+        d,
+        &path("sensorVendor"),
+        &left.sensor_vendor,
+        &right.sensor_vendor,
+    );
+    check(
+        d,
+        &path("sensorSerialNumber"),
+        &left.sensor_serial,
+        &right.sensor_serial,
+    );
+    check(
+        d,
+        &path("acquisitionStart"),
+        &left.acquisition_start,
+        &right.acquisition_start,
+    );
+    check(
+        d,
+        &path("acquisitionEnd"),
+        &left.acquisition_end,
+        &right.acquisition_end,
+    );
+    check_pose(
+        d,
+        &path("pose"),
+        &left.transform,
+        &right.transform,
+        tolerance,
+    );
+    check(
+        d,
+        &path("points/recordCount"),
+        &left.records,
+        &right.records,
+    );
+    check(
+        d,
+        &path("points/prototype"),
+        &left.prototype,
+        &right.prototype,
+    );
+    check(
+        d,
+        &path("indexBounds"),
+        &left.index_bounds,
+        &right.index_bounds,
+    );
+    check(
+        d,
+        &path("intensityLimits"),
+        &left.intensity_limits,
+        &right.intensity_limits,
+    );
+    check(
+        d,
+        &path("colorLimits"),
+        &left.color_limits,
+        &right.color_limits,
+    );
+
+    let cartesian = |pc: &PointCloud| {
+        let b = pc.cartesian_bounds.clone().unwrap_or_default();
+        [b.x_min, b.x_max, b.y_min, b.y_max, b.z_min, b.z_max]
+    };
+    let (lc, rc) = (cartesian(left), cartesian(right));
+    check_floats(d, &path("cartesianBounds"), &lc, &rc, tolerance);
+    let spherical = |pc: &PointCloud| {
+        let b = pc.spherical_bounds.clone().unwrap_or_default();
+        [
+            b.range_min,
+            b.range_max,
+            b.elevation_min,
+            b.elevation_max,
+            b.azimuth_start,
+            b.azimuth_end,
+        ]
+    };
+    let (ls, rs) = (spherical(left), spherical(right));
+    check_floats(d, &path("sphericalBounds"), &ls, &rs, tolerance);
+    diffs
+}
+
+fn compare_points<L: Read + Seek, R: Read + Seek>(
+    left: &mut E57Reader<L>,
+    right: &mut E57Reader<R>,
+    (lpc, rpc): (&PointCloud, &PointCloud),
+    subsampling: &Subsampling,
+    options: &DiffOptions,
+    report: &mut PointCloudDiff,
+) -> Result<()> {
+    // Pairs of record indices in the left and right prototype with the same name
+    let records: Vec<(usize, usize)> = lpc
+        .prototype
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| {
+            let j = rpc.prototype.iter().position(|r| r.name == l.name)?;
+            Some((i, j))
+        })
+        .collect();
+
+    let count = lpc.records.min(rpc.records);
+    let mut sampler = Sampler::new(subsampling.clone(), count);
+    let mut left_reader = left.pointcloud_raw(lpc)?;
+    let mut right_reader = right.pointcloud_raw(rpc)?;
+    let mut lv = Vec::with_capacity(lpc.prototype.len());
+    let mut rv = Vec::with_capacity(rpc.prototype.len());
+    for index in 0..count {
+        let (Some(l), Some(r)) = (
+            left_reader.next_into(&mut lv),
+            right_reader.next_into(&mut rv),
+        ) else {
+            break;
+        };
+        l?;
+        r?;
+        if !sampler.keep(index) {
+            continue;
+        }
+        report.compared_points += 1;
+        let mut differs = false;
+        for &(i, j) in &records {
+            let lvalue = lv[i].to_f64(&lpc.prototype[i].data_type)?;
+            let rvalue = rv[j].to_f64(&rpc.prototype[j].data_type)?;
+            if equal(lvalue, rvalue, options.tolerance) {
+                continue;
+            }
+            differs = true;
+            if report.points.len() < options.max_point_differences {
+                report.points.push(PointDifference {
+                    index,
+                    record: lpc.prototype[i].name.clone(),
+                    left: lvalue,
+                    right: rvalue,
+                });
+            }
+        }
+        if differs {
+            report.differing_points += 1;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use crate::cv_section::CompressedVectorSectionHeader;
+use crate::{Blob, Header, Image, ImageBlob, PointCloud, Projection, Result};
+
+/// Creates a human readable description of the binary structure of an E57 file.
+/// The supplied closure reads the compressed vector section header at the given physical offset.
+pub(crate) fn dump_structure<F>(
+    header: &Header,
+    guid: &str,
+    pointclouds: &[PointCloud],
+    images: &[Image],
+    mut section_header: F,
+) -> Result<String>
+where
+    F: FnMut(u64) -> Result<CompressedVectorSectionHeader>,
+{
+    let mut out = format!("E57 file {guid}\n");
+    out += &format!("  Format version: {}.{}\n", header.major, header.minor);
+    out += &format!("  Physical length: {} bytes\n", header.phys_length);
+    out += &format!("  Page size: {} bytes\n", header.page_size);
+    out += &format!(
+        "  XML section: offset {}, length {} bytes\n",
+        header.phys_xml_offset, header.xml_length
+    );
+
+    for (index, pc) in pointclouds.iter().enumerate() {
+        out += &format!("Point cloud {index}:{}\n", names(&pc.guid, &pc.name));
+        let section = section_header(pc.file_offset)?;
+        out += &format!("  {}\n", section_line(pc.file_offset, &section));
+        out += &format!("  Points: {}\n", pc.records);
+        let bits: usize = pc.prototype.iter().map(|r| r.data_type.bit_size()).sum();
+        out += &format!("  Records: {}, {bits} bits per point\n", pc.prototype.len());
+        for (i, record) in pc.prototype.iter().enumerate() {
+            let name = match record.name.namespace() {
+                Some(ns) => format!("{ns}:{}", record.name.tag_name()),
+                None => record.name.tag_name().to_owned(),
+            };
+            out += &format!(
+                "    {i}: {name}, {:?}, {} bits\n",
+                record.data_type,
+                record.data_type.bit_size()
+            );
+        }
+        if let Some(grouping) = &pc.line_grouping {
+            out += &format!(
+                "  Line groups: {} groups by {}\n",
+                grouping.records, grouping.id_element_name
+            );
+            let section = section_header(grouping.file_offset)?;
+            out += &format!("    {}\n", section_line(grouping.file_offset, &section));
+        }
+    }
+
+    for (index, img) in images.iter().enumerate() {
+        out += &format!("Image {index}:{}\n", names(&img.guid, &img.name));
+        if let Some(pc_guid) = &img.pointcloud_guid {
+            out += &format!("  Point cloud: {pc_guid}\n");
+        }
+        if let Some(vr) = &img.visual_reference {
+            let (w, h) = (vr.properties.width, vr.properties.height);
+            out += &image_lines("Visual reference", w, h, &vr.blob, &vr.mask);
+        }
+        match &img.projection {
+            Some(Projection::Pinhole(p)) => {
+                let (w, h) = (p.properties.width, p.properties.height);
+                out += &image_lines("Pinhole projection", w, h, &p.blob, &p.mask);
+            }
+            Some(Projection::Spherical(p)) => {
+                let (w, h) = (p.properties.width, p.properties.height);
+                out += &image_lines("Spherical projection", w, h, &p.blob, &p.mask);
+            }
+            Some(Projection::Cylindrical(p)) => {
+                let (w, h) = (p.properties.width, p.properties.height);
+                out += &image_lines("Cylindrical projection", w, h, &p.blob, &p.mask);
+            }
+            None => {}
+        }
+    }
+
+    Ok(out)
+}
+
+fn names(guid: &Option<String>, name: &Option<String>) -> String {
+    let mut parts = Vec::new();
+    if let Some(guid) = guid {
+        parts.push(format!(" guid {guid}"));
+    }
+    if let Some(name) = name {
+        parts.push(format!(" name \"{name}\""));
+    }
+    parts.join(",")
+}
+
+fn section_line(offset: u64, header: &CompressedVectorSectionHeader) -> String {
+    format!(
+        "Compressed vector section: offset {offset}, length {} bytes, data offset {}, index offset {}",
+        header.section_length, header.data_offset, header.index_offset
+    )
+}
+
+fn image_lines(
+    kind: &str,
+    width: u32,
+    height: u32,
+    blob: &ImageBlob,
+    mask: &Option<Blob>,
+) -> String {
+    let mut out = format!(
+        "  {kind}: {:?}, {width}x{height} pixels, {}\n",
+        blob.format,
+        blob_line(&blob.data)
+    );
+    if let Some(mask) = mask {
+        out += &format!("    Mask: {}\n", blob_line(mask));
+    }
+    out
+}
+
+fn blob_line(blob: &Blob) -> String {
+    format!("blob offset {}, length {} bytes", blob.offset, blob.length)
+}
@@ -1,17 +1,33 @@
+use crate::cv_section::CompressedVectorSectionHeader;
+use crate::dump::dump_structure;
 use crate::error::Converter;
 use crate::paged_reader::PagedReader;
 use crate::root::root_from_document;
 use crate::root::Root;
+use crate::xml;
 use crate::Blob;
+use crate::CoordinateReferenceSystem;
 use crate::DateTime;
 use crate::Error;
+use crate::ErrorKind;
+use crate::Exporter;
 use crate::Extension;
 use crate::Header;
 use crate::Image;
+use crate::LineGroup;
+use crate::PacketReader;
 use crate::PointCloud;
+use crate::PointCloudPreview;
 use crate::PointCloudReaderRaw;
 use crate::PointCloudReaderSimple;
+use crate::PointCloudStatistics;
+use crate::PoseCorrection;
+use crate::ReaderOptions;
+use crate::ResourceLimits;
 use crate::Result;
+use crate::SpillPolicy;
+use crate::StreamBuffer;
+use crate::Subsampling;
 use roxmltree::Document;
 use std::fs::File;
 use std::io::BufReader;
@@ -20,8 +36,6 @@ use std::io::Seek;
 use std::io::Write;
 use std::path::Path;
 
-const MAX_XML_SIZE: usize = 1024 * 1024 * 10;
-
 /// Main interface for reading E57 files.
 pub struct E57Reader<T: Read + Seek> {
     reader: PagedReader<T>,
@@ -31,30 +45,137 @@ pub struct E57Reader<T: Read + Seek> {
     pointclouds: Vec<PointCloud>,
     images: Vec<Image>,
     extensions: Vec<Extension>,
+    pose_corrections: Vec<PoseCorrection>,
+    unit_scale: f64,
+    limits: ResourceLimits,
 }
 
 impl<T: Read + Seek> E57Reader<T> {
     /// Creates a new E57 instance for from a reader.
-    pub fn new(mut reader: T) -> Result<Self> {
+    pub fn new(reader: T) -> Result<Self> {
+        Self::with_options(reader, ReaderOptions::default())
+    }
+
+    /// Creates a new E57 instance from a reader with custom options.
+    pub fn with_options(mut reader: T, options: ReaderOptions) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("e57_open").entered();
+
+        let unit_scale = options.unit_scale;
+        if !unit_scale.is_finite() || unit_scale <= 0.0 {
+            Error::invalid(format!(
+                "Unit scale factor must be a positive finite number, but is {unit_scale}"
+            ))?
+        }
+        if options.pages_per_read == 0 {
+            Error::invalid("Number of pages per read must be at least one")?
+        }
+
         // Read, parse and validate E57 header
         let header = Header::read(&mut reader)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            page_size = header.page_size,
+            phys_length = header.phys_length,
+            xml_offset = header.phys_xml_offset,
+            xml_length = header.xml_length,
+            "Read file header"
+        );
 
         // Set up paged reader for the CRC page layer
         let mut reader = PagedReader::new(reader, header.page_size)
             .read_err("Failed creating paged CRC reader")?;
+        reader.set_verify_checksums(options.verify_checksums);
+        reader.set_pages_per_read(options.pages_per_read);
 
         // Read and parse XML data
         let xml_raw = Self::extract_xml(
             &mut reader,
             header.phys_xml_offset,
-            header.xml_length as usize,
+            header.xml_length,
+            options.limits.max_xml_size,
         )?;
         let xml = String::from_utf8(xml_raw).read_err("Failed to parse XML as UTF8")?;
-        let document = Document::parse(&xml).invalid_err("Failed to parse XML data")?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(bytes = xml.len(), "Read XML section");
+        let document = Document::parse(&xml)
+            .invalid_err("Failed to parse XML data")
+            .map_err(|e| e.with_kind(ErrorKind::Xml))?;
         let root = root_from_document(&document)?;
-        let pointclouds = PointCloud::vec_from_document(&document)?;
-        let images = Image::vec_from_document(&document)?;
+        let mut pointclouds = PointCloud::vec_from_document(&document, &options.record_aliases)?;
+        let mut images = Image::vec_from_document(&document)?;
         let extensions = Extension::vec_from_document(&document);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            pointclouds = pointclouds.len(),
+            images = images.len(),
+            extensions = extensions.len(),
+            "Parsed XML section"
+        );
+
+        // Enforce prototype size limit early before any binary data is read
+        let max_prototype_len = options.limits.max_prototype_len;
+        for (i, pc) in pointclouds.iter().enumerate() {
+            if pc.prototype.len() > max_prototype_len {
+                Error::limit_exceeded(format!(
+                    "Prototype of point cloud {i} with {} records exceeds the limit of {max_prototype_len} records",
+                    pc.prototype.len()
+                ))?
+            }
+        }
+
+        // Bounds of float records are only checked on request
+        if options.strict_float_bounds {
+            for (i, pc) in pointclouds.iter().enumerate() {
+                for (j, record) in pc.prototype.iter().enumerate() {
+                    let context = format!("of record {j} in the prototype of point cloud {i}");
+                    record.data_type.check_float_bounds(&context)?;
+                }
+            }
+        }
+
+        // Validate and correct pose quaternions
+        let mut pose_corrections = Vec::new();
+        let poses = pointclouds
+            .iter_mut()
+            .enumerate()
+            .map(|(i, pc)| (format!("/data3D/{i}/pose"), &mut pc.transform));
+        let image_poses = images
+            .iter_mut()
+            .enumerate()
+            .map(|(i, img)| (format!("/images2D/{i}/pose"), &mut img.transform));
+        for (path, transform) in poses.chain(image_poses) {
+            if let Some(transform) = transform {
+                let original = transform.rotation.clone();
+                if let Some(action) = transform.rotation.validate(options.quaternions)? {
+                    pose_corrections.push(PoseCorrection {
+                        path,
+                        original,
+                        action,
+                    });
+                }
+            }
+        }
+
+        // Apply unit scale to all lengths in the descriptors
+        if unit_scale != 1.0 {
+            for pc in &mut pointclouds {
+                if let Some(b) = &mut pc.cartesian_bounds {
+                    b.scale(unit_scale);
+                }
+                if let Some(b) = &mut pc.spherical_bounds {
+                    b.scale(unit_scale);
+                }
+                if let Some(t) = &mut pc.transform {
+                    t.translation.scale(unit_scale);
+                }
+            }
+            for img in &mut images {
+                if let Some(t) = &mut img.transform {
+                    t.translation.scale(unit_scale);
+                }
+            }
+        }
 
         Ok(Self {
             reader,
@@ -64,6 +185,9 @@ impl<T: Read + Seek> E57Reader<T> {
             pointclouds,
             images,
             extensions,
+            pose_corrections,
+            unit_scale,
+            limits: options.limits,
         })
     }
 
@@ -77,6 +201,34 @@ impl<T: Read + Seek> E57Reader<T> {
         &self.xml
     }
 
+    /// Returns the XML section of the E57 file formatted with indentation and one element per line.
+    /// This is intended for debugging and comparing files, use [`E57Reader::xml()`] for the original XML.
+    pub fn xml_pretty(&self) -> Result<String> {
+        xml::pretty_print(&self.xml)
+    }
+
+    /// Returns a human readable overview of the file structure for debugging.
+    ///
+    /// The overview lists the binary file header, the XML section, all point clouds with
+    /// their compressed vector sections and record layouts and all images with their blobs.
+    /// Offsets are physical offsets in bytes relative to the start of the file.
+    /// The format of the text is not stable and should not be parsed.
+    pub fn structure(&mut self) -> Result<String> {
+        let reader = &mut self.reader;
+        dump_structure(
+            &self.header,
+            &self.root.guid,
+            &self.pointclouds,
+            &self.images,
+            |offset| {
+                reader
+                    .seek_physical(offset)
+                    .read_err("Cannot seek to compressed vector header")?;
+                CompressedVectorSectionHeader::read(reader)
+            },
+        )
+    }
+
     /// Returns format name stored in the XML section.
     pub fn format_name(&self) -> &str {
         &self.root.format
@@ -92,6 +244,56 @@ impl<T: Read + Seek> E57Reader<T> {
         self.root.library_version.as_deref()
     }
 
+    /// Returns the unchanged XML elements of extensions that are direct children of the root element.
+    ///
+    /// Together with the custom XML of point clouds and images and the extensions of the file,
+    /// this allows to preserve vendor specific metadata when rewriting a file.
+    /// See also [`E57Writer::add_custom_xml()`](crate::E57Writer::add_custom_xml).
+    pub fn custom_xml(&self) -> &[String] {
+        &self.root.custom_xml
+    }
+
+    /// Returns the XML attributes of extensions on the root element, for example `ven:id="42"`.
+    ///
+    /// See also [`E57Writer::add_custom_attribute()`](crate::E57Writer::add_custom_attribute).
+    pub fn custom_attributes(&self) -> &[String] {
+        &self.root.custom_attributes
+    }
+
+    /// Returns the unit scale factor applied to all lengths of this reader.
+    #[cfg(feature = "write")]
+    pub(crate) fn unit_scale(&self) -> f64 {
+        self.unit_scale
+    }
+
+    /// Exports all point clouds of the file with the supplied exporter.
+    ///
+    /// The exporter decides which point clouds are exported and how their points are normalized.
+    /// See [`Exporter`] for details.
+    /// Returns the number of points passed to the exporter.
+    pub fn export(&mut self, exporter: &mut dyn Exporter) -> Result<u64> {
+        let mut count = 0;
+        for pc in self.pointclouds() {
+            let Some(options) = exporter.begin_pointcloud(&pc)? else {
+                continue;
+            };
+            let mut iter = self.pointcloud_simple(&pc)?;
+            iter.spherical_to_cartesian(options.spherical_to_cartesian);
+            iter.cartesian_to_spherical(options.cartesian_to_spherical);
+            iter.intensity_to_color(options.intensity_to_color);
+            iter.use_intensity_limits(options.use_intensity_limits);
+            iter.use_color_limits(options.use_color_limits);
+            iter.use_range_limits(options.use_range_limits);
+            iter.apply_pose(options.apply_pose);
+            for point in iter {
+                exporter.add_point(&point?)?;
+                count += 1;
+            }
+        }
+        exporter.finish()?;
+        Ok(count)
+    }
+
     /// Returns a list of all extensions defined in this file.
     pub fn extensions(&self) -> Vec<Extension> {
         self.extensions.clone()
@@ -102,19 +304,107 @@ impl<T: Read + Seek> E57Reader<T> {
         self.pointclouds.clone()
     }
 
+    /// Returns the descriptor of the point cloud with the given GUID.
+    /// The descriptor can be used directly to open any of the point cloud readers.
+    pub fn pointcloud_by_guid(&self, guid: &str) -> Option<PointCloud> {
+        self.pointclouds
+            .iter()
+            .find(|pc| pc.guid.as_deref() == Some(guid))
+            .cloned()
+    }
+
+    /// Returns the descriptor of the first point cloud with the given name.
+    /// Names are optional and not necessarily unique, prefer GUIDs to identify point clouds.
+    pub fn pointcloud_by_name(&self, name: &str) -> Option<PointCloud> {
+        self.pointclouds
+            .iter()
+            .find(|pc| pc.name.as_deref() == Some(name))
+            .cloned()
+    }
+
     /// Returns an iterator for reading point cloud data.
     /// The data provided by this interface is already normalized for convenience.
     /// There is also a raw iterator for advanced use-cases that require direct access.
-    pub fn pointcloud_simple(&mut self, pc: &PointCloud) -> Result<PointCloudReaderSimple<T>> {
-        PointCloudReaderSimple::new(pc, &mut self.reader)
+    pub fn pointcloud_simple(&mut self, pc: &PointCloud) -> Result<PointCloudReaderSimple<'_, T>> {
+        let mut reader = PointCloudReaderSimple::new(pc, &mut self.reader, self.limits)?;
+        reader.set_unit_scale(self.unit_scale);
+        Ok(reader)
+    }
+
+    /// Creates a small preview of a point cloud with at most the given number of points.
+    ///
+    /// The points are evenly subsampled while streaming through the point cloud,
+    /// so the memory usage only depends on the requested number of points.
+    /// The pose is applied, spherical coordinates are converted to Cartesian coordinates and
+    /// intensities are converted to gray colors if the point cloud has no color.
+    /// Points without valid Cartesian coordinates are skipped.
+    pub fn pointcloud_preview(
+        &mut self,
+        pc: &PointCloud,
+        max_points: u64,
+    ) -> Result<PointCloudPreview> {
+        let color = pc.has_color() || pc.has_intensity();
+        let capacity = max_points.min(pc.records) as usize;
+        let mut iter = self.pointcloud_simple(pc)?;
+        iter.subsampling(Subsampling::TargetCount(max_points))?;
+        PointCloudPreview::collect(iter.single_precision(), capacity, color)
     }
 
     /// Returns an iterator for reading raw low level point cloud data.
     /// This provides access to the original values stored in the E57 file.
     /// This interface is only recommended for advanced use-cases.
     /// In most scenarios the simple iterator is the better choice.
-    pub fn pointcloud_raw(&mut self, pc: &PointCloud) -> Result<PointCloudReaderRaw<T>> {
-        PointCloudReaderRaw::new(pc, &mut self.reader)
+    pub fn pointcloud_raw(&mut self, pc: &PointCloud) -> Result<PointCloudReaderRaw<'_, T>> {
+        PointCloudReaderRaw::new(pc, &mut self.reader, self.limits)
+    }
+
+    /// Returns an iterator for reading the raw packets of the binary section of a point cloud.
+    /// This low level interface is only recommended for custom decoders or forensic tools.
+    pub fn pointcloud_packets(&mut self, pc: &PointCloud) -> Result<PacketReader<'_, T>> {
+        PacketReader::new(pc, &mut self.reader, self.limits)
+    }
+
+    /// Scans all points of a point cloud to calculate statistics about its content.
+    /// Only the byte streams of the required records are decoded and no points are created,
+    /// which makes this faster than iterating over all points manually.
+    /// Byte streams of other records are skipped, but pages they share with required
+    /// byte streams still need to be read and checked.
+    pub fn pointcloud_statistics(&mut self, pc: &PointCloud) -> Result<PointCloudStatistics> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("pointcloud_statistics", offset = pc.file_offset).entered();
+        let mut stats = PointCloudStatistics::calculate(pc, &mut self.reader, self.limits)?;
+        if let Some(b) = &mut stats.cartesian_bounds {
+            b.scale(self.unit_scale);
+        }
+        if let Some(b) = &mut stats.spherical_bounds {
+            b.scale(self.unit_scale);
+        }
+        Ok(stats)
+    }
+
+    /// Reads all line groups of a point cloud.
+    /// Returns an error if the point cloud has no line grouping.
+    pub fn line_groups(&mut self, pc: &PointCloud) -> Result<Vec<LineGroup>> {
+        let grouping = pc
+            .line_grouping
+            .as_ref()
+            .invalid_err("The point cloud has no line grouping")?;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("line_groups", offset = grouping.file_offset).entered();
+        let mut groups = grouping.read_groups(&mut self.reader, self.limits)?;
+        if self.unit_scale != 1.0 {
+            for g in &mut groups {
+                g.scale(self.unit_scale);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Returns a list of all pose quaternions that were corrected while opening the file.
+    /// The list is empty if all quaternions were valid or if the raw handling mode was selected.
+    pub fn pose_corrections(&self) -> &[PoseCorrection] {
+        &self.pose_corrections
     }
 
     /// Returns a list of all image descriptors in the file.
@@ -128,6 +418,44 @@ impl<T: Read + Seek> E57Reader<T> {
         blob.read(&mut self.reader, writer)
     }
 
+    /// Returns a reader for the data of a blob, used to stream blobs into other files.
+    #[cfg(feature = "write")]
+    pub(crate) fn blob_reader(&mut self, blob: &Blob) -> Result<impl Read + '_> {
+        blob.open(&mut self.reader)
+    }
+
+    /// Looks up a blob referenced by custom XML elements of extensions.
+    ///
+    /// The elements are searched recursively for a blob element with the given tag name,
+    /// including the namespace prefix used by this file, for example `traj:data`.
+    /// This works with the custom XML of the root element, point clouds and images.
+    /// Returns the first matching blob or `None` if there is no such element.
+    /// Returns an error if the found element is not a valid blob.
+    /// Use [`E57Reader::blob()`] to read the data of the found blob.
+    pub fn custom_blob(&self, custom_xml: &[String], tag_name: &str) -> Result<Option<Blob>> {
+        let (prefix, name) = tag_name
+            .split_once(':')
+            .invalid_err(format!("Tag name '{tag_name}' has no namespace prefix"))?;
+        let Some(ext) = self.extensions.iter().find(|e| e.namespace == prefix) else {
+            return Ok(None);
+        };
+        let mut namespaces = String::new();
+        for ext in &self.extensions {
+            namespaces += &format!(" xmlns:{}=\"{}\"", ext.namespace, ext.url);
+        }
+        for xml in custom_xml {
+            let wrapped = format!("<e57Root{namespaces}>{xml}</e57Root>");
+            let document = Document::parse(&wrapped).invalid_err("Failed to parse custom XML")?;
+            let node = document
+                .descendants()
+                .find(|n| n.has_tag_name((ext.url.as_str(), name)));
+            if let Some(node) = node {
+                return Ok(Some(Blob::from_node(&node)?));
+            }
+        }
+        Ok(None)
+    }
+
     /// Returns the optional creation date and time of the file.
     pub fn creation(&self) -> Option<DateTime> {
         self.root.creation.clone()
@@ -144,6 +472,29 @@ impl<T: Read + Seek> E57Reader<T> {
         self.root.coordinate_metadata.as_deref()
     }
 
+    /// Returns the coordinate reference system parsed from the optional coordinate metadata.
+    /// Empty metadata is treated like missing metadata.
+    /// Fails if the metadata is not a valid WKT string.
+    pub fn coordinate_reference_system(&self) -> Result<Option<CoordinateReferenceSystem>> {
+        self.root
+            .coordinate_metadata
+            .as_deref()
+            .filter(|m| !m.trim().is_empty())
+            .map(CoordinateReferenceSystem::parse)
+            .transpose()
+    }
+
+    /// Verifies the CRC checksums of all pages of the file.
+    ///
+    /// This reads the whole file and ignores the checksum option of the reader.
+    /// It can be used to reject corrupt files before doing any further processing.
+    /// Returns the number of verified pages.
+    pub fn verify_checksums(&mut self) -> Result<u64> {
+        self.reader
+            .verify_all_pages()
+            .read_err("Failed to verify page checksums")
+    }
+
     /// Iterate over an reader to check an E57 file for CRC errors.
     ///
     /// This standalone function does only the minimal parsing required
@@ -167,12 +518,32 @@ impl<T: Read + Seek> E57Reader<T> {
         Ok(page_size)
     }
 
+    /// Reads and validates only the binary file header of an E57 file.
+    ///
+    /// This standalone function does not parse the XML section and does not check any CRC checksums.
+    /// It's useful for quickly checking the file type, version or page size of a file.
+    pub fn read_header(mut reader: T) -> Result<Header> {
+        reader
+            .seek(std::io::SeekFrom::Start(0))
+            .read_err("Cannot seek to file header")?;
+        Header::read(&mut reader)
+    }
+
     /// Returns the raw unparsed binary XML data of the E57 file as bytes.
     ///
     /// This standalone function does only the minimal parsing required
     /// to get the XML section without any other checks or any other
     /// validation than basic CRC ckecking for the XML section itself.
-    pub fn raw_xml(mut reader: T) -> Result<Vec<u8>> {
+    /// The size of the XML section is checked against the default [`ResourceLimits`].
+    pub fn raw_xml(reader: T) -> Result<Vec<u8>> {
+        Self::raw_xml_with_limits(reader, ResourceLimits::default())
+    }
+
+    /// Returns the raw unparsed binary XML data of the E57 file as bytes.
+    ///
+    /// Works like [`E57Reader::raw_xml()`], but checks the size of the XML section
+    /// against the maximum XML size of the given limits.
+    pub fn raw_xml_with_limits(mut reader: T, limits: ResourceLimits) -> Result<Vec<u8>> {
         let page_size = Self::get_u64(&mut reader, 40, "page size")?;
         let xml_offset = Self::get_u64(&mut reader, 24, "XML offset")?;
         let xml_length = Self::get_u64(&mut reader, 32, "XML length")?;
@@ -182,7 +553,12 @@ impl<T: Read + Seek> E57Reader<T> {
             PagedReader::new(reader, page_size).read_err("Failed creating paged CRC reader")?;
 
         // Read XML data
-        Self::extract_xml(&mut paged_reader, xml_offset, xml_length as usize)
+        Self::extract_xml(
+            &mut paged_reader,
+            xml_offset,
+            xml_length,
+            limits.max_xml_size,
+        )
     }
 
     fn get_u64(reader: &mut T, offset: u64, name: &str) -> Result<u64> {
@@ -196,12 +572,18 @@ impl<T: Read + Seek> E57Reader<T> {
         Ok(u64::from_le_bytes(buf))
     }
 
-    fn extract_xml(reader: &mut PagedReader<T>, offset: u64, length: usize) -> Result<Vec<u8>> {
-        if length > MAX_XML_SIZE {
-            Error::not_implemented(format!(
-                "XML sections larger than {MAX_XML_SIZE} bytes are not supported"
+    fn extract_xml(
+        reader: &mut PagedReader<T>,
+        offset: u64,
+        length: u64,
+        max_size: usize,
+    ) -> Result<Vec<u8>> {
+        if length > max_size as u64 {
+            Error::limit_exceeded(format!(
+                "XML section with {length} bytes exceeds the limit of {max_size} bytes"
             ))?
         }
+        let length = length as usize;
         reader
             .seek_physical(offset)
             .read_err("Cannot seek to XML offset")?;
@@ -221,3 +603,12 @@ impl E57Reader<BufReader<File>> {
         Self::new(reader)
     }
 }
+
+impl<R: Read> E57Reader<StreamBuffer<R>> {
+    /// Creates an E57 instance from a source that can only be read sequentially, like stdin.
+    ///
+    /// The data is buffered according to the spill policy, see [`StreamBuffer`] for details.
+    pub fn from_stream(source: R, policy: SpillPolicy) -> Result<Self> {
+        Self::new(StreamBuffer::new(source, policy))
+    }
+}
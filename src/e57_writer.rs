@@ -1,19 +1,46 @@
 use crate::error::Converter;
 use crate::paged_writer::PagedWriter;
-use crate::pc_writer::PointCloudWriter;
+#[cfg(feature = "read")]
+use crate::pc_writer::copy_section;
+use crate::pc_writer::{rollback_section, PointCloudWriter};
 use crate::root::{serialize_root, Root};
-use crate::{DateTime, Error, Extension, Header, Image, ImageWriter, PointCloud, Record, Result};
+use crate::{
+    Blob, DateTime, Error, Extension, ExtensionDefinition, Header, Image, ImageWriter, Point,
+    PointCloud, Record, RecordName, Result,
+};
+#[cfg(feature = "read")]
+use crate::{E57Reader, Projection, RawValues, Transform};
+#[cfg(feature = "read")]
+use roxmltree::Document;
+#[cfg(feature = "read")]
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, Write};
 use std::path::Path;
 
 /// Main interface for creating and writing E57 files.
+///
+/// Point clouds, images and blobs can be added in any order and interleaved as needed.
+/// All binary sections are written in a single pass and the XML section
+/// describing them is assembled and written when the file is finalized.
+///
+/// Only one point cloud or image can be written at a time.
+/// This is enforced by the section writers, which mutably borrow the E57 writer
+/// until they are dropped. A section writer that is dropped without calling
+/// `finalize()` or `abort()` leaves an unfinished section, which is discarded
+/// when the next section is added or the file is finalized.
 pub struct E57Writer<T: Read + Write + Seek> {
     pub(crate) writer: PagedWriter<T>,
     pub(crate) pointclouds: Vec<PointCloud>,
     extensions: Vec<Extension>,
+    known_extensions: Vec<ExtensionDefinition>,
     images: Vec<Image>,
+    custom_xml: Vec<String>,
+    custom_attributes: Vec<String>,
     root: Root,
+    unit_scale: f64,
+    open_section: Option<u64>,
 }
 
 impl<T: Write + Read + Seek> E57Writer<T> {
@@ -44,11 +71,29 @@ impl<T: Write + Read + Seek> E57Writer<T> {
             pointclouds: Vec::new(),
             images: Vec::new(),
             extensions: Vec::new(),
+            known_extensions: Vec::new(),
+            custom_xml: Vec::new(),
+            custom_attributes: Vec::new(),
             root,
+            unit_scale: 1.0,
+            open_section: None,
         })
     }
 
+    /// Creates a new E57 generator instance with a randomly generated GUID.
+    ///
+    /// Works like [`E57Writer::new()`], but does not require a GUID for the file.
+    #[cfg(feature = "uuid")]
+    pub fn new_with_random_guid(writer: T) -> Result<Self> {
+        Self::new(writer, &random_guid())
+    }
+
     /// Set optional coordinate metadata string (empty by default).
+    ///
+    /// Should be a WKT string describing the coordinate reference system of the points,
+    /// see [`CoordinateReferenceSystem`](crate::CoordinateReferenceSystem) to inspect such strings.
+    /// Points are written as given, so any reprojection into this coordinate system
+    /// must be applied by the application before adding the points.
     pub fn set_coordinate_metadata(&mut self, value: Option<String>) {
         self.root.coordinate_metadata = value;
     }
@@ -58,19 +103,471 @@ impl<T: Write + Read + Seek> E57Writer<T> {
         self.root.creation = value;
     }
 
+    /// Set linear scale factor applied to all lengths of subsequently added point clouds and images.
+    /// For example, use 0.3048 to convert coordinates given in feet into meters.
+    ///
+    /// The scale is applied to Cartesian coordinates, spherical ranges, bounds and pose translations.
+    /// Floating point values are multiplied with the factor before they are encoded,
+    /// while the scale and offset of scaled integer records are adjusted instead.
+    /// Integer Cartesian coordinates or ranges cannot be scaled and will be rejected.
+    /// Default value is 1.0, meaning no scaling.
+    pub fn set_unit_scale(&mut self, factor: f64) -> Result<()> {
+        if !factor.is_finite() || factor <= 0.0 {
+            Error::invalid(format!(
+                "Unit scale factor must be a positive finite number, but is {factor}"
+            ))?
+        }
+        self.unit_scale = factor;
+        Ok(())
+    }
+
     /// Creates a new writer for adding a new point cloud to the E57 file.
     pub fn add_pointcloud(
         &mut self,
         guid: &str,
         prototype: Vec<Record>,
-    ) -> Result<PointCloudWriter<T>> {
-        Extension::validate_prototype(&prototype, &self.extensions)?;
-        PointCloudWriter::new(&mut self.writer, &mut self.pointclouds, guid, prototype)
+    ) -> Result<PointCloudWriter<'_, T>> {
+        self.register_used_extensions(&prototype)?;
+        self.discard_open_section()?;
+        PointCloudWriter::new(
+            &mut self.writer,
+            &mut self.pointclouds,
+            &mut self.open_section,
+            &self.extensions,
+            guid,
+            prototype,
+            self.unit_scale,
+        )
+    }
+
+    /// Creates a new writer for adding a new point cloud with a randomly generated GUID.
+    #[cfg(feature = "uuid")]
+    pub fn add_pointcloud_with_random_guid(
+        &mut self,
+        prototype: Vec<Record>,
+    ) -> Result<PointCloudWriter<'_, T>> {
+        self.add_pointcloud(&random_guid(), prototype)
+    }
+
+    /// Creates, fills and finalizes a new point cloud in a single call.
+    ///
+    /// The points are converted to the types of the prototype records,
+    /// see [`PointCloudWriter::add_simple_point()`] for details.
+    /// Returns the number of written points.
+    pub fn write_pointcloud_from_iter<I>(
+        &mut self,
+        guid: &str,
+        prototype: Vec<Record>,
+        points: I,
+    ) -> Result<u64>
+    where
+        I: IntoIterator<Item = Point>,
+    {
+        let mut pc_writer = self.add_pointcloud(guid, prototype)?;
+        let mut count = 0;
+        for p in points {
+            pc_writer.add_simple_point(&p)?;
+            count += 1;
+        }
+        pc_writer.finalize()?;
+        Ok(count)
     }
 
     /// Creates a new image writer for adding an image to the E57 file.
-    pub fn add_image(&mut self, guid: &str) -> Result<ImageWriter<T>> {
-        ImageWriter::new(&mut self.writer, &mut self.images, guid)
+    pub fn add_image(&mut self, guid: &str) -> Result<ImageWriter<'_, T>> {
+        self.discard_open_section()?;
+        ImageWriter::new(
+            &mut self.writer,
+            &mut self.images,
+            &mut self.open_section,
+            &self.extensions,
+            guid,
+            self.unit_scale,
+        )
+    }
+
+    /// Creates a new image writer for adding an image with a randomly generated GUID.
+    #[cfg(feature = "uuid")]
+    pub fn add_image_with_random_guid(&mut self) -> Result<ImageWriter<'_, T>> {
+        self.add_image(&random_guid())
+    }
+
+    /// Writes a new binary blob section with the data from the supplied reader.
+    ///
+    /// Returns the blob descriptor, which needs to be referenced from
+    /// a custom XML element to make the data discoverable for readers.
+    /// Use [`Blob::xml_string()`] to generate such an element and
+    /// [`E57Writer::add_custom_xml()`] to add it to the XML section.
+    pub fn add_blob(&mut self, reader: &mut dyn Read) -> Result<Blob> {
+        self.discard_open_section()?;
+        Blob::write(&mut self.writer, reader)
+    }
+
+    /// Copies a point cloud from another E57 file without decoding and encoding the points.
+    ///
+    /// The data packets of the binary section are copied unchanged and
+    /// only the offsets and the XML metadata of the point cloud are updated.
+    /// This is much faster than reading and writing all points, for example when merging files.
+    /// Optional line groups of the point cloud are also copied.
+    ///
+    /// Extensions used by the point cloud must be registered before.
+    /// The binary data cannot be scaled, so the unit scale of the reader and this writer must be 1.0.
+    #[cfg(feature = "read")]
+    pub fn copy_pointcloud<R: Read + Seek>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        pc: &PointCloud,
+    ) -> Result<()> {
+        if self.unit_scale != 1.0 || reader.unit_scale() != 1.0 {
+            Error::invalid("Point clouds cannot be copied when a unit scale is applied")?
+        }
+        self.register_used_extensions(&pc.prototype)?;
+        for xml in &pc.custom_xml {
+            Extension::validate_custom_xml(xml, &self.extensions)?;
+        }
+        for attr in &pc.custom_attributes {
+            Extension::validate_custom_attribute(attr, &self.extensions)?;
+        }
+        self.discard_open_section()?;
+
+        let mut pc = pc.clone();
+        self.open_section = Some(self.writer.physical_position()?);
+        let packets = reader.pointcloud_packets(&pc)?;
+        pc.file_offset = copy_section(packets, &mut self.writer)?;
+        if let Some(grouping) = &mut pc.line_grouping {
+            let section = PointCloud {
+                file_offset: grouping.file_offset,
+                ..Default::default()
+            };
+            let packets = reader.pointcloud_packets(&section)?;
+            grouping.file_offset = copy_section(packets, &mut self.writer)?;
+        }
+        self.open_section = None;
+
+        self.pointclouds.push(pc);
+        Ok(())
+    }
+
+    /// Reads all points of a point cloud from another E57 file and writes them into a new point cloud.
+    ///
+    /// The points are streamed from the reader to this writer, so memory usage does not
+    /// depend on the size of the point cloud. Each point is passed to the supplied closure as
+    /// raw values in the order of the source prototype. The closure can modify the values,
+    /// for example to reproject or re-scale coordinates, and returns false to skip the point.
+    /// After the closure is applied, the values must match the supplied target prototype.
+    /// Use the prototype of the source point cloud to keep the original layout.
+    ///
+    /// The metadata of the source point cloud, like name, pose, sensor and custom XML, is copied.
+    /// Bounds and limits are calculated from the written points.
+    /// Line groups are copied and their point indices are adjusted for skipped points,
+    /// if the ID element of the grouping is part of the target prototype.
+    /// The bounds of the line groups are copied unchanged.
+    /// Extensions used by the point cloud must be registered before.
+    ///
+    /// The same pipeline is also available as free function [`copy_pointcloud()`](crate::copy_pointcloud).
+    /// The raw values are not scaled, so the unit scale of the reader must be 1.0.
+    /// Returns the number of written points.
+    #[cfg(feature = "read")]
+    pub fn transcode_pointcloud<R, F>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        pc: &PointCloud,
+        guid: &str,
+        prototype: Vec<Record>,
+        mut map: F,
+    ) -> Result<u64>
+    where
+        R: Read + Seek,
+        F: FnMut(&mut RawValues) -> Result<bool>,
+    {
+        if reader.unit_scale() != 1.0 {
+            Error::invalid(
+                "Point clouds cannot be transcoded when the reader applies a unit scale",
+            )?
+        }
+        // Line groups must be read before the points and can only be copied
+        // if the target prototype still contains the ID element
+        let grouping = pc.line_grouping.as_ref().filter(|g| {
+            prototype.iter().any(|r| {
+                let tag_name = r.name.tag_name();
+                match r.name.namespace() {
+                    Some(ns) => g.id_element_name == format!("{ns}:{tag_name}"),
+                    None => g.id_element_name == tag_name,
+                }
+            })
+        });
+        let mut groups = match grouping {
+            Some(_) => reader.line_groups(pc)?,
+            None => Vec::new(),
+        };
+        let mut boundaries: Vec<u64> = groups
+            .iter()
+            .flat_map(|g| {
+                let start = g.start_point_index as u64;
+                [start, start.saturating_add(g.point_count as u64)]
+            })
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut pc_writer = self.add_pointcloud(guid, prototype)?;
+        pc_writer.set_name(pc.name.clone());
+        pc_writer.set_description(pc.description.clone());
+        pc_writer.set_original_guids(pc.original_guids.clone());
+        pc_writer.set_transform(pc.transform.clone());
+        pc_writer.set_acquisition_start(pc.acquisition_start.clone());
+        pc_writer.set_acquisition_end(pc.acquisition_end.clone());
+        pc_writer.set_sensor_vendor(pc.sensor_vendor.clone());
+        pc_writer.set_sensor_model(pc.sensor_model.clone());
+        pc_writer.set_sensor_serial(pc.sensor_serial.clone());
+        pc_writer.set_sensor_sw_version(pc.sensor_sw_version.clone());
+        pc_writer.set_sensor_hw_version(pc.sensor_hw_version.clone());
+        pc_writer.set_sensor_fw_version(pc.sensor_fw_version.clone());
+        pc_writer.set_temperature(pc.temperature);
+        pc_writer.set_humidity(pc.humidity);
+        pc_writer.set_atmospheric_pressure(pc.atmospheric_pressure);
+        for xml in &pc.custom_xml {
+            pc_writer.add_custom_xml(xml)?;
+        }
+        for attr in &pc.custom_attributes {
+            pc_writer.add_custom_attribute(attr)?;
+        }
+
+        let mut iter = reader.pointcloud_raw(pc)?;
+        let mut values = RawValues::with_capacity(pc.prototype.len());
+        let mut count = 0;
+        let mut index = 0;
+        // Number of written points before each line group boundary
+        let mut written_before = Vec::with_capacity(boundaries.len());
+        while let Some(result) = iter.next_into(&mut values) {
+            result?;
+            while boundaries.get(written_before.len()) == Some(&index) {
+                written_before.push(count);
+            }
+            if map(&mut values)? {
+                // The writer keeps the values, so the buffer is handed over instead of cloned
+                let next = RawValues::with_capacity(pc.prototype.len());
+                pc_writer.add_point(std::mem::replace(&mut values, next))?;
+                count += 1;
+            }
+            index += 1;
+        }
+        written_before.resize(boundaries.len(), count);
+
+        if let Some(grouping) = grouping {
+            let written = |index: u64| {
+                boundaries
+                    .binary_search(&index)
+                    .map_or(count, |i| written_before[i])
+            };
+            for g in &mut groups {
+                let start = g.start_point_index as u64;
+                let end = start.saturating_add(g.point_count as u64);
+                g.start_point_index = written(start) as i64;
+                g.point_count = (written(end) - written(start)) as i64;
+            }
+            pc_writer.set_line_groups(&grouping.id_element_name, groups)?;
+        }
+        pc_writer.finalize()?;
+        Ok(count)
+    }
+
+    /// Copies an image with all its binary image and mask data from another E57 file.
+    ///
+    /// The binary data is copied unchanged and only the offsets of the XML metadata are updated.
+    /// Extensions used by custom XML of the image must be registered before.
+    /// The lengths of the image are not scaled, so the unit scale of the reader and this writer must be 1.0.
+    #[cfg(feature = "read")]
+    pub fn copy_image<R: Read + Seek>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        image: &Image,
+    ) -> Result<()> {
+        if self.unit_scale != 1.0 || reader.unit_scale() != 1.0 {
+            Error::invalid("Images cannot be copied when a unit scale is applied")?
+        }
+        for xml in &image.custom_xml {
+            Extension::validate_custom_xml(xml, &self.extensions)?;
+        }
+        for attr in &image.custom_attributes {
+            Extension::validate_custom_attribute(attr, &self.extensions)?;
+        }
+        self.discard_open_section()?;
+
+        let mut image = image.clone();
+        self.open_section = Some(self.writer.physical_position()?);
+        let mut blobs = Vec::new();
+        if let Some(vr) = &mut image.visual_reference {
+            blobs.push(&mut vr.blob.data);
+            blobs.extend(vr.mask.as_mut());
+        }
+        match &mut image.projection {
+            Some(Projection::Pinhole(p)) => {
+                blobs.push(&mut p.blob.data);
+                blobs.extend(p.mask.as_mut());
+            }
+            Some(Projection::Spherical(p)) => {
+                blobs.push(&mut p.blob.data);
+                blobs.extend(p.mask.as_mut());
+            }
+            Some(Projection::Cylindrical(p)) => {
+                blobs.push(&mut p.blob.data);
+                blobs.extend(p.mask.as_mut());
+            }
+            None => {}
+        }
+        for blob in blobs {
+            *blob = self.write_blob_copy(reader, blob)?;
+        }
+        self.open_section = None;
+
+        self.images.push(image);
+        Ok(())
+    }
+
+    /// Copies a binary blob section unchanged from another E57 file.
+    ///
+    /// Returns the descriptor of the new blob, which can be used to
+    /// update custom XML elements referencing the blob.
+    #[cfg(feature = "read")]
+    pub fn copy_blob<R: Read + Seek>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        blob: &Blob,
+    ) -> Result<Blob> {
+        self.discard_open_section()?;
+        self.write_blob_copy(reader, blob)
+    }
+
+    /// Copies all point clouds and images from another E57 file and replaces the point cloud poses.
+    ///
+    /// Allows to store the results of an external registration step without touching the point data.
+    /// The poses are expected in the same order as the point clouds of the reader,
+    /// None removes the pose of the corresponding point cloud.
+    /// Extensions, creation date, coordinate metadata and the custom XML and attributes of the root element
+    /// are copied as well. Binary blobs referenced by the custom XML are copied
+    /// and their offsets are updated in the copied XML.
+    /// Returns an error if an extension of the source file uses a namespace
+    /// that is already registered with a different URL.
+    #[cfg(feature = "read")]
+    pub fn copy_with_poses<R: Read + Seek>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        poses: &[Option<Transform>],
+    ) -> Result<()> {
+        let pointclouds = reader.pointclouds();
+        if poses.len() != pointclouds.len() {
+            Error::invalid(format!(
+                "Expected {} poses for the point clouds, but got {}",
+                pointclouds.len(),
+                poses.len()
+            ))?
+        }
+        let extensions = reader.extensions();
+        for extension in &extensions {
+            let ns = &extension.namespace;
+            if let Some(e) = self.extensions.iter().find(|e| &e.namespace == ns) {
+                if e.url != extension.url {
+                    Error::invalid(format!(
+                        "The namespace {ns} of the source file uses the URL {}, but is already registered with the URL {}",
+                        extension.url, e.url
+                    ))?
+                }
+            }
+        }
+        for extension in extensions {
+            if !self
+                .extensions
+                .iter()
+                .any(|e| e.namespace == extension.namespace)
+            {
+                self.extensions.push(extension);
+            }
+        }
+        self.set_creation(reader.creation());
+        self.set_coordinate_metadata(reader.coordinate_metadata().map(ToOwned::to_owned));
+        for (mut pc, pose) in pointclouds.into_iter().zip(poses) {
+            pc.transform = pose.clone();
+            self.copy_pointcloud(reader, &pc)?;
+        }
+        for image in reader.images() {
+            self.copy_image(reader, &image)?;
+        }
+        let mut blobs = HashMap::new();
+        for xml in reader.custom_xml().to_vec() {
+            let xml = self.copy_referenced_blobs(reader, &xml, &mut blobs)?;
+            self.add_custom_xml(&xml)?;
+        }
+        for attr in reader.custom_attributes() {
+            self.add_custom_attribute(attr)?;
+        }
+        Ok(())
+    }
+
+    /// Copies all binary blobs referenced by a custom XML element of another E57 file
+    /// and returns the element with the updated blob offsets.
+    /// Blobs referenced multiple times are only copied once.
+    #[cfg(feature = "read")]
+    fn copy_referenced_blobs<R: Read + Seek>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        xml: &str,
+        blobs: &mut HashMap<u64, Blob>,
+    ) -> Result<String> {
+        let mut namespaces = String::new();
+        for ext in &self.extensions {
+            namespaces += &format!(" xmlns:{}=\"{}\"", ext.namespace, ext.url);
+        }
+        let prefix = format!("<e57Root{namespaces}>");
+        let wrapped = format!("{prefix}{xml}</e57Root>");
+        let document = Document::parse(&wrapped).invalid_err("Failed to parse custom XML")?;
+        let mut result = String::with_capacity(xml.len());
+        let mut copied_until = prefix.len();
+        for node in document.descendants() {
+            if node.attribute("type") != Some("Blob") {
+                continue;
+            }
+            let blob = Blob::from_node(&node)?;
+            let copy = match blobs.get(&blob.offset) {
+                Some(copy) => copy.clone(),
+                None => {
+                    self.discard_open_section()?;
+                    let copy = self.write_blob_copy(reader, &blob)?;
+                    blobs.insert(blob.offset, copy.clone());
+                    copy
+                }
+            };
+            // Blob elements have no children, so they can be replaced as a whole
+            let range = node.range();
+            let tag_name = wrapped[range.start + 1..range.end]
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .next()
+                .unwrap_or_default();
+            result += &wrapped[copied_until..range.start];
+            result += copy.xml_string(tag_name).trim_end();
+            copied_until = range.end;
+        }
+        result += &wrapped[copied_until..prefix.len() + xml.len()];
+        Ok(result)
+    }
+
+    /// Adds a custom XML element as child of the E57 root element.
+    ///
+    /// The element must use the namespace of a registered extension and needs to be well-formed XML.
+    /// It will be included unchanged in the XML section of the file.
+    pub fn add_custom_xml(&mut self, xml: &str) -> Result<()> {
+        Extension::validate_custom_xml(xml, &self.extensions)?;
+        self.custom_xml.push(xml.to_owned());
+        Ok(())
+    }
+
+    /// Adds a custom XML attribute to the E57 root element, for example `ven:id="42"`.
+    ///
+    /// The attribute must use the namespace of a registered extension and needs to be well-formed XML.
+    pub fn add_custom_attribute(&mut self, attribute: &str) -> Result<()> {
+        Extension::validate_custom_attribute(attribute, &self.extensions)?;
+        self.custom_attributes.push(attribute.to_owned());
+        Ok(())
     }
 
     /// Registers a new E57 extension used by this file.
@@ -90,16 +587,84 @@ impl<T: Write + Read + Seek> E57Writer<T> {
         }
     }
 
+    /// Registers a known extension with its namespace and record definitions.
+    ///
+    /// The extension itself is only declared in the XML section when a point cloud prototype
+    /// uses records with its namespace, there is no need to call [`E57Writer::register_extesion()`].
+    /// Returns an error if a known extension with the same namespace is already registered
+    /// or if an extension with the same namespace but a different URL is already registered.
+    /// See `EXTENSIONS.md` in the repository for the namespaces defined by this library.
+    pub fn register_known_extension(&mut self, definition: ExtensionDefinition) -> Result<()> {
+        let ns = &definition.extension.namespace;
+        Extension::validate_name(ns)?;
+        if self
+            .known_extensions
+            .iter()
+            .any(|d| &d.extension.namespace == ns)
+        {
+            Error::invalid(format!(
+                "A known extension using the namespace {ns} is already registered"
+            ))?
+        }
+        if let Some(e) = self.extensions.iter().find(|e| &e.namespace == ns) {
+            if e.url != definition.extension.url {
+                Error::invalid(format!(
+                    "The namespace {ns} is already registered with the different URL {}",
+                    e.url
+                ))?
+            }
+        }
+        self.known_extensions.push(definition);
+        Ok(())
+    }
+
+    /// Declares all known extensions used by the prototype that are not yet registered
+    /// and validates the prototype against the resulting extensions.
+    ///
+    /// Nothing is registered if the prototype is invalid or if a namespace of a known extension
+    /// is already registered with a different URL.
+    fn register_used_extensions(&mut self, prototype: &[Record]) -> Result<()> {
+        let mut extensions = self.extensions.clone();
+        for record in prototype {
+            if let RecordName::Unknown { namespace, .. } = &record.name {
+                let known = self
+                    .known_extensions
+                    .iter()
+                    .find(|d| &d.extension.namespace == namespace);
+                let Some(definition) = known else {
+                    continue;
+                };
+                match extensions.iter().find(|e| &e.namespace == namespace) {
+                    Some(e) if e.url != definition.extension.url => Error::invalid(format!(
+                        "The namespace {namespace} is registered with the URL {}, but the known extension uses the URL {}",
+                        e.url, definition.extension.url
+                    ))?,
+                    Some(_) => {}
+                    None => extensions.push(definition.extension.clone()),
+                }
+            }
+        }
+        Extension::validate_prototype(prototype, &extensions)?;
+        self.extensions = extensions;
+        Ok(())
+    }
+
     /// Needs to be called after adding all point clouds and images.
     ///
     /// This will generate and write the XML metadata to finalize and complete the E57 file.
     /// Without calling this method before dropping the E57 file will be incomplete and invalid!
+    ///
+    /// Returns an error if the same GUID was used for multiple point clouds or images.
     pub fn finalize(&mut self) -> Result<()> {
+        self.discard_open_section()?;
+        self.check_duplicate_guids()?;
         let xml = serialize_root(
             &self.root,
             &self.pointclouds,
             &self.images,
             &self.extensions,
+            &self.custom_xml,
+            &self.custom_attributes,
         )?;
         let xml_bytes = xml.as_bytes();
         let xml_length = xml_bytes.len();
@@ -124,6 +689,48 @@ impl<T: Write + Read + Seek> E57Writer<T> {
     }
 }
 
+impl<T: Write + Read + Seek> E57Writer<T> {
+    /// Rolls back any point cloud or image section that was neither finalized nor aborted.
+    /// With the tracing feature this is reported as warning, because it usually means that a call to `finalize()` is missing.
+    fn discard_open_section(&mut self) -> Result<()> {
+        if let Some(offset) = self.open_section.take() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                offset,
+                "Discarding unfinished section, its writer was dropped without calling finalize() or abort()"
+            );
+            rollback_section(&mut self.writer, offset)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a blob from another E57 file and writes it into a new blob section.
+    #[cfg(feature = "read")]
+    fn write_blob_copy<R: Read + Seek>(
+        &mut self,
+        reader: &mut E57Reader<R>,
+        blob: &Blob,
+    ) -> Result<Blob> {
+        let mut data = reader.blob_reader(blob)?;
+        Blob::write(&mut self.writer, &mut data)
+    }
+
+    /// Makes sure that all point clouds and images have unique GUIDs.
+    fn check_duplicate_guids(&self) -> Result<()> {
+        let pc_guids = self.pointclouds.iter().filter_map(|pc| pc.guid.as_deref());
+        let img_guids = self.images.iter().filter_map(|img| img.guid.as_deref());
+        let mut guids = HashSet::new();
+        for guid in pc_guids.chain(img_guids) {
+            if !guids.insert(guid) {
+                Error::invalid(format!(
+                    "The GUID '{guid}' is used for more than one point cloud or image"
+                ))?
+            }
+        }
+        Ok(())
+    }
+}
+
 impl E57Writer<File> {
     /// Creates an E57 writer instance from a Path.
     pub fn from_file(path: impl AsRef<Path>, guid: &str) -> Result<Self> {
@@ -136,4 +743,41 @@ impl E57Writer<File> {
             .read_err("Unable to create file for writing, reading and seeking")?;
         Self::new(file, guid)
     }
+
+    /// Creates an E57 writer instance from a Path with a randomly generated GUID.
+    #[cfg(feature = "uuid")]
+    pub fn from_file_with_random_guid(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_file(path, &random_guid())
+    }
+}
+
+/// Copies a point cloud from a reader into a writer, record by record.
+///
+/// Unlike [`E57Writer::copy_pointcloud()`], which copies the binary data unchanged,
+/// all points are decoded and encoded again with the target prototype.
+/// The map closure can modify the raw values of each point and
+/// returns `false` to skip points, for example to filter or subsample a point cloud.
+/// Works exactly like [`E57Writer::transcode_pointcloud()`], see there for all details.
+/// Returns the number of written points.
+#[cfg(feature = "read")]
+pub fn copy_pointcloud<R, W, F>(
+    reader: &mut E57Reader<R>,
+    writer: &mut E57Writer<W>,
+    pc: &PointCloud,
+    guid: &str,
+    prototype: Vec<Record>,
+    map: F,
+) -> Result<u64>
+where
+    R: Read + Seek,
+    W: Write + Read + Seek,
+    F: FnMut(&mut RawValues) -> Result<bool>,
+{
+    writer.transcode_pointcloud(reader, pc, guid, prototype, map)
+}
+
+/// Generates a new random GUID in the braced upper case format used by most E57 files.
+#[cfg(feature = "uuid")]
+fn random_guid() -> String {
+    format!("{:X}", uuid::Uuid::new_v4().braced())
 }
@@ -7,6 +7,68 @@ use std::result::Result as StdResult;
 /// To be used as error message when extracting stuff from arrays that should never fail
 pub const WRONG_OFFSET: &str = "Wrong buffer offset detected";
 
+/// Describes a page with an invalid checksum.
+/// Wrapped inside the IO errors of the paged reader to allow detecting it later.
+#[derive(Debug)]
+pub struct ChecksumError {
+    pub page: u64,
+    pub physical_offset: u64,
+    pub expected: [u8; 4],
+    pub calculated: [u8; 4],
+}
+
+impl std::fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Detected invalid checksum (expected: {:?}, actual: {:?}) for page {}",
+            self.expected, self.calculated, self.page
+        )
+    }
+}
+
+impl std::error::Error for ChecksumError {}
+
+/// Stable classification of errors for programmatic handling.
+///
+/// Use [`Error::kind`] to get the kind of an error instead of parsing the error message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A page of the file has an invalid CRC checksum and is most likely corrupt.
+    Checksum,
+    /// An IO operation failed or the file ended unexpectedly.
+    Io,
+    /// The XML section is malformed or misses required elements.
+    Xml,
+    /// A binary structure like the file header, a section header or a data packet is malformed.
+    Format,
+    /// The file uses a feature of the E57 format that is not supported by this library.
+    Unsupported,
+    /// The content or the supplied input violates the E57 format specification.
+    Invalid,
+    /// A configured resource limit was exceeded, see [`crate::ResourceLimits`].
+    Limit,
+    /// An unexpected internal issue occured.
+    Internal,
+}
+
+/// Additional context information attached to an error.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorContext {
+    /// Explicitly assigned kind, overrides the kind derived from the error variant.
+    pub kind: Option<ErrorKind>,
+    /// Physical byte offset in the file, including the CRC checksums of all pages.
+    pub physical_offset: Option<u64>,
+    /// Logical byte offset in the file, without the CRC checksums of all pages.
+    pub logical_offset: Option<u64>,
+    /// Path of the involved XML element, for example `/data3D/0/pose/rotation/w`.
+    pub xml_path: Option<String>,
+    /// Index of the involved data packet inside a binary section, starting at zero.
+    pub packet_index: Option<u64>,
+}
+
 /// Possible errors that can occur while working with E57 files.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -15,6 +77,7 @@ pub enum Error {
     Invalid {
         desc: String,
         source: Option<Box<dyn StdError + Send + Sync + 'static>>,
+        context: Option<Box<ErrorContext>>,
     },
 
     /// Something went wrong while reading data from an E57 file.
@@ -22,6 +85,7 @@ pub enum Error {
     Read {
         desc: String,
         source: Option<Box<dyn StdError + Send + Sync + 'static>>,
+        context: Option<Box<ErrorContext>>,
     },
 
     /// Something went wrong while writing data to an E57 file.
@@ -29,10 +93,14 @@ pub enum Error {
     Write {
         desc: String,
         source: Option<Box<dyn StdError + Send + Sync + 'static>>,
+        context: Option<Box<ErrorContext>>,
     },
 
     /// Some feature or aspect of E57 that is not yet implement by this library.
-    NotImplemented { desc: String },
+    NotImplemented {
+        desc: String,
+        context: Option<Box<ErrorContext>>,
+    },
 
     /// An unexpected internal issue occured.
     /// Most likely this is a logic bug inside the library.
@@ -40,6 +108,7 @@ pub enum Error {
     Internal {
         desc: String,
         source: Option<Box<dyn StdError + Send + Sync + 'static>>,
+        context: Option<Box<ErrorContext>>,
     },
 }
 
@@ -52,6 +121,40 @@ impl Error {
         Err(Error::Invalid {
             desc: desc.to_string(),
             source: None,
+            context: None,
+        })
+    }
+
+    /// Creates a new Invalid error for malformed binary structures with the kind Format.
+    #[cfg(feature = "read")]
+    pub(crate) fn malformed<T, C>(desc: C) -> Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        Self::invalid_with_kind(desc, ErrorKind::Format)
+    }
+
+    /// Creates a new Invalid error for exceeded resource limits with the kind Limit.
+    #[cfg(feature = "read")]
+    pub(crate) fn limit_exceeded<T, C>(desc: C) -> Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        Self::invalid_with_kind(desc, ErrorKind::Limit)
+    }
+
+    #[cfg(feature = "read")]
+    fn invalid_with_kind<T, C>(desc: C, kind: ErrorKind) -> Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        Err(Error::Invalid {
+            desc: desc.to_string(),
+            source: None,
+            context: Some(Box::new(ErrorContext {
+                kind: Some(kind),
+                ..Default::default()
+            })),
         })
     }
 
@@ -62,6 +165,7 @@ impl Error {
     {
         Err(Error::NotImplemented {
             desc: desc.to_string(),
+            context: None,
         })
     }
 
@@ -73,8 +177,133 @@ impl Error {
         Err(Error::Internal {
             desc: desc.to_string(),
             source: None,
+            context: None,
         })
     }
+
+    /// Returns the kind of the error.
+    ///
+    /// Checksum errors are detected even if they are wrapped in other errors.
+    pub fn kind(&self) -> ErrorKind {
+        if self.checksum_error().is_some() {
+            return ErrorKind::Checksum;
+        }
+        if let Some(kind) = self.context().and_then(|c| c.kind) {
+            return kind;
+        }
+        match self {
+            Error::Invalid { .. } => ErrorKind::Invalid,
+            Error::Read { .. } | Error::Write { .. } => ErrorKind::Io,
+            Error::NotImplemented { .. } => ErrorKind::Unsupported,
+            Error::Internal { .. } => ErrorKind::Internal,
+        }
+    }
+
+    /// Returns the context information attached to the error, if available.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::Invalid { context, .. }
+            | Error::Read { context, .. }
+            | Error::Write { context, .. }
+            | Error::NotImplemented { context, .. }
+            | Error::Internal { context, .. } => context.as_deref(),
+        }
+    }
+
+    /// Returns the physical byte offset in the file where the error occured, if known.
+    /// For checksum errors this is the start offset of the corrupt page.
+    pub fn physical_offset(&self) -> Option<u64> {
+        self.checksum_error()
+            .map(|c| c.physical_offset)
+            .or_else(|| self.context().and_then(|c| c.physical_offset))
+    }
+
+    /// Returns the logical byte offset in the file where the error occured, if known.
+    pub fn logical_offset(&self) -> Option<u64> {
+        self.context().and_then(|c| c.logical_offset)
+    }
+
+    /// Returns the path of the XML element involved in the error, if known.
+    pub fn xml_path(&self) -> Option<&str> {
+        self.context().and_then(|c| c.xml_path.as_deref())
+    }
+
+    /// Returns the index of the data packet involved in the error, if known.
+    pub fn packet_index(&self) -> Option<u64> {
+        self.context().and_then(|c| c.packet_index)
+    }
+
+    /// Returns the number of the page with the invalid checksum for checksum errors.
+    pub fn checksum_page(&self) -> Option<u64> {
+        self.checksum_error().map(|c| c.page)
+    }
+
+    /// Sets the kind of the error, unless it was already set before.
+    #[cfg(feature = "read")]
+    pub(crate) fn with_kind(mut self, kind: ErrorKind) -> Self {
+        let context = self.context_mut();
+        context.kind.get_or_insert(kind);
+        self
+    }
+
+    /// Sets the physical offset of the error, unless it was already set before.
+    #[cfg(feature = "read")]
+    pub(crate) fn with_physical_offset(mut self, offset: u64) -> Self {
+        self.context_mut().physical_offset.get_or_insert(offset);
+        self
+    }
+
+    /// Sets the logical offset of the error, unless it was already set before.
+    #[cfg(feature = "read")]
+    pub(crate) fn with_logical_offset(mut self, offset: u64) -> Self {
+        self.context_mut().logical_offset.get_or_insert(offset);
+        self
+    }
+
+    /// Sets the XML path of the error, unless it was already set before.
+    #[cfg(feature = "read")]
+    pub(crate) fn with_xml_path(mut self, path: impl Into<String>) -> Self {
+        self.context_mut()
+            .xml_path
+            .get_or_insert_with(|| path.into());
+        self
+    }
+
+    /// Sets the packet index of the error, unless it was already set before.
+    #[cfg(feature = "read")]
+    pub(crate) fn with_packet_index(mut self, index: u64) -> Self {
+        self.context_mut().packet_index.get_or_insert(index);
+        self
+    }
+
+    #[cfg(feature = "read")]
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        let context = match self {
+            Error::Invalid { context, .. }
+            | Error::Read { context, .. }
+            | Error::Write { context, .. }
+            | Error::NotImplemented { context, .. }
+            | Error::Internal { context, .. } => context,
+        };
+        context.get_or_insert_with(Box::default)
+    }
+
+    fn checksum_error(&self) -> Option<&ChecksumError> {
+        let mut current = self.source();
+        while let Some(error) = current {
+            if let Some(checksum) = error.downcast_ref::<ChecksumError>() {
+                return Some(checksum);
+            }
+            if let Some(io) = error.downcast_ref::<std::io::Error>() {
+                if let Some(checksum) = io.get_ref().and_then(|e| e.downcast_ref::<ChecksumError>())
+                {
+                    return Some(checksum);
+                }
+            }
+            current = error.source();
+        }
+        None
+    }
 }
 
 impl Display for Error {
@@ -83,7 +312,7 @@ impl Display for Error {
             Error::Invalid { desc, .. } => write!(f, "Invalid E57 content: {desc}"),
             Error::Read { desc, .. } => write!(f, "Failed to read E57: {desc}"),
             Error::Internal { desc, .. } => write!(f, "Internal error: {desc}"),
-            Error::NotImplemented { desc } => write!(f, "Not implemented: {desc}"),
+            Error::NotImplemented { desc, .. } => write!(f, "Not implemented: {desc}"),
             Error::Write { desc, .. } => write!(f, "Failed to write E57: {desc}"),
         }
     }
@@ -145,6 +374,7 @@ where
             Err(error) => Err(Error::Read {
                 desc: desc.to_string(),
                 source: Some(Box::new(error)),
+                context: None,
             }),
         }
     }
@@ -158,6 +388,7 @@ where
             Err(error) => Err(Error::Invalid {
                 desc: desc.to_string(),
                 source: Some(Box::new(error)),
+                context: None,
             }),
         }
     }
@@ -171,6 +402,7 @@ where
             Err(error) => Err(Error::Internal {
                 desc: desc.to_string(),
                 source: Some(Box::new(error)),
+                context: None,
             }),
         }
     }
@@ -184,6 +416,7 @@ where
             Err(error) => Err(Error::Write {
                 desc: desc.to_string(),
                 source: Some(Box::new(error)),
+                context: None,
             }),
         }
     }
@@ -200,6 +433,7 @@ impl<T> Converter<T, Infallible> for Option<T> {
             None => Err(Error::Read {
                 desc: desc.to_string(),
                 source: None,
+                context: None,
             }),
         }
     }
@@ -213,6 +447,7 @@ impl<T> Converter<T, Infallible> for Option<T> {
             None => Err(Error::Invalid {
                 desc: desc.to_string(),
                 source: None,
+                context: None,
             }),
         }
     }
@@ -226,6 +461,7 @@ impl<T> Converter<T, Infallible> for Option<T> {
             None => Err(Error::Internal {
                 desc: desc.to_string(),
                 source: None,
+                context: None,
             }),
         }
     }
@@ -239,7 +475,45 @@ impl<T> Converter<T, Infallible> for Option<T> {
             None => Err(Error::Write {
                 desc: desc.to_string(),
                 source: None,
+                context: None,
             }),
         }
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_from_variant_and_context() {
+        let error = Error::invalid::<(), _>("test").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Invalid);
+        assert!(error.context().is_none());
+
+        let error = error.with_kind(ErrorKind::Xml).with_kind(ErrorKind::Format);
+        assert_eq!(error.kind(), ErrorKind::Xml);
+
+        let error = Error::not_implemented::<(), _>("test").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let error = Error::malformed::<(), _>("test").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+    }
+
+    #[test]
+    fn context_is_not_overwritten() {
+        let error = Error::internal::<(), _>("test")
+            .unwrap_err()
+            .with_packet_index(3)
+            .with_packet_index(5)
+            .with_xml_path("/inner")
+            .with_xml_path("/outer")
+            .with_logical_offset(7);
+        assert_eq!(error.packet_index(), Some(3));
+        assert_eq!(error.xml_path(), Some("/inner"));
+        assert_eq!(error.logical_offset(), Some(7));
+        assert_eq!(error.physical_offset(), None);
+    }
+}
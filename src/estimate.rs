@@ -0,0 +1,75 @@
+use crate::{Point, PointCloud, RecordDataType, RecordName, RecordValue};
+use std::mem::size_of;
+
+/// Layout of a single record of a point cloud prototype.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RecordLayout {
+    /// Name of the record.
+    pub name: RecordName,
+    /// Data type of the record.
+    pub data_type: RecordDataType,
+    /// Number of bits used to store a single value of this record in the binary section.
+    pub bit_size: u64,
+}
+
+/// Estimated resource usage for reading a point cloud.
+///
+/// All values are calculated from the point cloud descriptor without decoding any points.
+/// Memory sizes are estimates for holding all points at once in the corresponding output mode
+/// and do not include any temporary buffers used while decoding.
+/// They can be used to decide up front between in-memory and streaming processing.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ResourceEstimate {
+    /// Number of points in the point cloud.
+    pub points: u64,
+    /// Layout of all records of the prototype, in the same order as the prototype.
+    pub records: Vec<RecordLayout>,
+    /// Number of bits required to store a single point in the binary section.
+    pub bits_per_point: u64,
+    /// Size of the bit packed point data in bytes, excluding packet headers and padding.
+    pub encoded_bytes: u64,
+    /// Size in bytes of a vector of simple points with double precision coordinates.
+    /// See [`PointCloudReaderSimple`](crate::PointCloudReaderSimple).
+    pub simple_f64_bytes: u64,
+    /// Size in bytes of a vector of simple points with single precision coordinates.
+    /// See [`PointCloudReaderSimple::single_precision()`](crate::PointCloudReaderSimple::single_precision).
+    pub simple_f32_bytes: u64,
+    /// Size in bytes of a vector of raw values for each point.
+    /// See [`PointCloudReaderRaw`](crate::PointCloudReaderRaw).
+    pub raw_bytes: u64,
+    /// Size in bytes of a structure of arrays with one vector of double values per record.
+    pub columns_f64_bytes: u64,
+    /// Size in bytes of a structure of arrays with one vector of single values per record.
+    pub columns_f32_bytes: u64,
+}
+
+impl ResourceEstimate {
+    pub(crate) fn from_pointcloud(pc: &PointCloud) -> Self {
+        let records: Vec<RecordLayout> = pc
+            .prototype
+            .iter()
+            .map(|r| RecordLayout {
+                name: r.name.clone(),
+                data_type: r.data_type.clone(),
+                bit_size: r.data_type.bit_size() as u64,
+            })
+            .collect();
+        let bits_per_point = records.iter().map(|r| r.bit_size).sum::<u64>();
+        let points = pc.records;
+        let bytes = |per_point: usize| points.saturating_mul(per_point as u64);
+        let len = records.len();
+        Self {
+            points,
+            bits_per_point,
+            encoded_bytes: points.saturating_mul(bits_per_point).div_ceil(8),
+            simple_f64_bytes: bytes(size_of::<Point<f64>>()),
+            simple_f32_bytes: bytes(size_of::<Point<f32>>()),
+            raw_bytes: bytes(size_of::<Vec<RecordValue>>() + len * size_of::<RecordValue>()),
+            columns_f64_bytes: bytes(len * size_of::<f64>()),
+            columns_f32_bytes: bytes(len * size_of::<f32>()),
+            records,
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::error::Converter;
+use crate::{CartesianCoordinate, Point, PointCloud, Result};
+use std::io::{Seek, SeekFrom, Write};
+
+/// Options for the simple point cloud reader requested by an exporter.
+///
+/// See [`PointCloudReaderSimple`](crate::PointCloudReaderSimple) for details on the individual options.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExportOptions {
+    /// Convert spherical coordinates to Cartesian coordinates (enabled by default).
+    pub spherical_to_cartesian: bool,
+    /// Convert Cartesian coordinates to spherical coordinates (disabled by default).
+    pub cartesian_to_spherical: bool,
+    /// Use the intensity as grayscale color for points without color (disabled by default).
+    pub intensity_to_color: bool,
+    /// Normalize intensities with the intensity limits of the point cloud (disabled by default).
+    pub use_intensity_limits: bool,
+    /// Normalize colors with the color limits of the point cloud (disabled by default).
+    pub use_color_limits: bool,
+    /// Skip the Cartesian conversion for ranges outside of the range limits of the point cloud (disabled by default).
+    pub use_range_limits: bool,
+    /// Apply the pose of the point cloud to the coordinates (enabled by default).
+    pub apply_pose: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            spherical_to_cartesian: true,
+            cartesian_to_spherical: false,
+            intensity_to_color: false,
+            use_intensity_limits: false,
+            use_color_limits: false,
+            use_range_limits: false,
+            apply_pose: true,
+        }
+    }
+}
+
+/// Interface for exporting the points of an E57 file into other formats.
+///
+/// Exporters are driven by [`E57Reader::export()`](crate::E57Reader::export),
+/// which takes care of reading and normalizing all points.
+/// This allows to add support for additional formats without implementing the read loop.
+pub trait Exporter {
+    /// Called once for each point cloud before any of its points are passed to the exporter.
+    ///
+    /// The exporter can inspect the prototype and other properties of the point cloud
+    /// and returns the options used for reading its points.
+    /// Returning `None` will skip the point cloud.
+    fn begin_pointcloud(&mut self, pointcloud: &PointCloud) -> Result<Option<ExportOptions>> {
+        let _ = pointcloud;
+        Ok(Some(ExportOptions::default()))
+    }
+
+    /// Called for each point of all exported point clouds.
+    fn add_point(&mut self, point: &Point) -> Result<()>;
+
+    /// Called once after all point clouds were exported.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Exports all points with valid Cartesian coordinates as lines of delimited ASCII text.
+///
+/// Each line contains the X, Y and Z coordinates and, if available,
+/// the red, green and blue color values as integers between 0 and 255.
+/// The values are separated by a single space by default, like in typical XYZ files.
+/// Colors are normalized with the color limits of the point clouds.
+/// Points of point clouds without color will use their intensity as grayscale color if available.
+pub struct CsvExporter<W: Write> {
+    writer: W,
+    separator: char,
+}
+
+impl<W: Write> CsvExporter<W> {
+    /// Creates a new exporter that writes into the supplied writer.
+    /// Consider using a buffered writer for better performance.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            separator: ' ',
+        }
+    }
+
+    /// Set the separator between the values of a point (single space by default).
+    pub fn set_separator(&mut self, separator: char) {
+        self.separator = separator;
+    }
+}
+
+impl<W: Write> Exporter for CsvExporter<W> {
+    fn begin_pointcloud(&mut self, _pointcloud: &PointCloud) -> Result<Option<ExportOptions>> {
+        Ok(Some(ExportOptions {
+            intensity_to_color: true,
+            use_color_limits: true,
+            ..Default::default()
+        }))
+    }
+
+    fn add_point(&mut self, point: &Point) -> Result<()> {
+        let CartesianCoordinate::Valid { x, y, z } = point.cartesian else {
+            return Ok(());
+        };
+        let s = self.separator;
+        let line = if let Some(color) = &point.color {
+            let (r, g, b) = color.to_rgb8();
+            format!("{x}{s}{y}{s}{z}{s}{r}{s}{g}{s}{b}\n")
+        } else {
+            format!("{x}{s}{y}{s}{z}\n")
+        };
+        self.writer
+            .write_all(line.as_bytes())
+            .write_err("Failed to write point")
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush().write_err("Failed to flush writer")
+    }
+}
+
+/// Exports all points with valid Cartesian coordinates into a binary little endian PLY file.
+///
+/// The vertices have double precision X, Y and Z coordinates and
+/// optional red, green and blue color values as unsigned bytes.
+/// Points without color will be exported as black points if colors are enabled.
+/// Colors are normalized with the color limits of the point clouds.
+/// The writer must be seekable to update the vertex count in the header when finishing.
+pub struct PlyExporter<W: Write + Seek> {
+    writer: W,
+    color: bool,
+    count: u64,
+    count_offset: Option<u64>,
+}
+
+impl<W: Write + Seek> PlyExporter<W> {
+    /// Width of the vertex count in the header, padded with leading zeros.
+    const COUNT_WIDTH: usize = 20;
+
+    /// Creates a new exporter that writes into the supplied writer.
+    /// Set `color` to include RGB color properties for all vertices.
+    pub fn new(writer: W, color: bool) -> Self {
+        Self {
+            writer,
+            color,
+            count: 0,
+            count_offset: None,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let start = "ply\nformat binary_little_endian 1.0\nelement vertex ";
+        self.writer
+            .write_all(start.as_bytes())
+            .write_err("Failed to write PLY header")?;
+        let offset = self
+            .writer
+            .stream_position()
+            .write_err("Failed to get position of PLY vertex count")?;
+        self.count_offset = Some(offset);
+        let mut header = format!("{:0width$}\n", 0, width = Self::COUNT_WIDTH);
+        header += "property double x\nproperty double y\nproperty double z\n";
+        if self.color {
+            header += "property uchar red\nproperty uchar green\nproperty uchar blue\n";
+        }
+        header += "end_header\n";
+        self.writer
+            .write_all(header.as_bytes())
+            .write_err("Failed to write PLY header")
+    }
+}
+
+impl<W: Write + Seek> Exporter for PlyExporter<W> {
+    fn begin_pointcloud(&mut self, _pointcloud: &PointCloud) -> Result<Option<ExportOptions>> {
+        Ok(Some(ExportOptions {
+            use_color_limits: true,
+            ..Default::default()
+        }))
+    }
+
+    fn add_point(&mut self, point: &Point) -> Result<()> {
+        let CartesianCoordinate::Valid { x, y, z } = point.cartesian else {
+            return Ok(());
+        };
+        if self.count_offset.is_none() {
+            self.write_header()?;
+        }
+        let mut bytes = [0_u8; 27];
+        bytes[0..8].copy_from_slice(&x.to_le_bytes());
+        bytes[8..16].copy_from_slice(&y.to_le_bytes());
+        bytes[16..24].copy_from_slice(&z.to_le_bytes());
+        let len = if self.color {
+            if let Some(color) = &point.color {
+                let (r, g, b) = color.to_rgb8();
+                bytes[24..27].copy_from_slice(&[r, g, b]);
+            }
+            27
+        } else {
+            24
+        };
+        self.writer
+            .write_all(&bytes[..len])
+            .write_err("Failed to write PLY vertex")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // Files without any points still need a header
+        if self.count_offset.is_none() {
+            self.write_header()?;
+        }
+        let offset = self
+            .count_offset
+            .internal_err("Missing offset of PLY vertex count")?;
+        let end = self
+            .writer
+            .stream_position()
+            .write_err("Failed to get end position of PLY file")?;
+        let count = format!("{:0width$}", self.count, width = Self::COUNT_WIDTH);
+        self.writer
+            .seek(SeekFrom::Start(offset))
+            .write_err("Failed to seek to PLY vertex count")?;
+        self.writer
+            .write_all(count.as_bytes())
+            .write_err("Failed to write PLY vertex count")?;
+        self.writer
+            .seek(SeekFrom::Start(end))
+            .write_err("Failed to seek to end of PLY file")?;
+        self.writer.flush().write_err("Failed to flush writer")
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{Error, Record, RecordName, Result};
+use crate::error::Converter;
+use crate::{Error, PointCloud, Record, RecordDataType, RecordName, RecordValue, Result};
+#[cfg(any(feature = "read", feature = "write"))]
 use roxmltree::Document;
 
 /// Describes an extension by name and URL as used in the XML namespace desclaration.
@@ -22,6 +24,7 @@ impl Extension {
         }
     }
 
+    #[cfg(feature = "read")]
     pub(crate) fn vec_from_document(document: &Document) -> Vec<Extension> {
         let mut extensions = Vec::new();
         for item in document.root_element().namespaces() {
@@ -35,6 +38,7 @@ impl Extension {
         extensions
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn validate_prototype(prototype: &[Record], extensions: &[Extension]) -> Result<()> {
         for record in prototype {
             if let RecordName::Unknown { namespace, name } = &record.name {
@@ -50,6 +54,55 @@ impl Extension {
         Ok(())
     }
 
+    /// Makes sure the custom XML is well-formed and all its elements use a registered extension namespace.
+    #[cfg(feature = "write")]
+    pub(crate) fn validate_custom_xml(xml: &str, extensions: &[Extension]) -> Result<()> {
+        let mut namespaces = String::new();
+        for ext in extensions {
+            namespaces += &format!(" xmlns:{}=\"{}\"", ext.namespace, ext.url);
+        }
+        let wrapped = format!("<e57Root{namespaces}>{xml}</e57Root>");
+        let document = Document::parse(&wrapped)
+            .invalid_err("Custom XML is not well-formed or uses an unregistered namespace")?;
+        let elements = document
+            .root_element()
+            .children()
+            .filter(|n| n.is_element());
+        for element in elements {
+            if element.tag_name().namespace().is_none() {
+                let name = element.tag_name().name();
+                Error::invalid(format!(
+                    "Custom XML element '{name}' must use the namespace of a registered extension"
+                ))?
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes sure the custom XML attribute is well-formed and uses a registered extension namespace.
+    #[cfg(feature = "write")]
+    pub(crate) fn validate_custom_attribute(
+        attribute: &str,
+        extensions: &[Extension],
+    ) -> Result<()> {
+        let mut namespaces = String::new();
+        for ext in extensions {
+            namespaces += &format!(" xmlns:{}=\"{}\"", ext.namespace, ext.url);
+        }
+        let wrapped = format!("<e57Root{namespaces} {attribute}/>");
+        let document = Document::parse(&wrapped).invalid_err(
+            "Custom XML attribute is not well-formed or uses an unregistered namespace",
+        )?;
+        let mut attributes = document.root_element().attributes();
+        match (attributes.next(), attributes.next()) {
+            (Some(attr), None) if attr.namespace().is_some() => Ok(()),
+            _ => Error::invalid(format!(
+                "Custom XML attribute '{attribute}' must be a single attribute using the namespace of a registered extension"
+            )),
+        }
+    }
+
+    #[cfg(feature = "write")]
     pub(crate) fn validate_name(name: &str) -> Result<()> {
         if name.to_lowercase().starts_with("xml") {
             Error::invalid(format!(
@@ -68,7 +121,246 @@ impl Extension {
     }
 }
 
+/// Describes the semantics of a record defined by an extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExtensionValueKind {
+    /// Floating point value, scaled integers are converted with their scale and offset.
+    Float,
+    /// Value normalized to the range 0.0 to 1.0 using the minimum and maximum of the record data type.
+    Unit,
+    /// Signed integer value.
+    Integer,
+    /// Integer flag where zero is false and any other value is true.
+    Boolean,
+}
+
+/// Typed value of an extension record decoded with an [`ExtensionDecoder`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ExtensionValue {
+    /// Value of a record with the kind [`ExtensionValueKind::Float`].
+    Float(f64),
+    /// Value of a record with the kind [`ExtensionValueKind::Unit`].
+    Unit(f32),
+    /// Value of a record with the kind [`ExtensionValueKind::Integer`].
+    Integer(i64),
+    /// Value of a record with the kind [`ExtensionValueKind::Boolean`].
+    Boolean(bool),
+}
+
+/// Describes a single point record defined by a known extension.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExtensionRecordDefinition {
+    /// Name of the record without namespace prefix.
+    pub name: String,
+    /// Semantics of the record values.
+    pub kind: ExtensionValueKind,
+    /// Recommended data type for writing the record.
+    /// Used by [`ExtensionDefinition::default_record()`].
+    pub data_type: Option<RecordDataType>,
+}
+
+impl ExtensionRecordDefinition {
+    /// Creates a new record definition with the given name and semantics.
+    pub fn new(name: &str, kind: ExtensionValueKind) -> Self {
+        Self {
+            name: name.to_owned(),
+            kind,
+            data_type: None,
+        }
+    }
+}
+
+/// Describes a known extension with its namespace and the point records it defines.
+///
+/// Known extensions can be registered with an [`E57Writer`](crate::E57Writer)
+/// to automatically declare their namespace when a prototype uses them.
+/// When reading, extensions are identified by URL rather than by namespace prefix,
+/// since different files might use different prefixes for the same extension.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExtensionDefinition {
+    /// Namespace prefix and URL of the extension.
+    pub extension: Extension,
+    /// Point records defined by the extension.
+    pub records: Vec<ExtensionRecordDefinition>,
+}
+
+impl ExtensionDefinition {
+    /// URL of the surface normals extension.
+    pub const NORMALS_URL: &'static str = "http://www.libe57.org/E57_NOR_surface_normals.txt";
+
+    /// URL of the extension with common LAS point attributes defined by this library.
+    /// The extension is documented in the file `EXTENSIONS.md` of the repository.
+    pub const LAS_URL: &'static str =
+        "https://github.com/cry-inc/e57/blob/master/EXTENSIONS.md#las-attributes";
+
+    /// Creates a new definition for the given extension and records.
+    pub fn new(extension: Extension, records: Vec<ExtensionRecordDefinition>) -> Self {
+        Self { extension, records }
+    }
+
+    /// Returns the definition of the surface normals extension
+    /// with the records `nor:normalX`, `nor:normalY` and `nor:normalZ`.
+    pub fn normals() -> Self {
+        let records = ["normalX", "normalY", "normalZ"]
+            .iter()
+            .map(|n| ExtensionRecordDefinition::new(n, ExtensionValueKind::Float))
+            .collect();
+        Self::new(Extension::new("nor", Self::NORMALS_URL), records)
+    }
+
+    /// Returns the definition of the LAS attributes extension with the following records:
+    ///
+    /// - `las:classification`: Classification code as integer between 0 and 255.
+    /// - `las:scanAngle`: Scan angle in degrees between -180 and 180,
+    ///   stored as scaled integer with the resolution of LAS 1.4.
+    /// - `las:userData`: User data as integer between 0 and 255.
+    /// - `las:pointSourceId`: Point source ID as integer between 0 and 65535.
+    ///
+    /// All records have a recommended data type for [`Self::default_record()`].
+    /// LAS return numbers and counts are not part of this extension,
+    /// they map to the standard records [`RecordName::ReturnIndex`] with the return number minus one
+    /// and [`RecordName::ReturnCount`].
+    pub fn las() -> Self {
+        let record = |name: &str, kind, data_type| ExtensionRecordDefinition {
+            data_type: Some(data_type),
+            ..ExtensionRecordDefinition::new(name, kind)
+        };
+        let scan_angle = RecordDataType::ScaledInteger {
+            min: -30000,
+            max: 30000,
+            scale: 0.006,
+            offset: 0.0,
+        };
+        let records = vec![
+            record(
+                "classification",
+                ExtensionValueKind::Integer,
+                RecordDataType::U8,
+            ),
+            record("scanAngle", ExtensionValueKind::Float, scan_angle),
+            record("userData", ExtensionValueKind::Integer, RecordDataType::U8),
+            record(
+                "pointSourceId",
+                ExtensionValueKind::Integer,
+                RecordDataType::U16,
+            ),
+        ];
+        Self::new(Extension::new("las", Self::LAS_URL), records)
+    }
+
+    /// Creates a prototype record for one of the records defined by this extension
+    /// using the recommended data type of the record.
+    ///
+    /// Returns an error if the extension does not define a record with the given name
+    /// or if the record has no recommended data type.
+    pub fn default_record(&self, name: &str) -> Result<Record> {
+        let data_type = self
+            .records
+            .iter()
+            .find(|r| r.name == name)
+            .and_then(|r| r.data_type.clone())
+            .invalid_err(format!(
+                "The extension {} has no record called '{name}' with a recommended data type",
+                self.extension.url
+            ))?;
+        self.record(name, data_type)
+    }
+
+    /// Creates a prototype record for one of the records defined by this extension.
+    ///
+    /// Returns an error if the extension does not define a record with the given name.
+    pub fn record(&self, name: &str, data_type: RecordDataType) -> Result<Record> {
+        if !self.records.iter().any(|r| r.name == name) {
+            let url = &self.extension.url;
+            Error::invalid(format!(
+                "The extension {url} does not define a record called '{name}'"
+            ))?
+        }
+        Ok(Record {
+            name: RecordName::Unknown {
+                namespace: self.extension.namespace.clone(),
+                name: name.to_owned(),
+            },
+            data_type,
+        })
+    }
+}
+
+/// Decodes the records of known extensions from raw point values.
+///
+/// Use it together with [`PointCloudReaderRaw`](crate::PointCloudReaderRaw)
+/// to get typed values of extension records, for example surface normals.
+#[derive(Clone, Debug)]
+pub struct ExtensionDecoder {
+    fields: Vec<(usize, String, ExtensionValueKind, RecordDataType)>,
+}
+
+impl ExtensionDecoder {
+    /// Creates a decoder for all records of the point cloud that belong to one of the given known extensions.
+    ///
+    /// The extensions of the file are required to map the namespace prefixes used in the prototype to URLs.
+    /// Records of unknown extensions or with names not defined by the matching extension are ignored.
+    pub fn new(
+        pointcloud: &PointCloud,
+        file_extensions: &[Extension],
+        definitions: &[ExtensionDefinition],
+    ) -> Self {
+        let mut fields = Vec::new();
+        for (index, record) in pointcloud.prototype.iter().enumerate() {
+            if let RecordName::Unknown { namespace, name } = &record.name {
+                let definition = file_extensions
+                    .iter()
+                    .find(|e| &e.namespace == namespace)
+                    .and_then(|e| definitions.iter().find(|d| d.extension.url == e.url));
+                let record_def =
+                    definition.and_then(|d| d.records.iter().find(|r| &r.name == name));
+                if let Some(rd) = record_def {
+                    fields.push((index, rd.name.clone(), rd.kind, record.data_type.clone()));
+                }
+            }
+        }
+        Self { fields }
+    }
+
+    /// Returns the names of all decoded records in the order of the values returned by [`Self::decode()`].
+    pub fn names(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .map(|(_, name, _, _)| name.as_str())
+            .collect()
+    }
+
+    /// Returns true if the point cloud does not contain any records of the known extensions.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Decodes the extension records from the raw values of a single point.
+    pub fn decode(&self, values: &[RecordValue]) -> Result<Vec<ExtensionValue>> {
+        let mut decoded = Vec::with_capacity(self.fields.len());
+        for (index, name, kind, data_type) in &self.fields {
+            let value = values
+                .get(*index)
+                .invalid_err(format!("Missing value for extension record '{name}'"))?;
+            decoded.push(match kind {
+                ExtensionValueKind::Float => ExtensionValue::Float(value.to_f64(data_type)?),
+                ExtensionValueKind::Unit => ExtensionValue::Unit(value.to_unit_f32(data_type)?),
+                ExtensionValueKind::Integer => ExtensionValue::Integer(value.to_i64(data_type)?),
+                ExtensionValueKind::Boolean => {
+                    ExtensionValue::Boolean(value.to_i64(data_type)? != 0)
+                }
+            });
+        }
+        Ok(decoded)
+    }
+}
+
 #[cfg(test)]
+#[cfg(feature = "write")]
 mod tests {
     use super::*;
 
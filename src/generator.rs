@@ -0,0 +1,415 @@
+use crate::sampling::SplitMix64;
+use crate::{
+    E57Writer, Extension, ImageFormat, PinholeImageProperties, RawValues, Record, RecordDataType,
+    RecordName, Result, VisualReferenceImageProperties,
+};
+use std::io::{Read, Seek, Write};
+
+/// Minimal PNG image with a single transparent pixel used for generated images.
+const PNG_1X1: [u8; 68] = [
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F, 0x15, 0xC4,
+    0x89, 0x00, 0x00, 0x00, 0x0B, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x60, 0x00, 0x02, 0x00,
+    0x00, 0x05, 0x00, 0x01, 0x7A, 0x5E, 0xAB, 0x3F, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44,
+    0xAE, 0x42, 0x60, 0x82,
+];
+
+/// Namespace of the extension used by generated files.
+const NAMESPACE: &str = "gen";
+
+/// URL of the extension used by generated files, documented in the file `EXTENSIONS.md` of the repository.
+const NAMESPACE_URL: &str = "https://github.com/cry-inc/e57/blob/master/EXTENSIONS.md#generator";
+
+/// Number of columns used for generated row and column indices.
+const COLUMNS: u64 = 100;
+
+/// Options to control the characteristics of files created by [`generate()`].
+///
+/// The default options create a single point cloud with 1000 Cartesian points
+/// and all other attributes disabled.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GeneratorOptions {
+    /// Seed for the pseudo random point values and GUIDs.
+    /// The same options always generate identical files.
+    pub seed: u64,
+    /// Number of point clouds in the file.
+    pub pointclouds: usize,
+    /// Number of points in each point cloud.
+    /// Data packets have a limited size, so larger point clouds are split into multiple packets.
+    /// Zero creates empty point clouds.
+    pub points: u64,
+    /// Adds Cartesian coordinates to the point clouds.
+    pub cartesian: bool,
+    /// Adds spherical coordinates to the point clouds.
+    /// If combined with Cartesian coordinates, both describe the same locations.
+    pub spherical: bool,
+    /// Adds red, green and blue colors to the point clouds.
+    pub color: bool,
+    /// Adds intensity values to the point clouds.
+    pub intensity: bool,
+    /// Adds row and column indices to the point clouds.
+    pub row_column: bool,
+    /// Adds return count and return index values to the point clouds.
+    pub returns: bool,
+    /// Adds time stamps to the point clouds.
+    pub time_stamp: bool,
+    /// Adds invalid state records for all enabled attributes
+    /// and marks some points as invalid or direction-only.
+    pub invalid_states: bool,
+    /// Uses different data types for the records of consecutive point clouds.
+    ///
+    /// The first point cloud uses double coordinates and integer attributes,
+    /// the second one single precision floats for everything
+    /// and the third one scaled integers wherever they are allowed.
+    /// Use at least three point clouds to cover all record types.
+    /// Otherwise all point clouds use the types of the first point cloud.
+    pub all_record_types: bool,
+    /// Interval for data packets with the compressor restart flag, zero disables the flag.
+    /// See [`PointCloudWriter::set_compressor_restart_interval()`](crate::PointCloudWriter::set_compressor_restart_interval).
+    pub compressor_restart_interval: u64,
+    /// Number of images in the file.
+    /// Each image has a visual reference and a pinhole projection with a PNG image of a single pixel.
+    pub images: usize,
+    /// Registers an extension that adds a custom record with a sequence number
+    /// and some custom XML elements to all point clouds and images.
+    pub extension: bool,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            pointclouds: 1,
+            points: 1000,
+            cartesian: true,
+            spherical: false,
+            color: false,
+            intensity: false,
+            row_column: false,
+            returns: false,
+            time_stamp: false,
+            invalid_states: false,
+            all_record_types: false,
+            compressor_restart_interval: 0,
+            images: 0,
+            extension: false,
+        }
+    }
+}
+
+/// Generates a synthetic E57 file with the given characteristics.
+///
+/// The files are small and valid according to the E57 standard,
+/// which makes them useful for testing and fuzzing software that consumes E57 files.
+/// Use a mutable reference as writer to access the written data afterwards,
+/// for example with a `Cursor<Vec<u8>>`.
+/// Index packets are not generated, since they are optional and not supported by this library.
+pub fn generate<T: Read + Write + Seek>(writer: T, options: &GeneratorOptions) -> Result<()> {
+    let mut rng = SplitMix64::new(options.seed);
+    let file_guid = guid(options.seed, 0, 0);
+    let mut e57 = E57Writer::new(writer, &file_guid)?;
+    if options.extension {
+        e57.register_extesion(Extension::new(NAMESPACE, NAMESPACE_URL))?;
+    }
+
+    let mut pointcloud_guids = Vec::new();
+    for index in 0..options.pointclouds {
+        let variant = if options.all_record_types {
+            index % 3
+        } else {
+            0
+        };
+        let fields = fields(options);
+        let prototype = fields
+            .iter()
+            .map(|f| f.record(variant, options.points))
+            .collect();
+        let pc_guid = guid(options.seed, 1, index as u64);
+        let mut pc_writer = e57.add_pointcloud(&pc_guid, prototype)?;
+        pc_writer.set_name(Some(format!("Generated point cloud {index}")));
+        pc_writer.set_compressor_restart_interval(options.compressor_restart_interval);
+        if options.extension {
+            pc_writer.add_custom_xml(&format!(
+                "<{NAMESPACE}:variant type=\"Integer\">{variant}</{NAMESPACE}:variant>"
+            ))?;
+        }
+        for i in 0..options.points {
+            let sample = Sample::new(&mut rng, i, options.invalid_states);
+            let mut values = RawValues::with_capacity(fields.len());
+            for field in &fields {
+                let data_type = field.record(variant, options.points).data_type;
+                values.push(field.value(&data_type, &sample)?);
+            }
+            pc_writer.add_point(values)?;
+        }
+        pc_writer.finalize()?;
+        pointcloud_guids.push(pc_guid);
+    }
+
+    for index in 0..options.images {
+        let image_guid = guid(options.seed, 2, index as u64);
+        let mut img_writer = e57.add_image(&image_guid)?;
+        img_writer.set_name(&format!("Generated image {index}"));
+        if let Some(pc_guid) = pointcloud_guids.get(index % pointcloud_guids.len().max(1)) {
+            img_writer.set_pointcloud_guid(pc_guid);
+        }
+        if options.extension {
+            img_writer.add_custom_xml(&format!(
+                "<{NAMESPACE}:index type=\"Integer\">{index}</{NAMESPACE}:index>"
+            ))?;
+        }
+        let properties = VisualReferenceImageProperties {
+            width: 1,
+            height: 1,
+        };
+        img_writer.add_visual_reference(ImageFormat::Png, &mut &PNG_1X1[..], properties, None)?;
+        let properties = PinholeImageProperties {
+            width: 1,
+            height: 1,
+            focal_length: 0.01,
+            pixel_width: 0.00001,
+            pixel_height: 0.00001,
+            principal_x: 0.5,
+            principal_y: 0.5,
+        };
+        let mask = &mut &PNG_1X1[..];
+        img_writer.add_pinhole(ImageFormat::Png, &mut &PNG_1X1[..], properties, Some(mask))?;
+        img_writer.finalize()?;
+    }
+
+    e57.finalize()
+}
+
+/// Creates a deterministic GUID from the seed, the kind of object and its index.
+fn guid(seed: u64, kind: u16, index: u64) -> String {
+    let high = (seed >> 32) as u32;
+    let low = seed as u16;
+    format!("{{{high:08X}-{low:04X}-4{kind:03X}-8000-{index:012X}}}")
+}
+
+/// Attributes of the records that can be generated.
+#[derive(Clone, Copy)]
+enum Field {
+    CartesianX,
+    CartesianY,
+    CartesianZ,
+    CartesianInvalidState,
+    SphericalRange,
+    SphericalAzimuth,
+    SphericalElevation,
+    SphericalInvalidState,
+    ColorRed,
+    ColorGreen,
+    ColorBlue,
+    IsColorInvalid,
+    Intensity,
+    IsIntensityInvalid,
+    RowIndex,
+    ColumnIndex,
+    ReturnCount,
+    ReturnIndex,
+    TimeStamp,
+    IsTimeStampInvalid,
+    Sequence,
+}
+
+/// Returns the list of generated records for the given options.
+fn fields(options: &GeneratorOptions) -> Vec<Field> {
+    let invalid = options.invalid_states;
+    let mut fields = Vec::new();
+    if options.cartesian {
+        fields.extend([Field::CartesianX, Field::CartesianY, Field::CartesianZ]);
+        if invalid {
+            fields.push(Field::CartesianInvalidState);
+        }
+    }
+    if options.spherical {
+        fields.extend([
+            Field::SphericalRange,
+            Field::SphericalAzimuth,
+            Field::SphericalElevation,
+        ]);
+        if invalid {
+            fields.push(Field::SphericalInvalidState);
+        }
+    }
+    if options.color {
+        fields.extend([Field::ColorRed, Field::ColorGreen, Field::ColorBlue]);
+        if invalid {
+            fields.push(Field::IsColorInvalid);
+        }
+    }
+    if options.intensity {
+        fields.push(Field::Intensity);
+        if invalid {
+            fields.push(Field::IsIntensityInvalid);
+        }
+    }
+    if options.row_column {
+        fields.extend([Field::RowIndex, Field::ColumnIndex]);
+    }
+    if options.returns {
+        fields.extend([Field::ReturnCount, Field::ReturnIndex]);
+    }
+    if options.time_stamp {
+        fields.push(Field::TimeStamp);
+        if invalid {
+            fields.push(Field::IsTimeStampInvalid);
+        }
+    }
+    if options.extension {
+        fields.push(Field::Sequence);
+    }
+    fields
+}
+
+impl Field {
+    /// Returns the record for this field with the data type of the given variant.
+    fn record(self, variant: usize, points: u64) -> Record {
+        let float = |min: Option<f64>, max: Option<f64>| match variant {
+            1 => RecordDataType::Single {
+                min: min.map(|v| v as f32),
+                max: max.map(|v| v as f32),
+            },
+            _ => RecordDataType::Double { min, max },
+        };
+        let scaled = |min: f64, max: f64, scale: f64| match variant {
+            2 => RecordDataType::ScaledInteger {
+                min: (min / scale) as i64,
+                max: (max / scale) as i64,
+                scale,
+                offset: 0.0,
+            },
+            _ => float(Some(min), Some(max)),
+        };
+        let unit = |max: i64| match variant {
+            0 => RecordDataType::Integer { min: 0, max },
+            _ => scaled(0.0, 1.0, 0.001),
+        };
+        let integer = |min: i64, max: i64| RecordDataType::Integer { min, max };
+        let (name, data_type) = match self {
+            Field::CartesianX => (RecordName::CartesianX, scaled(-1000.0, 1000.0, 0.0001)),
+            Field::CartesianY => (RecordName::CartesianY, scaled(-1000.0, 1000.0, 0.0001)),
+            Field::CartesianZ => (RecordName::CartesianZ, scaled(-1000.0, 1000.0, 0.0001)),
+            Field::CartesianInvalidState => (RecordName::CartesianInvalidState, integer(0, 2)),
+            Field::SphericalRange => (RecordName::SphericalRange, scaled(0.0, 1000.0, 0.0001)),
+            Field::SphericalAzimuth => (RecordName::SphericalAzimuth, float(None, None)),
+            Field::SphericalElevation => (RecordName::SphericalElevation, float(None, None)),
+            Field::SphericalInvalidState => (RecordName::SphericalInvalidState, integer(0, 2)),
+            Field::ColorRed => (RecordName::ColorRed, unit(255)),
+            Field::ColorGreen => (RecordName::ColorGreen, unit(255)),
+            Field::ColorBlue => (RecordName::ColorBlue, unit(255)),
+            Field::IsColorInvalid => (RecordName::IsColorInvalid, integer(0, 1)),
+            Field::Intensity => (RecordName::Intensity, unit(u16::MAX as i64)),
+            Field::IsIntensityInvalid => (RecordName::IsIntensityInvalid, integer(0, 1)),
+            Field::RowIndex => (RecordName::RowIndex, integer(0, (points / COLUMNS) as i64)),
+            Field::ColumnIndex => (RecordName::ColumnIndex, integer(0, COLUMNS as i64 - 1)),
+            Field::ReturnCount => (RecordName::ReturnCount, integer(1, 3)),
+            Field::ReturnIndex => (RecordName::ReturnIndex, integer(0, 2)),
+            Field::TimeStamp => (RecordName::TimeStamp, scaled(0.0, 100000.0, 0.000001)),
+            Field::IsTimeStampInvalid => (RecordName::IsTimeStampInvalid, integer(0, 1)),
+            Field::Sequence => (
+                RecordName::Unknown {
+                    namespace: NAMESPACE.to_owned(),
+                    name: String::from("sequence"),
+                },
+                integer(0, points as i64),
+            ),
+        };
+        Record { name, data_type }
+    }
+
+    /// Returns the value of this field for the given sample point.
+    fn value(self, dt: &RecordDataType, s: &Sample) -> Result<crate::RecordValue> {
+        match self {
+            Field::CartesianX => dt.value_from_f64(s.cartesian[0]),
+            Field::CartesianY => dt.value_from_f64(s.cartesian[1]),
+            Field::CartesianZ => dt.value_from_f64(s.cartesian[2]),
+            Field::CartesianInvalidState => dt.value_from_f64(s.cartesian_state as f64),
+            Field::SphericalRange => dt.value_from_f64(s.spherical[0]),
+            Field::SphericalAzimuth => dt.value_from_f64(s.spherical[1]),
+            Field::SphericalElevation => dt.value_from_f64(s.spherical[2]),
+            Field::SphericalInvalidState => dt.value_from_f64(s.spherical_state as f64),
+            Field::ColorRed => dt.value_from_unit_f32(s.color[0]),
+            Field::ColorGreen => dt.value_from_unit_f32(s.color[1]),
+            Field::ColorBlue => dt.value_from_unit_f32(s.color[2]),
+            Field::IsColorInvalid => dt.value_from_f64(s.color_invalid as u8 as f64),
+            Field::Intensity => dt.value_from_unit_f32(s.intensity),
+            Field::IsIntensityInvalid => dt.value_from_f64(s.intensity_invalid as u8 as f64),
+            Field::RowIndex => dt.value_from_f64((s.index / COLUMNS) as f64),
+            Field::ColumnIndex => dt.value_from_f64((s.index % COLUMNS) as f64),
+            Field::ReturnCount => dt.value_from_f64(s.returns.0 as f64),
+            Field::ReturnIndex => dt.value_from_f64(s.returns.1 as f64),
+            Field::TimeStamp => dt.value_from_f64(s.time_stamp),
+            Field::IsTimeStampInvalid => dt.value_from_f64(s.time_stamp_invalid as u8 as f64),
+            Field::Sequence => dt.value_from_f64(s.index as f64),
+        }
+    }
+}
+
+/// Values of a single generated point, independent of the record types.
+struct Sample {
+    index: u64,
+    cartesian: [f64; 3],
+    cartesian_state: u8,
+    spherical: [f64; 3],
+    spherical_state: u8,
+    color: [f32; 3],
+    color_invalid: bool,
+    intensity: f32,
+    intensity_invalid: bool,
+    returns: (u8, u8),
+    time_stamp: f64,
+    time_stamp_invalid: bool,
+}
+
+impl Sample {
+    fn new(rng: &mut SplitMix64, index: u64, invalid_states: bool) -> Self {
+        let range = 1.0 + rng.next_random() * 99.0;
+        let azimuth = (rng.next_random() * 2.0 - 1.0) * std::f64::consts::PI;
+        let elevation = (rng.next_random() - 0.5) * 2.8;
+        let direction = [
+            elevation.cos() * azimuth.cos(),
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+        ];
+        let color = [
+            rng.next_random() as f32,
+            rng.next_random() as f32,
+            rng.next_random() as f32,
+        ];
+        let intensity = rng.next_random() as f32;
+        let return_count = 1 + (rng.next_random() * 3.0) as u8;
+        let return_index = (rng.next_random() * return_count as f64) as u8;
+
+        // Invalid states are distributed over the points with different periods
+        let check = |period: u64, offset: u64| invalid_states && index % period == offset;
+        let state = if check(7, 3) {
+            2
+        } else if check(7, 5) {
+            1
+        } else {
+            0
+        };
+        let (cartesian, spherical) = match state {
+            0 => (direction.map(|v| v * range), [range, azimuth, elevation]),
+            1 => (direction, [0.0, azimuth, elevation]),
+            _ => ([0.0; 3], [0.0; 3]),
+        };
+        Self {
+            index,
+            cartesian,
+            cartesian_state: state,
+            spherical,
+            spherical_state: state,
+            color,
+            color_invalid: check(11, 4),
+            intensity,
+            intensity_invalid: check(13, 6),
+            returns: (return_count, return_index),
+            time_stamp: index as f64 * 0.001,
+            time_stamp_invalid: check(17, 8),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{Quaternion, Transform, Translation};
+use glam::{DMat4, DQuat, DVec3, Mat4};
+
+impl From<&Quaternion> for DQuat {
+    fn from(q: &Quaternion) -> Self {
+        DQuat::from_xyzw(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl From<&DQuat> for Quaternion {
+    fn from(q: &DQuat) -> Self {
+        Self {
+            w: q.w,
+            x: q.x,
+            y: q.y,
+            z: q.z,
+        }
+    }
+}
+
+impl From<&Translation> for DVec3 {
+    fn from(t: &Translation) -> Self {
+        DVec3::new(t.x, t.y, t.z)
+    }
+}
+
+impl From<&DVec3> for Translation {
+    fn from(v: &DVec3) -> Self {
+        Self {
+            x: v.x,
+            y: v.y,
+            z: v.z,
+        }
+    }
+}
+
+impl From<&Transform> for DMat4 {
+    /// Creates a rigid body transformation matrix.
+    /// The rotation quaternion is normalized before the conversion.
+    fn from(t: &Transform) -> Self {
+        let rotation = DQuat::from(&t.rotation).normalize();
+        DMat4::from_rotation_translation(rotation, (&t.translation).into())
+    }
+}
+
+impl From<&Transform> for Mat4 {
+    /// Creates a single precision rigid body transformation matrix.
+    /// Be aware that large translations will lose precision.
+    fn from(t: &Transform) -> Self {
+        DMat4::from(t).as_mat4()
+    }
+}
+
+impl TryFrom<&DMat4> for Transform {
+    type Error = crate::Error;
+
+    /// Extracts rotation and translation from a matrix.
+    /// Fails if the matrix contains scaling, shearing or projective components.
+    fn try_from(m: &DMat4) -> crate::Result<Self> {
+        let (scale, rotation, translation) = m.to_scale_rotation_translation();
+        let rigid = (scale - DVec3::ONE).abs().max_element() < 1e-9
+            && m.row(3).abs_diff_eq(glam::DVec4::W, 1e-9)
+            && DMat4::from_rotation_translation(rotation, translation).abs_diff_eq(*m, 1e-9);
+        if !rigid {
+            crate::Error::invalid("Matrix is not a rigid body transformation")?
+        }
+        Ok(Self {
+            rotation: (&rotation).into(),
+            translation: (&translation).into(),
+        })
+    }
+}
+
+impl From<Quaternion> for DQuat {
+    fn from(q: Quaternion) -> Self {
+        (&q).into()
+    }
+}
+
+impl From<DQuat> for Quaternion {
+    fn from(q: DQuat) -> Self {
+        (&q).into()
+    }
+}
+
+impl From<Translation> for DVec3 {
+    fn from(t: Translation) -> Self {
+        (&t).into()
+    }
+}
+
+impl From<DVec3> for Translation {
+    fn from(v: DVec3) -> Self {
+        (&v).into()
+    }
+}
+
+impl From<Transform> for DMat4 {
+    /// Creates a rigid body transformation matrix.
+    /// The rotation quaternion is normalized before the conversion.
+    fn from(t: Transform) -> Self {
+        (&t).into()
+    }
+}
+
+impl From<Transform> for Mat4 {
+    /// Creates a single precision rigid body transformation matrix.
+    /// Be aware that large translations will lose precision.
+    fn from(t: Transform) -> Self {
+        (&t).into()
+    }
+}
+
+impl TryFrom<DMat4> for Transform {
+    type Error = crate::Error;
+
+    /// Extracts rotation and translation from a matrix.
+    /// Fails if the matrix contains scaling, shearing or projective components.
+    fn try_from(m: DMat4) -> crate::Result<Self> {
+        Self::try_from(&m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform() -> Transform {
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        Transform {
+            rotation: Quaternion {
+                w: s,
+                x: 0.0,
+                y: 0.0,
+                z: s,
+            },
+            translation: Translation {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        }
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let t = transform();
+        let m = DMat4::from(&t);
+        let p = m.transform_point3(DVec3::X);
+        assert!(p.abs_diff_eq(DVec3::new(1.0, 3.0, 3.0), 1e-9));
+
+        let expected = t.to_matrix();
+        for (r, row) in expected.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                assert!((m.row(r)[c] - value).abs() < 1e-12);
+            }
+        }
+
+        let back = Transform::try_from(&m).unwrap();
+        assert!((back.rotation.w - t.rotation.w).abs() < 1e-12);
+        assert!((back.translation.z - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn owned_conversions() {
+        let t = transform();
+        let rotation: DQuat = t.rotation.clone().into();
+        let translation: DVec3 = t.translation.clone().into();
+        let m: DMat4 = t.clone().into();
+        assert!(m.abs_diff_eq(
+            DMat4::from_rotation_translation(rotation, translation),
+            1e-12
+        ));
+        let single: Mat4 = t.into();
+        assert_eq!(single.w_axis.y, 2.0);
+        let back = Transform::try_from(m).unwrap();
+        assert_eq!(Translation::from(translation).z, back.translation.z);
+        assert!((Quaternion::from(rotation).w - back.rotation.w).abs() < 1e-12);
+    }
+
+    #[test]
+    fn scaled_matrix_is_rejected() {
+        let m = DMat4::from_scale(DVec3::splat(2.0));
+        assert!(Transform::try_from(&m).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+#[cfg(feature = "write")]
+use crate::bs_write::ByteStreamWriteBuffer;
+#[cfg(feature = "write")]
+use crate::cv_section::CompressedVectorSectionHeader;
+#[cfg(any(feature = "read", feature = "write"))]
+use crate::error::Converter;
+#[cfg(feature = "read")]
+use crate::paged_reader::PagedReader;
+#[cfg(feature = "write")]
+use crate::paged_writer::PagedWriter;
+#[cfg(feature = "write")]
+use crate::pc_writer::write_data_packet;
+#[cfg(feature = "read")]
+use crate::queue_reader::QueueReader;
+#[cfg(feature = "write")]
+use crate::record::serialize_record_type;
+#[cfg(any(feature = "read", feature = "write"))]
+use crate::xml;
+#[cfg(feature = "write")]
+use crate::Error;
+#[cfg(feature = "read")]
+use crate::ResourceLimits;
+use crate::{CartesianBounds, SphericalBounds};
+#[cfg(feature = "read")]
+use crate::{PointCloud, Record, RecordName};
+#[cfg(any(feature = "read", feature = "write"))]
+use crate::{RecordDataType, RecordValue, Result};
+#[cfg(feature = "read")]
+use roxmltree::Node;
+#[cfg(feature = "write")]
+use std::io::Write;
+#[cfg(any(feature = "read", feature = "write"))]
+use std::io::{Read, Seek};
+
+#[cfg(any(feature = "read", feature = "write"))]
+const ID_ELEMENT_VALUE: &str = "idElementValue";
+#[cfg(any(feature = "read", feature = "write"))]
+const START_POINT_INDEX: &str = "startPointIndex";
+#[cfg(any(feature = "read", feature = "write"))]
+const POINT_COUNT: &str = "pointCount";
+#[cfg(any(feature = "read", feature = "write"))]
+const CARTESIAN_BOUNDS: [&str; 6] = [
+    "cartesianBounds/xMinimum",
+    "cartesianBounds/xMaximum",
+    "cartesianBounds/yMinimum",
+    "cartesianBounds/yMaximum",
+    "cartesianBounds/zMinimum",
+    "cartesianBounds/zMaximum",
+];
+#[cfg(any(feature = "read", feature = "write"))]
+const SPHERICAL_BOUNDS: [&str; 6] = [
+    "sphericalBounds/rangeMinimum",
+    "sphericalBounds/rangeMaximum",
+    "sphericalBounds/elevationMinimum",
+    "sphericalBounds/elevationMaximum",
+    "sphericalBounds/azimuthStart",
+    "sphericalBounds/azimuthEnd",
+];
+
+/// Descriptor for the optional grouping of the points of a point cloud into lines.
+///
+/// Mobile and line scanners typically organize points into scan lines.
+/// The actual groups are stored in a binary section and can be read with
+/// [`E57Reader::line_groups()`](crate::E57Reader::line_groups).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct LineGrouping {
+    /// Name of the point attribute that identifies the lines, for example `columnIndex`.
+    pub id_element_name: String,
+    /// Physical file offset of the start of the associated binary section.
+    pub file_offset: u64,
+    /// Number of line groups.
+    pub records: u64,
+    /// Flattened group prototype with the slash-separated paths of all leaf elements.
+    #[cfg(any(feature = "read", feature = "write"))]
+    prototype: Vec<(String, RecordDataType)>,
+}
+
+/// Describes a group of consecutive points belonging to the same line.
+#[derive(Clone, Debug, Default)]
+pub struct LineGroup {
+    /// Value of the identifying point attribute shared by all points of this line.
+    pub id_element_value: i64,
+    /// Index of the first point of the line in the point cloud.
+    pub start_point_index: i64,
+    /// Number of points in the line.
+    pub point_count: i64,
+    /// Optional Cartesian bounds of all points of the line.
+    pub cartesian_bounds: Option<CartesianBounds>,
+    /// Optional spherical bounds of all points of the line.
+    pub spherical_bounds: Option<SphericalBounds>,
+}
+
+impl LineGroup {
+    #[cfg(any(feature = "read", feature = "write"))]
+    pub(crate) fn scale(&mut self, factor: f64) {
+        if let Some(b) = &mut self.cartesian_bounds {
+            b.scale(factor);
+        }
+        if let Some(b) = &mut self.spherical_bounds {
+            b.scale(factor);
+        }
+    }
+}
+
+impl LineGrouping {
+    #[cfg(feature = "read")]
+    pub(crate) fn from_node(node: &Node) -> Result<Option<Self>> {
+        let line_node = node
+            .children()
+            .find(|n| n.has_tag_name("pointGroupingSchemes"))
+            .and_then(|n| n.children().find(|n| n.has_tag_name("groupingByLine")));
+        let line_node = if let Some(line_node) = line_node {
+            line_node
+        } else {
+            return Ok(None);
+        };
+
+        let id_element_name = xml::req_string(&line_node, "idElementName")?;
+        let groups_node = line_node
+            .children()
+            .find(|n| n.has_tag_name("groups") && n.attribute("type") == Some("CompressedVector"))
+            .invalid_err("Cannot find 'groups' tag inside 'groupingByLine' tag")?;
+        let file_offset = groups_node
+            .attribute("fileOffset")
+            .invalid_err("Cannot find 'fileOffset' attribute in 'groups' tag")?
+            .parse::<u64>()
+            .invalid_err("Cannot parse 'fileOffset' attribute value as u64")?;
+        let records = groups_node
+            .attribute("recordCount")
+            .invalid_err("Cannot find 'recordCount' attribute in 'groups' tag")?
+            .parse::<u64>()
+            .invalid_err("Cannot parse 'recordCount' attribute value as u64")?;
+        let prototype_node = groups_node
+            .children()
+            .find(|n| n.has_tag_name("prototype") && n.attribute("type") == Some("Structure"))
+            .invalid_err("Cannot find 'prototype' child in 'groups' tag")?;
+
+        let mut prototype = Vec::new();
+        flatten_prototype(&prototype_node, "", &mut prototype)?;
+
+        Ok(Some(Self {
+            id_element_name,
+            file_offset,
+            records,
+            prototype,
+        }))
+    }
+
+    #[cfg(feature = "write")]
+    pub(crate) fn xml_string(&self) -> String {
+        let mut xml = String::new();
+        xml += "<pointGroupingSchemes type=\"Structure\">\n";
+        xml += "<groupingByLine type=\"Structure\">\n";
+        xml += &xml::gen_string("idElementName", &self.id_element_name);
+        xml += &format!(
+            "<groups type=\"CompressedVector\" fileOffset=\"{}\" recordCount=\"{}\">\n",
+            self.file_offset, self.records
+        );
+        xml += "<prototype type=\"Structure\">\n";
+        let mut open_struct: Option<&str> = None;
+        for (path, data_type) in &self.prototype {
+            let (parent, name) = match path.split_once('/') {
+                Some((parent, name)) => (Some(parent), name),
+                None => (None, path.as_str()),
+            };
+            if open_struct != parent {
+                if let Some(open) = open_struct {
+                    xml += &format!("</{open}>\n");
+                }
+                if let Some(parent) = parent {
+                    xml += &format!("<{parent} type=\"Structure\">\n");
+                }
+                open_struct = parent;
+            }
+            let (attrs, value) = serialize_record_type(data_type);
+            xml += &format!("<{name} {attrs}>{value}</{name}>\n");
+        }
+        if let Some(open) = open_struct {
+            xml += &format!("</{open}>\n");
+        }
+        xml += "</prototype>\n";
+        xml += "<codecs type=\"Vector\" allowHeterogeneousChildren=\"1\">\n</codecs>\n";
+        xml += "</groups>\n";
+        xml += "</groupingByLine>\n";
+        xml += "</pointGroupingSchemes>\n";
+        xml
+    }
+
+    #[cfg(feature = "read")]
+    pub(crate) fn read_groups<T: Read + Seek>(
+        &self,
+        reader: &mut PagedReader<T>,
+        limits: ResourceLimits,
+    ) -> Result<Vec<LineGroup>> {
+        // Reuse the point reading infrastructure with a prototype describing the group records
+        let prototype: Vec<Record> = self
+            .prototype
+            .iter()
+            .map(|(path, data_type)| Record {
+                name: RecordName::Unknown {
+                    namespace: String::new(),
+                    name: path.clone(),
+                },
+                data_type: data_type.clone(),
+            })
+            .collect();
+        let pc = PointCloud {
+            file_offset: self.file_offset,
+            records: self.records,
+            prototype,
+            ..Default::default()
+        };
+
+        let find = |name: &str| self.prototype.iter().position(|(p, _)| p == name);
+        let find_all =
+            |names: &[&str; 6]| -> Option<Vec<usize>> { names.iter().map(|n| find(n)).collect() };
+        let id_index = find(ID_ELEMENT_VALUE);
+        let start_index = find(START_POINT_INDEX)
+            .invalid_err("Line group prototype does not contain a start point index")?;
+        let count_index =
+            find(POINT_COUNT).invalid_err("Line group prototype does not contain a point count")?;
+        let cartesian_indices = find_all(&CARTESIAN_BOUNDS);
+        let spherical_indices = find_all(&SPHERICAL_BOUNDS);
+
+        let mut queue_reader = QueueReader::new(&pc, reader, limits)?;
+        let mut values = Vec::with_capacity(pc.prototype.len());
+        // The group count comes from the XML section and cannot be trusted for preallocation
+        let mut groups = Vec::new();
+        let proto = &pc.prototype;
+        let to_f64 = |values: &[RecordValue], indices: &Option<Vec<usize>>| -> Result<_> {
+            if let Some(indices) = indices {
+                let mut result = [0.0; 6];
+                for (i, index) in indices.iter().enumerate() {
+                    result[i] = values[*index].to_f64(&proto[*index].data_type)?;
+                }
+                Ok(Some(result))
+            } else {
+                Ok(None)
+            }
+        };
+        while (groups.len() as u64) < self.records {
+            if queue_reader.available() < 1 {
+                queue_reader.advance()?;
+                continue;
+            }
+            queue_reader.pop_point(&mut values)?;
+            let id_element_value = if let Some(index) = id_index {
+                values[index].to_i64(&proto[index].data_type)?
+            } else {
+                0
+            };
+            let cartesian_bounds = to_f64(&values, &cartesian_indices)?.map(|b| CartesianBounds {
+                x_min: Some(b[0]),
+                x_max: Some(b[1]),
+                y_min: Some(b[2]),
+                y_max: Some(b[3]),
+                z_min: Some(b[4]),
+                z_max: Some(b[5]),
+            });
+            let spherical_bounds = to_f64(&values, &spherical_indices)?.map(|b| SphericalBounds {
+                range_min: Some(b[0]),
+                range_max: Some(b[1]),
+                elevation_min: Some(b[2]),
+                elevation_max: Some(b[3]),
+                azimuth_start: Some(b[4]),
+                azimuth_end: Some(b[5]),
+            });
+            groups.push(LineGroup {
+                id_element_value,
+                start_point_index: values[start_index].to_i64(&proto[start_index].data_type)?,
+                point_count: values[count_index].to_i64(&proto[count_index].data_type)?,
+                cartesian_bounds,
+                spherical_bounds,
+            });
+        }
+        Ok(groups)
+    }
+
+    /// Writes a new binary section with all groups and returns the descriptor for it.
+    #[cfg(feature = "write")]
+    pub(crate) fn write_groups<T: Read + Write + Seek>(
+        writer: &mut PagedWriter<T>,
+        id_element_name: &str,
+        groups: &[LineGroup],
+    ) -> Result<Self> {
+        // Bounds are optional, but need to be consistent for all groups
+        let has_cartesian = groups.iter().any(|g| g.cartesian_bounds.is_some());
+        let has_spherical = groups.iter().any(|g| g.spherical_bounds.is_some());
+        if has_cartesian && !groups.iter().all(|g| g.cartesian_bounds.is_some()) {
+            Error::invalid("Either all or no line groups must have Cartesian bounds")?
+        }
+        if has_spherical && !groups.iter().all(|g| g.spherical_bounds.is_some()) {
+            Error::invalid("Either all or no line groups must have spherical bounds")?
+        }
+
+        // All types use 64 bits so that the byte streams never contain any partial bytes
+        let int_type = RecordDataType::Integer {
+            min: i64::MIN,
+            max: i64::MAX,
+        };
+        let float_type = RecordDataType::Double {
+            min: None,
+            max: None,
+        };
+        let mut prototype = vec![
+            (ID_ELEMENT_VALUE.to_owned(), int_type.clone()),
+            (START_POINT_INDEX.to_owned(), int_type.clone()),
+            (POINT_COUNT.to_owned(), int_type),
+        ];
+        if has_cartesian {
+            for path in CARTESIAN_BOUNDS {
+                prototype.push((path.to_owned(), float_type.clone()));
+            }
+        }
+        if has_spherical {
+            for path in SPHERICAL_BOUNDS {
+                prototype.push((path.to_owned(), float_type.clone()));
+            }
+        }
+
+        let section_offset = writer.physical_position()?;
+        let mut section_header = CompressedVectorSectionHeader::default();
+        section_header.section_length = CompressedVectorSectionHeader::SIZE;
+        section_header.write(writer)?;
+        // The header might cross a page boundary, so the data offset must include the checksum
+        section_header.data_offset = writer.physical_position()?;
+
+        let group_size: usize = prototype.iter().map(|(_, dt)| dt.bit_size()).sum();
+        let max_groups_per_packet = (64000 * 8) / group_size;
+        for chunk in groups.chunks(max_groups_per_packet) {
+            let mut buffers = vec![ByteStreamWriteBuffer::new(); prototype.len()];
+            for group in chunk {
+                let mut values = vec![
+                    RecordValue::Integer(group.id_element_value),
+                    RecordValue::Integer(group.start_point_index),
+                    RecordValue::Integer(group.point_count),
+                ];
+                if let Some(b) = &group.cartesian_bounds {
+                    for v in [b.x_min, b.x_max, b.y_min, b.y_max, b.z_min, b.z_max] {
+                        let v =
+                            v.invalid_err("Cartesian bounds of line groups must be complete")?;
+                        values.push(RecordValue::Double(v));
+                    }
+                }
+                if let Some(b) = &group.spherical_bounds {
+                    let bounds = [
+                        b.range_min,
+                        b.range_max,
+                        b.elevation_min,
+                        b.elevation_max,
+                        b.azimuth_start,
+                        b.azimuth_end,
+                    ];
+                    for v in bounds {
+                        let v =
+                            v.invalid_err("Spherical bounds of line groups must be complete")?;
+                        values.push(RecordValue::Double(v));
+                    }
+                }
+                for (i, (_, data_type)) in prototype.iter().enumerate() {
+                    data_type.write(&values[i], &mut buffers[i])?;
+                }
+            }
+            section_header.section_length += write_data_packet(writer, &mut buffers, true, false)?;
+        }
+
+        // Sections without any data packets use a data offset of zero
+        if groups.is_empty() {
+            section_header.data_offset = 0;
+        }
+
+        // Update section header with the final length
+        let end_offset = writer.physical_position()?;
+        writer.physical_seek(section_offset)?;
+        section_header.write(writer)?;
+        writer.physical_seek(end_offset)?;
+
+        Ok(Self {
+            id_element_name: id_element_name.to_owned(),
+            file_offset: section_offset,
+            records: groups.len() as u64,
+            prototype,
+        })
+    }
+}
+
+#[cfg(feature = "read")]
+fn flatten_prototype(
+    node: &Node,
+    parent: &str,
+    prototype: &mut Vec<(String, RecordDataType)>,
+) -> Result<()> {
+    for n in node.children() {
+        if !n.is_element() {
+            continue;
+        }
+        let name = n.tag_name().name();
+        let path = if parent.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{parent}/{name}")
+        };
+        if n.attribute("type") == Some("Structure") {
+            flatten_prototype(&n, &path, prototype)?;
+        } else {
+            prototype.push((path, RecordDataType::from_node(&n)?));
+        }
+    }
+    Ok(())
+}
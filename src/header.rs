@@ -37,9 +37,20 @@ pub struct Header {
 }
 
 impl Header {
+    /// Size of the E57 file header structure in bytes.
+    pub const SIZE: usize = 48;
+
+    /// Checks if the supplied data starts with the E57 file signature.
+    ///
+    /// This is a very cheap check that can be used to detect E57 files,
+    /// for example by only looking at the first few bytes of an upload.
+    pub fn has_signature(data: &[u8]) -> bool {
+        data.starts_with(SIGNATURE)
+    }
+
     /// Reads an E57 file header structure.
     pub fn read(reader: &mut dyn Read) -> Result<Self> {
-        let mut data = [0_u8; 48];
+        let mut data = [0_u8; Self::SIZE];
         reader
             .read_exact(&mut data)
             .read_err("Failed to read E57 file header")?;
@@ -57,16 +68,16 @@ impl Header {
         };
 
         if &header.signature != SIGNATURE {
-            Error::invalid("Found unsupported signature in header")?
+            Error::invalid("Found invalid signature in header, this is not an E57 file")?
         }
         if header.major != MAJOR_VERSION {
-            Error::invalid("Found unsupported major version in header")?
+            Error::not_implemented("Found unsupported major version in header")?
         }
         if header.minor != MINOR_VERSION {
-            Error::invalid("Found unsupported minor version in header")?
+            Error::not_implemented("Found unsupported minor version in header")?
         }
         if header.page_size != PAGE_SIZE {
-            Error::invalid("Found unsupported page size in header")?
+            Error::not_implemented("Found unsupported page size in header")?
         }
 
         Ok(header)
@@ -1,9 +1,11 @@
 use crate::paged_writer::PagedWriter;
+use crate::pc_writer::rollback_section;
 use crate::Blob;
 use crate::CylindricalImage;
 use crate::CylindricalImageProperties;
 use crate::DateTime;
 use crate::Error;
+use crate::Extension;
 use crate::Image;
 use crate::ImageBlob;
 use crate::ImageFormat;
@@ -22,18 +24,33 @@ use std::io::{Read, Seek, Write};
 pub struct ImageWriter<'a, T: Read + Write + Seek> {
     writer: &'a mut PagedWriter<T>,
     images: &'a mut Vec<Image>,
+    open_section: &'a mut Option<u64>,
+    extensions: &'a [Extension],
+    section_offset: u64,
     image: Image,
+    unit_scale: f64,
+    done: bool,
 }
 
 impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
     pub(crate) fn new(
         writer: &'a mut PagedWriter<T>,
         images: &'a mut Vec<Image>,
+        open_section: &'a mut Option<u64>,
+        extensions: &'a [Extension],
         guid: &str,
+        unit_scale: f64,
     ) -> Result<Self> {
+        let section_offset = writer.physical_position()?;
+        *open_section = Some(section_offset);
         Ok(Self {
             writer,
             images,
+            open_section,
+            extensions,
+            section_offset,
+            unit_scale,
+            done: false,
             image: Image {
                 guid: Some(guid.to_owned()),
                 visual_reference: None,
@@ -46,6 +63,8 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
                 sensor_vendor: None,
                 sensor_model: None,
                 sensor_serial: None,
+                custom_xml: Vec::new(),
+                custom_attributes: Vec::new(),
             },
         })
     }
@@ -99,6 +118,27 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
         self.image.sensor_serial = Some(value.to_owned());
     }
 
+    /// Adds a custom XML element as child of the image element.
+    ///
+    /// The element must use the namespace of a registered extension and needs to be well-formed XML.
+    /// It will be included unchanged in the XML section of the file.
+    /// This can be used to preserve the custom XML of an image read from another file.
+    pub fn add_custom_xml(&mut self, xml: &str) -> Result<()> {
+        Extension::validate_custom_xml(xml, self.extensions)?;
+        self.image.custom_xml.push(xml.to_owned());
+        Ok(())
+    }
+
+    /// Adds a custom XML attribute to the image element, for example `ven:id="42"`.
+    ///
+    /// The attribute must use the namespace of a registered extension and needs to be well-formed XML.
+    /// This can be used to preserve the custom attributes of an image read from another file.
+    pub fn add_custom_attribute(&mut self, attribute: &str) -> Result<()> {
+        Extension::validate_custom_attribute(attribute, self.extensions)?;
+        self.image.custom_attributes.push(attribute.to_owned());
+        Ok(())
+    }
+
     /// Adds an optional visual reference image, also known as preview image.
     /// See also `VisualReferenceImageProperties` struct for more details.
     /// The optional PNG mask image can be used to indicate valid/invalid
@@ -106,6 +146,7 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
     /// The mask must have the same size as the actual image.
     /// Non-zero-valued pixels mark valid pixel locations and
     /// zero-valued pixels mark invalid pixels.
+    /// The visual reference can be combined with one of the projected representations.
     pub fn add_visual_reference(
         &mut self,
         format: ImageFormat,
@@ -113,6 +154,10 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
         properties: VisualReferenceImageProperties,
         mask: Option<&mut dyn Read>,
     ) -> Result<()> {
+        self.check_not_done()?;
+        if self.image.visual_reference.is_some() {
+            Error::invalid("A visual reference image is already set")?
+        }
         let data = Blob::write(self.writer, image)?;
         let blob = ImageBlob { data, format };
         let mask = if let Some(mask_data) = mask {
@@ -143,6 +188,7 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
         properties: PinholeImageProperties,
         mask: Option<&mut dyn Read>,
     ) -> Result<()> {
+        self.check_not_done()?;
         if self.image.projection.is_some() {
             Error::invalid("A projected image is already set")?
         }
@@ -176,6 +222,7 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
         properties: SphericalImageProperties,
         mask: Option<&mut dyn Read>,
     ) -> Result<()> {
+        self.check_not_done()?;
         if self.image.projection.is_some() {
             Error::invalid("A projected image is already set")?
         }
@@ -209,6 +256,7 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
         properties: CylindricalImageProperties,
         mask_data: Option<&mut dyn Read>,
     ) -> Result<()> {
+        self.check_not_done()?;
         if self.image.projection.is_some() {
             Error::invalid("A projected image is already set")?
         }
@@ -232,16 +280,43 @@ impl<'a, T: Read + Write + Seek> ImageWriter<'a, T> {
     /// Binary image and mask data is directly written into the E57 file earlier,
     /// but the XML metadata will be only added to the E57 if you call finalize.
     /// Skipping the finalize call after you added image or mask data means
-    /// that the data will be part of the E57 file but is never referenced by
-    /// its XML header section.
+    /// that the data will be discarded when the next section is added
+    /// or when the E57 file is finalized.
     pub fn finalize(&mut self) -> Result<()> {
+        self.check_not_done()?;
         if self.image.visual_reference.is_none() && self.image.projection.is_none() {
             Error::invalid("Image must have a visual reference or a projection")?
         }
 
         // Add metadata for XML generation later, when the file is completed.
-        self.images.push(self.image.clone());
+        let mut image = self.image.clone();
+        if let Some(t) = &mut image.transform {
+            t.translation.scale(self.unit_scale);
+        }
+        self.images.push(image);
+        *self.open_section = None;
+        self.done = true;
+
+        Ok(())
+    }
 
+    /// Discards the image and all of its already written image and mask data.
+    ///
+    /// The space of the discarded data will be reused by the next section.
+    /// Like for [`PointCloudWriter::abort()`](crate::PointCloudWriter::abort),
+    /// the discarded data is not removed from the file if there is no next section.
+    /// After aborting the writer cannot be used anymore.
+    pub fn abort(&mut self) -> Result<()> {
+        self.check_not_done()?;
+        self.done = true;
+        *self.open_section = None;
+        rollback_section(self.writer, self.section_offset)
+    }
+
+    fn check_not_done(&self) -> Result<()> {
+        if self.done {
+            Error::invalid("Image writer was already finalized or aborted")?
+        }
         Ok(())
     }
 }
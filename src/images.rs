@@ -1,6 +1,9 @@
-use crate::error::Converter;
+#[cfg(any(feature = "read", feature = "write"))]
 use crate::xml;
-use crate::{Blob, DateTime, Error, Result, Transform};
+use crate::{Blob, DateTime, Transform};
+#[cfg(feature = "read")]
+use crate::{Error, Result};
+#[cfg(feature = "read")]
 use roxmltree::{Document, Node};
 
 /// Descriptor with metadata for a single image.
@@ -30,9 +33,16 @@ pub struct Image {
     pub sensor_model: Option<String>,
     /// The serial number of the sensor used to capture the image.
     pub sensor_serial: Option<String>,
+    /// Unchanged XML elements of extensions that are children of the image element.
+    /// They are written back as they are, which allows to preserve vendor specific metadata.
+    pub custom_xml: Vec<String>,
+    /// XML attributes of extensions on the image element, for example `ven:id="42"`.
+    /// They are written back as they are, like the custom XML elements.
+    pub custom_attributes: Vec<String>,
 }
 
 impl Image {
+    #[cfg(feature = "read")]
     fn from_node(node: &Node) -> Result<Self> {
         let guid = xml::opt_string(node, "guid")?;
         let pointcloud_guid = xml::opt_string(node, "associatedData3DGuid")?;
@@ -44,6 +54,8 @@ impl Image {
         let sensor_serial = xml::opt_string(node, "sensorSerialNumber")?;
         let acquisition = xml::opt_date_time(node, "acquisitionDateTime")?;
         let projection = Projection::from_image_node(node)?;
+        let custom_xml = xml::extension_elements(node);
+        let custom_attributes = xml::extension_attributes(node);
 
         let visual_reference_node = node
             .children()
@@ -66,14 +78,20 @@ impl Image {
             sensor_serial,
             projection,
             visual_reference,
+            custom_xml,
+            custom_attributes,
         })
     }
 
+    #[cfg(feature = "read")]
     pub(crate) fn vec_from_document(document: &Document) -> Result<Vec<Self>> {
-        let images2d_node = document
-            .descendants()
-            .find(|n| n.has_tag_name("images2D"))
-            .invalid_err("Cannot find 'images2D' tag in XML document")?;
+        // Many files only contain point clouds and omit the image vector
+        let images2d_node =
+            if let Some(node) = document.descendants().find(|n| n.has_tag_name("images2D")) {
+                node
+            } else {
+                return Ok(Vec::new());
+            };
 
         let mut images = Vec::new();
         for n in images2d_node.children() {
@@ -85,9 +103,13 @@ impl Image {
         Ok(images)
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::new();
-        xml += "<vectorChild type=\"Structure\">\n";
+        xml += &format!(
+            "<vectorChild type=\"Structure\"{}>\n",
+            xml::gen_attributes(&self.custom_attributes)
+        );
         if let Some(guid) = &self.guid {
             xml += &xml::gen_string("guid", &guid);
         }
@@ -121,6 +143,7 @@ impl Image {
         if let Some(serial) = &self.sensor_serial {
             xml += &xml::gen_string("sensorSerialNumber", &serial);
         }
+        xml += &xml::gen_custom(&self.custom_xml);
         xml += "</vectorChild>\n";
         xml
     }
@@ -138,6 +161,7 @@ pub enum Projection {
 }
 
 impl Projection {
+    #[cfg(feature = "read")]
     pub(crate) fn from_image_node(image_node: &Node) -> Result<Option<Self>> {
         let pinhole = image_node
             .children()
@@ -163,6 +187,7 @@ impl Projection {
         Ok(None)
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         match self {
             Projection::Pinhole(p) => p.xml_string(),
@@ -192,6 +217,7 @@ pub struct ImageBlob {
 }
 
 impl ImageBlob {
+    #[cfg(feature = "read")]
     pub(crate) fn from_rep_node(rep_node: &Node) -> Result<Self> {
         if let Some(node) = &rep_node.children().find(|n| n.has_tag_name("jpegImage")) {
             Ok(Self {
@@ -208,6 +234,7 @@ impl ImageBlob {
         }
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         match self.format {
             ImageFormat::Png => self.data.xml_string("pngImage"),
@@ -245,6 +272,7 @@ pub struct VisualReferenceImage {
 }
 
 impl VisualReferenceImage {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             blob: ImageBlob::from_rep_node(node)?,
@@ -256,6 +284,7 @@ impl VisualReferenceImage {
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::new();
         xml += "<visualReferenceRepresentation type=\"Structure\">\n";
@@ -307,6 +336,7 @@ pub struct PinholeImage {
 }
 
 impl PinholeImage {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             blob: ImageBlob::from_rep_node(node)?,
@@ -323,6 +353,7 @@ impl PinholeImage {
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::new();
         xml += "<pinholeRepresentation type=\"Structure\">\n";
@@ -373,6 +404,7 @@ pub struct SphericalImage {
 }
 
 impl SphericalImage {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             blob: ImageBlob::from_rep_node(node)?,
@@ -386,6 +418,7 @@ impl SphericalImage {
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::new();
         xml += "<sphericalRepresentation type=\"Structure\">\n";
@@ -437,6 +470,7 @@ pub struct CylindricalImage {
 }
 
 impl CylindricalImage {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         Ok(Self {
             blob: ImageBlob::from_rep_node(node)?,
@@ -452,6 +486,7 @@ impl CylindricalImage {
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::new();
         xml += "<cylindricalRepresentation type=\"Structure\">\n";
@@ -461,7 +496,7 @@ impl CylindricalImage {
         }
         xml += &xml::gen_int("imageWidth", self.properties.width);
         xml += &xml::gen_int("imageHeight", self.properties.height);
-        xml += &xml::gen_float("readius", self.properties.radius);
+        xml += &xml::gen_float("radius", self.properties.radius);
         xml += &xml::gen_float("principalPointY", self.properties.principal_y);
         xml += &xml::gen_float("pixelWidth", self.properties.pixel_width);
         xml += &xml::gen_float("pixelHeight", self.properties.pixel_height);
@@ -469,3 +504,17 @@ impl CylindricalImage {
         xml
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_images2d() {
+        let xml = "<e57Root type=\"Structure\"><data3D type=\"Vector\"/></e57Root>";
+        let document = Document::parse(xml).unwrap();
+        let images = Image::vec_from_document(&document).unwrap();
+        assert!(images.is_empty());
+    }
+}
@@ -3,11 +3,45 @@
 //! Some example code can be found [here](https://github.com/cry-inc/e57/tree/master/tools) in the GitHub repository.
 //!
 //! ### Optional Crate Features
+//! The features `read` and `write` contain the reader and the writer for E57 files.
+//! Both are **enabled by default**, but one of them can be disabled to reduce the code size
+//! when only reading or only writing is needed, for example in viewers or capture devices.
+//! Copying and transcoding point clouds or images from a reader into a writer requires both features.
+//!
 //! There is an optional feature called `crc32c`.
 //! If enabled, it will include an [external crate](https://crates.io/crates/crc32c) as additional dependency.
 //! This crate provides a faster CRC implementation with HW support.
 //! It can speed up reading and writing of larger E57 files.
 //! The feature is **disabled by default** to keep the number dependencies as small as possible.
+//!
+//! The optional features `nalgebra` and `glam` add conversions between the pose types of this crate
+//! and the corresponding types of the [nalgebra](https://crates.io/crates/nalgebra)
+//! and [glam](https://crates.io/crates/glam) crates, like `Isometry3` or `DMat4`.
+//! Conversions are available for owned values and references.
+//! Matrices can be converted back with `TryFrom`, which fails for matrices that are not rigid body transformations.
+//! Both features are **disabled by default**.
+//!
+//! The optional feature `tracing` emits spans and events with the [tracing](https://crates.io/crates/tracing) crate.
+//! It covers opening files and parsing the XML section, reading of binary sections,
+//! decoding of data packets and detected CRC failures.
+//! This can help with diagnosing performance issues or broken files of specific vendors.
+//! The feature is **disabled by default**.
+//!
+//! The optional feature `uuid` uses the [uuid](https://crates.io/crates/uuid) crate
+//! to generate random GUIDs for files, point clouds and images when writing E57 files,
+//! for example with `E57Writer::from_file_with_random_guid()`.
+//! Without this feature, all GUIDs must be supplied explicitly.
+//! The feature is **disabled by default**.
+//!
+//! The optional feature `chrono` adds conversions between the [`DateTime`] type of this crate
+//! and the `DateTime` type of the [chrono](https://crates.io/crates/chrono) crate.
+//! Leap seconds between GPS time and UTC are taken into account.
+//! The feature is **disabled by default**.
+//!
+//! The optional feature `generator` adds a `generate()` function to create small synthetic E57 files
+//! with controllable characteristics like record types, invalid states, images and extensions.
+//! This is intended for fuzzing and regression testing of software consuming E57 files.
+//! The feature is **disabled by default**.
 
 #![forbid(unsafe_code)]
 #![deny(
@@ -20,50 +54,146 @@
     clippy::cognitive_complexity
 )]
 
+#[cfg(feature = "read")]
 mod bitpack;
 mod blob;
 mod bounds;
+#[cfg(feature = "read")]
 mod bs_read;
+#[cfg(feature = "write")]
 mod bs_write;
+#[cfg(feature = "read")]
+mod crs;
+#[cfg(any(feature = "read", feature = "write"))]
 mod cv_section;
 mod date_time;
+#[cfg(feature = "write")]
+mod dedup;
+#[cfg(feature = "read")]
+mod diff;
+#[cfg(feature = "read")]
+mod dump;
+#[cfg(feature = "read")]
 mod e57_reader;
+#[cfg(feature = "write")]
 mod e57_writer;
 mod error;
+mod estimate;
+#[cfg(feature = "read")]
+mod export;
 mod extension;
+mod grouping;
 mod header;
+#[cfg(feature = "write")]
 mod image_writer;
 mod images;
 mod limits;
+mod morton;
+#[cfg(any(feature = "read", feature = "write"))]
 mod packet;
+#[cfg(feature = "read")]
+mod packet_reader;
+#[cfg(feature = "read")]
 mod paged_reader;
+#[cfg(feature = "write")]
 mod paged_writer;
+#[cfg(feature = "read")]
 mod pc_reader_raw;
+#[cfg(feature = "read")]
 mod pc_reader_simple;
+#[cfg(feature = "write")]
 mod pc_writer;
 mod point;
 mod pointcloud;
+#[cfg(feature = "read")]
+mod preview;
+#[cfg(feature = "read")]
 mod queue_reader;
+#[cfg(feature = "read")]
+mod reader_options;
 mod record;
+mod registration;
+#[cfg(any(feature = "read", feature = "write"))]
 mod root;
+#[cfg(any(feature = "read", feature = "generator"))]
+mod sampling;
+#[cfg(any(feature = "read", feature = "write"))]
+mod spill;
+#[cfg(feature = "read")]
+mod statistics;
+#[cfg(feature = "read")]
+mod stream_buffer;
 mod transform;
+mod voxel;
 mod xml;
 
-#[cfg(not(feature = "crc32c"))]
+#[cfg(feature = "chrono")]
+mod chrono_interop;
+
+#[cfg(all(not(feature = "crc32c"), any(feature = "read", feature = "write")))]
 mod crc32;
 
+#[cfg(feature = "generator")]
+mod generator;
+
+#[cfg(feature = "glam")]
+mod glam_interop;
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop;
+
 // Public types
 pub use self::blob::Blob;
 pub use self::bounds::CartesianBounds;
 pub use self::bounds::IndexBounds;
 pub use self::bounds::SphericalBounds;
+#[cfg(feature = "read")]
+pub use self::crs::CoordinateReferenceSystem;
 pub use self::date_time::DateTime;
+#[cfg(feature = "write")]
+pub use self::dedup::DuplicateRemoval;
+#[cfg(feature = "read")]
+pub use self::diff::diff;
+#[cfg(feature = "read")]
+pub use self::diff::DiffOptions;
+#[cfg(feature = "read")]
+pub use self::diff::Difference;
+#[cfg(feature = "read")]
+pub use self::diff::FileDiff;
+#[cfg(feature = "read")]
+pub use self::diff::PointCloudDiff;
+#[cfg(feature = "read")]
+pub use self::diff::PointDifference;
+#[cfg(feature = "read")]
 pub use self::e57_reader::E57Reader;
+#[cfg(all(feature = "read", feature = "write"))]
+pub use self::e57_writer::copy_pointcloud;
+#[cfg(feature = "write")]
 pub use self::e57_writer::E57Writer;
 pub use self::error::Error;
+pub use self::error::ErrorContext;
+pub use self::error::ErrorKind;
 pub use self::error::Result;
+pub use self::estimate::RecordLayout;
+pub use self::estimate::ResourceEstimate;
+#[cfg(feature = "read")]
+pub use self::export::CsvExporter;
+#[cfg(feature = "read")]
+pub use self::export::ExportOptions;
+#[cfg(feature = "read")]
+pub use self::export::Exporter;
+#[cfg(feature = "read")]
+pub use self::export::PlyExporter;
 pub use self::extension::Extension;
+pub use self::extension::ExtensionDecoder;
+pub use self::extension::ExtensionDefinition;
+pub use self::extension::ExtensionRecordDefinition;
+pub use self::extension::ExtensionValue;
+pub use self::extension::ExtensionValueKind;
+pub use self::grouping::LineGroup;
+pub use self::grouping::LineGrouping;
 pub use self::header::Header;
+#[cfg(feature = "write")]
 pub use self::image_writer::ImageWriter;
 pub use self::images::CylindricalImage;
 pub use self::images::CylindricalImageProperties;
@@ -79,21 +209,63 @@ pub use self::images::VisualReferenceImage;
 pub use self::images::VisualReferenceImageProperties;
 pub use self::limits::ColorLimits;
 pub use self::limits::IntensityLimits;
+pub use self::morton::MortonOrder;
+#[cfg(feature = "read")]
+pub use self::packet_reader::PacketReader;
+#[cfg(feature = "read")]
+pub use self::packet_reader::RawPacket;
+#[cfg(feature = "read")]
 pub use self::pc_reader_raw::PointCloudReaderRaw;
+#[cfg(feature = "read")]
+pub use self::pc_reader_simple::PointChunks;
+#[cfg(feature = "read")]
 pub use self::pc_reader_simple::PointCloudReaderSimple;
+#[cfg(feature = "read")]
+pub use self::pc_reader_simple::Reprojection;
+#[cfg(feature = "write")]
 pub use self::pc_writer::PointCloudWriter;
 pub use self::point::CartesianCoordinate;
 pub use self::point::Color;
 pub use self::point::Point;
 pub use self::point::SphericalCoordinate;
 pub use self::pointcloud::PointCloud;
+#[cfg(feature = "read")]
+pub use self::preview::PointCloudPreview;
+#[cfg(feature = "read")]
+pub use self::reader_options::ReaderOptions;
+#[cfg(feature = "read")]
+pub use self::reader_options::ResourceLimits;
 pub use self::record::Record;
 pub use self::record::RecordDataType;
 pub use self::record::RecordName;
 pub use self::record::RecordValue;
+pub use self::registration::scan_poses;
+pub use self::registration::PoseStatus;
+pub use self::registration::RegistrationFrame;
+pub use self::registration::ScanPose;
+#[cfg(feature = "read")]
+pub use self::sampling::Subsampling;
+#[cfg(feature = "read")]
+pub use self::statistics::PointCloudStatistics;
+#[cfg(feature = "read")]
+pub use self::statistics::HISTOGRAM_BINS;
+#[cfg(feature = "read")]
+pub use self::stream_buffer::SpillPolicy;
+#[cfg(feature = "read")]
+pub use self::stream_buffer::StreamBuffer;
+pub use self::transform::PoseCorrection;
 pub use self::transform::Quaternion;
+pub use self::transform::QuaternionAction;
+pub use self::transform::QuaternionHandling;
 pub use self::transform::Transform;
 pub use self::transform::Translation;
+pub use self::voxel::downsample_voxel;
+pub use self::voxel::VoxelPoint;
+
+#[cfg(feature = "generator")]
+pub use self::generator::generate;
+#[cfg(feature = "generator")]
+pub use self::generator::GeneratorOptions;
 
 /// Storage container for low level point data.
 pub type RawValues = Vec<RecordValue>;
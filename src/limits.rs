@@ -1,10 +1,16 @@
+#[cfg(feature = "read")]
 use crate::error::Converter;
+#[cfg(feature = "read")]
 use crate::Error;
+#[cfg(feature = "write")]
 use crate::RecordDataType;
 use crate::RecordValue;
+#[cfg(feature = "read")]
 use crate::Result;
+#[cfg(feature = "read")]
 use roxmltree::Node;
 
+#[cfg(feature = "read")]
 fn extract_limit(bounds: &Node, tag_name: &str) -> Result<Option<RecordValue>> {
     if let Some(tag) = bounds.descendants().find(|n| n.has_tag_name(tag_name)) {
         let type_str = tag
@@ -55,15 +61,21 @@ pub struct IntensityLimits {
 }
 
 impl IntensityLimits {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         let intensity_min = extract_limit(node, "intensityMinimum")?;
-        let intensity_max = extract_limit(node, "intenstiyMaximum")?;
+        // Older versions of this library wrote a misspelled tag name for the maximum
+        let intensity_max = match extract_limit(node, "intensityMaximum")? {
+            Some(max) => Some(max),
+            None => extract_limit(node, "intenstiyMaximum")?,
+        };
         Ok(Self {
             intensity_min,
             intensity_max,
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn from_record_type(data_type: &RecordDataType) -> Self {
         let (intensity_min, intensity_max) = data_type.limits();
         Self {
@@ -72,13 +84,14 @@ impl IntensityLimits {
         }
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::from("<intensityLimits type=\"Structure\">");
         if let Some(min) = &self.intensity_min {
             xml += &format!("<intensityMinimum type=\"Integer\">{min}</intensityMinimum>");
         }
         if let Some(max) = &self.intensity_max {
-            xml += &format!("<intenstiyMaximum type=\"Integer\">{max}</intenstiyMaximum>");
+            xml += &format!("<intensityMaximum type=\"Integer\">{max}</intensityMaximum>");
         }
         xml += "</intensityLimits>";
         xml
@@ -97,6 +110,7 @@ pub struct ColorLimits {
 }
 
 impl ColorLimits {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         let red_min = extract_limit(node, "colorRedMinimum")?;
         let red_max = extract_limit(node, "colorRedMaximum")?;
@@ -114,6 +128,7 @@ impl ColorLimits {
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn from_record_types(
         red: &RecordDataType,
         green: &RecordDataType,
@@ -132,6 +147,7 @@ impl ColorLimits {
         }
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let mut xml = String::from("<colorLimits type=\"Structure\">");
         if let Some(min) = &self.red_min {
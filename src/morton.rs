@@ -0,0 +1,449 @@
+#[cfg(feature = "write")]
+use crate::error::Converter;
+#[cfg(feature = "write")]
+use crate::spill::SpillFile;
+#[cfg(feature = "write")]
+use crate::{Error, RawValues, Record, RecordName, RecordValue, Result};
+#[cfg(feature = "write")]
+use std::cmp::Reverse;
+#[cfg(feature = "write")]
+use std::collections::BinaryHeap;
+#[cfg(feature = "write")]
+use std::fs::File;
+#[cfg(feature = "write")]
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+/// Namespace URL of the custom XML element that describes the Morton order of a point cloud.
+/// The element is documented in the file `EXTENSIONS.md` of the repository.
+pub(crate) const MORTON_NAMESPACE: &str =
+    "https://github.com/cry-inc/e57/blob/master/EXTENSIONS.md#point-order";
+
+/// Number of bits per axis in a Morton code.
+#[cfg(feature = "write")]
+const AXIS_BITS: u32 = 42;
+
+/// Maximum number of spilled runs that are opened and merged at once.
+/// More runs are merged in multiple passes to limit the number of open files.
+#[cfg(feature = "write")]
+const MAX_MERGE_RUNS: usize = 64;
+
+/// Options for writing the points of a point cloud sorted by their Morton code.
+///
+/// The Morton code, also known as Z-order, interleaves the bits of the quantized
+/// X, Y and Z coordinates, so that points close to each other in space
+/// are also close to each other in the file.
+/// All points are buffered until the point cloud is finalized.
+/// When the buffered points exceed the memory budget, they are sorted
+/// and spilled to a temporary file. All sorted runs are merged when finalizing,
+/// large numbers of runs are merged in multiple passes.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct MortonOrder {
+    /// Edge length of the grid cells used to quantize the coordinates.
+    /// Uses the same unit as the added points, default is 0.001.
+    /// Coordinates are clamped to about 2^41 cells in each direction from the origin.
+    pub resolution: f64,
+    /// Maximum number of bytes used for buffering points in memory, default is 256 MiB.
+    pub memory_budget: usize,
+    /// Directory for temporary files, uses the temporary directory of the system if not set.
+    pub spill_directory: Option<PathBuf>,
+}
+
+impl Default for MortonOrder {
+    fn default() -> Self {
+        Self {
+            resolution: 0.001,
+            memory_budget: 256 * 1024 * 1024,
+            spill_directory: None,
+        }
+    }
+}
+
+impl MortonOrder {
+    #[cfg(feature = "write")]
+    pub(crate) fn validate(&self) -> Result<()> {
+        if !self.resolution.is_finite() || self.resolution <= 0.0 {
+            Error::invalid(format!(
+                "Morton order resolution must be a positive number, but is {}",
+                self.resolution
+            ))?
+        }
+        Ok(())
+    }
+
+    /// Creates the custom XML element that describes the order in the point cloud metadata.
+    #[cfg(feature = "write")]
+    pub(crate) fn xml_string(&self) -> String {
+        format!(
+            "<e57rs:mortonOrder xmlns:e57rs=\"{MORTON_NAMESPACE}\" type=\"Float\">{}</e57rs:mortonOrder>",
+            self.resolution
+        )
+    }
+
+    /// Extracts the resolution from a custom XML element created by [`Self::xml_string()`].
+    pub(crate) fn resolution_from_xml(xml: &str) -> Option<f64> {
+        let document = roxmltree::Document::parse(xml).ok()?;
+        let root = document.root_element();
+        let name = root.tag_name();
+        if name.namespace() == Some(MORTON_NAMESPACE) && name.name() == "mortonOrder" {
+            root.text()?.trim().parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Buffers points and returns them sorted by their Morton code.
+#[cfg(feature = "write")]
+pub(crate) struct MortonSorter {
+    options: MortonOrder,
+    xyz: [usize; 3],
+    state: Option<usize>,
+    buffer: Vec<(u128, RawValues)>,
+    buffer_bytes: usize,
+    runs: Vec<SpillFile>,
+}
+
+#[cfg(feature = "write")]
+impl MortonSorter {
+    /// The resolution is expected to be already scaled to the unit of the stored coordinates.
+    pub fn new(options: MortonOrder, prototype: &[Record]) -> Result<Self> {
+        options.validate()?;
+        let find = |name: RecordName| prototype.iter().position(|p| p.name == name);
+        let xyz = [
+            find(RecordName::CartesianX),
+            find(RecordName::CartesianY),
+            find(RecordName::CartesianZ),
+        ];
+        let [Some(x), Some(y), Some(z)] = xyz else {
+            Error::invalid("Morton order requires Cartesian coordinates")?
+        };
+        Ok(Self {
+            options,
+            xyz: [x, y, z],
+            state: find(RecordName::CartesianInvalidState),
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            runs: Vec::new(),
+        })
+    }
+
+    pub fn push(&mut self, prototype: &[Record], values: RawValues) -> Result<()> {
+        let code = self.code(prototype, &values)?;
+        self.buffer_bytes += std::mem::size_of::<(u128, RawValues)>()
+            + values.len() * std::mem::size_of::<RecordValue>();
+        self.buffer.push((code, values));
+        if self.buffer_bytes > self.options.memory_budget {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Returns all buffered points sorted by their Morton code.
+    /// Points with the same code keep the order in which they were added.
+    pub fn into_sorted(mut self) -> Result<MortonMerge> {
+        self.buffer.sort_by_key(|(code, _)| *code);
+        // Merging consecutive runs keeps the order of points with the same code
+        let mut runs = std::mem::take(&mut self.runs);
+        while runs.len() > MAX_MERGE_RUNS {
+            let mut merged = Vec::with_capacity(runs.len().div_ceil(MAX_MERGE_RUNS));
+            let mut remaining = runs.into_iter().peekable();
+            while remaining.peek().is_some() {
+                let group = remaining.by_ref().take(MAX_MERGE_RUNS).collect();
+                merged.push(self.merge_runs(group)?);
+            }
+            runs = merged;
+        }
+        MortonMerge::new(runs, std::mem::take(&mut self.buffer))
+    }
+
+    /// Merges multiple sorted runs into a single new sorted run.
+    fn merge_runs(&self, runs: Vec<SpillFile>) -> Result<SpillFile> {
+        let mut merge = MortonMerge::new(runs, Vec::new())?;
+        let (file, handle) = SpillFile::create("morton", self.options.spill_directory.clone())?;
+        let mut writer = BufWriter::new(handle);
+        while let Some(entry) = merge.next_entry() {
+            let (code, values) = entry?;
+            write_entry(&mut writer, code, &values)?;
+        }
+        writer.flush().write_err("Failed to write temporary file")?;
+        Ok(file)
+    }
+
+    fn code(&self, prototype: &[Record], values: &RawValues) -> Result<u128> {
+        if let Some(i) = self.state {
+            if values[i].to_i64(&prototype[i].data_type)? != 0 {
+                return Ok(u128::MAX);
+            }
+        }
+        let mut code = 0;
+        for (axis, i) in self.xyz.into_iter().enumerate() {
+            let v = values[i].to_f64(&prototype[i].data_type)?;
+            let limit = 1_i64 << (AXIS_BITS - 1);
+            let cell = ((v / self.options.resolution).floor() as i64).clamp(-limit, limit - 1);
+            code |= spread_bits((cell + limit) as u64) << axis;
+        }
+        Ok(code)
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        self.buffer.sort_by_key(|(code, _)| *code);
+        let (file, handle) = SpillFile::create("morton", self.options.spill_directory.clone())?;
+        let mut writer = BufWriter::new(handle);
+        for (code, values) in self.buffer.drain(..) {
+            write_entry(&mut writer, code, &values)?;
+        }
+        writer.flush().write_err("Failed to write temporary file")?;
+        self.buffer_bytes = 0;
+        self.runs.push(file);
+        Ok(())
+    }
+}
+
+/// Merges the sorted runs of a [`MortonSorter`] into a single sorted sequence.
+#[cfg(feature = "write")]
+pub(crate) struct MortonMerge {
+    runs: Vec<(SpillFile, BufReader<File>)>,
+    memory: std::vec::IntoIter<(u128, RawValues)>,
+    heap: BinaryHeap<Reverse<(u128, usize)>>,
+    pending: Vec<Option<RawValues>>,
+}
+
+#[cfg(feature = "write")]
+impl MortonMerge {
+    fn new(files: Vec<SpillFile>, memory: Vec<(u128, RawValues)>) -> Result<Self> {
+        let mut runs = Vec::with_capacity(files.len());
+        for file in files {
+            let reader = BufReader::new(File::open(file.path()).read_err(format!(
+                "Failed to open temporary file {}",
+                file.path().display()
+            ))?);
+            runs.push((file, reader));
+        }
+        let mut merge = Self {
+            runs,
+            memory: memory.into_iter(),
+            heap: BinaryHeap::new(),
+            pending: Vec::new(),
+        };
+        for run in 0..=merge.runs.len() {
+            merge.advance(run)?;
+        }
+        Ok(merge)
+    }
+
+    /// Reads the next entry of a run into the heap, the last run is the in-memory buffer.
+    fn advance(&mut self, run: usize) -> Result<()> {
+        let entry = if let Some((_, reader)) = self.runs.get_mut(run) {
+            read_entry(reader)?
+        } else {
+            self.memory.next()
+        };
+        if self.pending.len() <= run {
+            self.pending.resize(run + 1, None);
+        }
+        if let Some((code, values)) = entry {
+            self.heap.push(Reverse((code, run)));
+            self.pending[run] = Some(values);
+        }
+        Ok(())
+    }
+
+    /// Returns the next entry with the smallest code together with its code.
+    fn next_entry(&mut self) -> Option<Result<(u128, RawValues)>> {
+        let Reverse((code, run)) = self.heap.pop()?;
+        let values = self.pending.get_mut(run)?.take()?;
+        if let Err(err) = self.advance(run) {
+            self.heap.clear();
+            return Some(Err(err));
+        }
+        Some(Ok((code, values)))
+    }
+}
+
+#[cfg(feature = "write")]
+impl Iterator for MortonMerge {
+    type Item = Result<RawValues>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry()
+            .map(|entry| entry.map(|(_, values)| values))
+    }
+}
+
+#[cfg(feature = "write")]
+fn write_entry(writer: &mut dyn Write, code: u128, values: &RawValues) -> Result<()> {
+    let mut bytes = Vec::with_capacity(20 + values.len() * 9);
+    bytes.extend_from_slice(&code.to_le_bytes());
+    bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        let (tag, payload) = match value {
+            RecordValue::Single(v) => (0_u8, v.to_bits() as u64),
+            RecordValue::Double(v) => (1, v.to_bits()),
+            RecordValue::ScaledInteger(v) => (2, *v as u64),
+            RecordValue::Integer(v) => (3, *v as u64),
+        };
+        bytes.push(tag);
+        bytes.extend_from_slice(&payload.to_le_bytes());
+    }
+    writer
+        .write_all(&bytes)
+        .write_err("Failed to write temporary file")
+}
+
+#[cfg(feature = "write")]
+fn read_entry(reader: &mut dyn Read) -> Result<Option<(u128, RawValues)>> {
+    let mut code = [0_u8; 16];
+    match reader.read_exact(&mut code) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => Err(err).read_err("Failed to read temporary file")?,
+    }
+    let mut len = [0_u8; 4];
+    reader
+        .read_exact(&mut len)
+        .read_err("Failed to read temporary file")?;
+    let len = u32::from_le_bytes(len) as usize;
+    let mut values = Vec::with_capacity(len);
+    for _ in 0..len {
+        let mut entry = [0_u8; 9];
+        reader
+            .read_exact(&mut entry)
+            .read_err("Failed to read temporary file")?;
+        let mut payload = [0_u8; 8];
+        payload.copy_from_slice(&entry[1..]);
+        let payload = u64::from_le_bytes(payload);
+        values.push(match entry[0] {
+            0 => RecordValue::Single(f32::from_bits(payload as u32)),
+            1 => RecordValue::Double(f64::from_bits(payload)),
+            2 => RecordValue::ScaledInteger(payload as i64),
+            3 => RecordValue::Integer(payload as i64),
+            tag => Error::internal(format!("Unexpected value tag {tag} in temporary file"))?,
+        });
+    }
+    Ok(Some((u128::from_le_bytes(code), values)))
+}
+
+/// Spreads the lower bits of the value so that there are two zero bits between each of them.
+#[cfg(feature = "write")]
+fn spread_bits(value: u64) -> u128 {
+    let mut result = 0;
+    for bit in 0..AXIS_BITS {
+        result |= (((value >> bit) & 1) as u128) << (3 * bit);
+    }
+    result
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64, z: f64) -> RawValues {
+        vec![
+            RecordValue::Double(x),
+            RecordValue::Double(y),
+            RecordValue::Double(z),
+        ]
+    }
+
+    fn sorted(options: MortonOrder, points: &[RawValues]) -> Vec<RawValues> {
+        let prototype = [
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mut sorter = MortonSorter::new(options, &prototype).unwrap();
+        for p in points {
+            sorter.push(&prototype, p.clone()).unwrap();
+        }
+        let merge = sorter.into_sorted().unwrap();
+        merge.collect::<Result<Vec<_>>>().unwrap()
+    }
+
+    #[test]
+    fn spread() {
+        assert_eq!(spread_bits(0), 0);
+        assert_eq!(spread_bits(1), 1);
+        assert_eq!(spread_bits(0b101), 0b1000001);
+        assert_eq!(spread_bits(u64::MAX).count_ones(), AXIS_BITS);
+    }
+
+    #[test]
+    fn sort_in_memory_and_spilled() {
+        let mut points = Vec::new();
+        for i in 0..100 {
+            let v = ((i * 37) % 100) as f64;
+            points.push(point(v, -v, v * 0.5));
+        }
+        let options = MortonOrder {
+            resolution: 1.0,
+            ..Default::default()
+        };
+        let in_memory = sorted(options.clone(), &points);
+        let spilled = sorted(
+            MortonOrder {
+                memory_budget: 1000,
+                ..options.clone()
+            },
+            &points,
+        );
+        assert_eq!(in_memory.len(), 100);
+        assert_eq!(in_memory, spilled);
+
+        let line = [
+            point(3.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+            point(2.0, 0.0, 0.0),
+        ];
+        let expected = [
+            point(1.0, 0.0, 0.0),
+            point(2.0, 0.0, 0.0),
+            point(3.0, 0.0, 0.0),
+        ];
+        assert_eq!(sorted(options, &line), expected);
+    }
+
+    #[test]
+    fn merge_many_runs_in_passes() {
+        // Every point is spilled into its own run
+        let options = MortonOrder {
+            resolution: 1.0,
+            memory_budget: 0,
+            ..Default::default()
+        };
+        let mut points = Vec::new();
+        for i in 0..(MAX_MERGE_RUNS * 3) {
+            let v = ((i * 37) % 100) as f64;
+            points.push(point(v, 0.0, 0.0));
+        }
+        // Points in the same cell keep the order in which they were added
+        for i in 0..10 {
+            points.push(point(0.01 * i as f64, 0.0, 0.0));
+        }
+        let result = sorted(options.clone(), &points);
+        let in_memory = sorted(
+            MortonOrder {
+                memory_budget: usize::MAX,
+                ..options
+            },
+            &points,
+        );
+        assert_eq!(result.len(), points.len());
+        assert_eq!(result, in_memory);
+    }
+
+    #[test]
+    fn metadata_xml() {
+        let order = MortonOrder::default();
+        let xml = order.xml_string();
+        assert_eq!(MortonOrder::resolution_from_xml(&xml), Some(0.001));
+        assert_eq!(MortonOrder::resolution_from_xml("<a>1</a>"), None);
+        let invalid = MortonOrder {
+            resolution: 0.0,
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{Quaternion, Transform, Translation};
+use nalgebra::{
+    Isometry3, Matrix3, Matrix4, Quaternion as NaQuaternion, Rotation3, Translation3,
+    UnitQuaternion,
+};
+
+impl From<&Quaternion> for UnitQuaternion<f64> {
+    /// Converts the quaternion and normalizes it.
+    fn from(q: &Quaternion) -> Self {
+        UnitQuaternion::from_quaternion(NaQuaternion::new(q.w, q.x, q.y, q.z))
+    }
+}
+
+impl From<&UnitQuaternion<f64>> for Quaternion {
+    fn from(q: &UnitQuaternion<f64>) -> Self {
+        Self {
+            w: q.w,
+            x: q.i,
+            y: q.j,
+            z: q.k,
+        }
+    }
+}
+
+impl From<&Translation> for Translation3<f64> {
+    fn from(t: &Translation) -> Self {
+        Translation3::new(t.x, t.y, t.z)
+    }
+}
+
+impl From<&Translation3<f64>> for Translation {
+    fn from(t: &Translation3<f64>) -> Self {
+        Self {
+            x: t.x,
+            y: t.y,
+            z: t.z,
+        }
+    }
+}
+
+impl From<&Transform> for Isometry3<f64> {
+    fn from(t: &Transform) -> Self {
+        Isometry3::from_parts((&t.translation).into(), (&t.rotation).into())
+    }
+}
+
+impl From<&Isometry3<f64>> for Transform {
+    fn from(iso: &Isometry3<f64>) -> Self {
+        Self {
+            rotation: (&iso.rotation).into(),
+            translation: (&iso.translation).into(),
+        }
+    }
+}
+
+impl From<&Transform> for Matrix4<f64> {
+    fn from(t: &Transform) -> Self {
+        Isometry3::from(t).to_homogeneous()
+    }
+}
+
+impl TryFrom<&Matrix4<f64>> for Transform {
+    type Error = crate::Error;
+
+    /// Extracts rotation and translation from a matrix.
+    /// Fails if the matrix contains scaling, shearing or projective components.
+    fn try_from(m: &Matrix4<f64>) -> crate::Result<Self> {
+        let rotation: Matrix3<f64> = m.fixed_view::<3, 3>(0, 0).into_owned();
+        let rotation =
+            UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(rotation));
+        let translation = Translation3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]);
+        let iso = Isometry3::from_parts(translation, rotation);
+        if !iso.to_homogeneous().relative_eq(m, 1e-9, 1e-9) {
+            crate::Error::invalid("Matrix is not a rigid body transformation")?
+        }
+        Ok((&iso).into())
+    }
+}
+
+impl From<Quaternion> for UnitQuaternion<f64> {
+    /// Converts the quaternion and normalizes it.
+    fn from(q: Quaternion) -> Self {
+        (&q).into()
+    }
+}
+
+impl From<UnitQuaternion<f64>> for Quaternion {
+    fn from(q: UnitQuaternion<f64>) -> Self {
+        (&q).into()
+    }
+}
+
+impl From<Translation> for Translation3<f64> {
+    fn from(t: Translation) -> Self {
+        (&t).into()
+    }
+}
+
+impl From<Translation3<f64>> for Translation {
+    fn from(t: Translation3<f64>) -> Self {
+        (&t).into()
+    }
+}
+
+impl From<Transform> for Isometry3<f64> {
+    fn from(t: Transform) -> Self {
+        (&t).into()
+    }
+}
+
+impl From<Isometry3<f64>> for Transform {
+    fn from(iso: Isometry3<f64>) -> Self {
+        (&iso).into()
+    }
+}
+
+impl From<Transform> for Matrix4<f64> {
+    fn from(t: Transform) -> Self {
+        (&t).into()
+    }
+}
+
+impl TryFrom<Matrix4<f64>> for Transform {
+    type Error = crate::Error;
+
+    /// Extracts rotation and translation from a matrix.
+    /// Fails if the matrix contains scaling, shearing or projective components.
+    fn try_from(m: Matrix4<f64>) -> crate::Result<Self> {
+        Self::try_from(&m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Point3;
+
+    fn transform() -> Transform {
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        Transform {
+            rotation: Quaternion {
+                w: s,
+                x: 0.0,
+                y: 0.0,
+                z: s,
+            },
+            translation: Translation {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        }
+    }
+
+    #[test]
+    fn isometry_round_trip() {
+        let t = transform();
+        let iso = Isometry3::from(&t);
+        let p = iso * Point3::new(1.0, 0.0, 0.0);
+        assert!((p.x - 1.0).abs() < 1e-9);
+        assert!((p.y - 3.0).abs() < 1e-9);
+        assert!((p.z - 3.0).abs() < 1e-9);
+
+        let back = Transform::from(&iso);
+        assert!((back.rotation.w - t.rotation.w).abs() < 1e-12);
+        assert!((back.rotation.z - t.rotation.z).abs() < 1e-12);
+        assert_eq!(back.translation.y, 2.0);
+    }
+
+    #[test]
+    fn matrix_matches_crate_matrix() {
+        let t = transform();
+        let m = Matrix4::from(&t);
+        let expected = t.to_matrix();
+        for (r, row) in expected.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                assert!((m[(r, c)] - value).abs() < 1e-12);
+            }
+        }
+
+        let back = Transform::try_from(m).unwrap();
+        assert!((back.rotation.w - t.rotation.w).abs() < 1e-12);
+        assert!((back.rotation.z - t.rotation.z).abs() < 1e-12);
+        assert!((back.translation.x - 1.0).abs() < 1e-12);
+        assert!((back.translation.z - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn owned_conversions() {
+        let t = transform();
+        let iso: Isometry3<f64> = t.clone().into();
+        let rotation: UnitQuaternion<f64> = t.rotation.clone().into();
+        let translation: Translation3<f64> = t.translation.clone().into();
+        assert_eq!(iso, Isometry3::from_parts(translation, rotation));
+        let back: Transform = iso.into();
+        assert_eq!(back.translation.y, 2.0);
+        let m: Matrix4<f64> = back.into();
+        assert_eq!(m[(1, 3)], 2.0);
+    }
+
+    #[test]
+    fn non_rigid_matrix_is_rejected() {
+        let scaled = Matrix4::new_scaling(2.0);
+        assert!(Transform::try_from(&scaled).is_err());
+        let mut projective = Matrix4::identity();
+        projective[(3, 0)] = 0.5;
+        assert!(Transform::try_from(&projective).is_err());
+        let mut sheared = Matrix4::identity();
+        sheared[(0, 1)] = 0.5;
+        assert!(Transform::try_from(&sheared).is_err());
+    }
+}
@@ -1,13 +1,22 @@
-use crate::error::{Converter, WRONG_OFFSET};
-use crate::{Error, Result};
-use std::io::{Read, Write};
-
+use crate::error::Converter;
+#[cfg(feature = "read")]
+use crate::error::WRONG_OFFSET;
+#[cfg(feature = "read")]
+use crate::Error;
+use crate::Result;
+#[cfg(feature = "read")]
+use std::io::Read;
+#[cfg(feature = "write")]
+use std::io::Write;
+
+#[cfg(feature = "read")]
 pub enum PacketHeader {
     Index(IndexPacketHeader),
     Data(DataPacketHeader),
     Ignored(IgnoredPacketHeader),
 }
 
+#[cfg(feature = "read")]
 impl PacketHeader {
     pub fn read(reader: &mut dyn Read) -> Result<Self> {
         // Read only first byte of header to indetify packet type
@@ -23,17 +32,19 @@ impl PacketHeader {
         } else if buffer[0] == 2 {
             Ok(PacketHeader::Ignored(IgnoredPacketHeader::read(reader)?))
         } else {
-            Error::invalid("Found unknown packet ID when trying to read packet header")?
+            Error::malformed("Found unknown packet ID when trying to read packet header")?
         }
     }
 }
 
+#[cfg(feature = "read")]
 pub struct IndexPacketHeader {
     pub packet_length: u64,
     pub entry_count: u16,
     pub index_level: u8,
 }
 
+#[cfg(feature = "read")]
 impl IndexPacketHeader {
     pub fn read(reader: &mut dyn Read) -> Result<Self> {
         let mut buffer = [0_u8; 15];
@@ -49,10 +60,10 @@ impl IndexPacketHeader {
 
         // Validate values
         if packet_length == 0 {
-            Error::invalid("A data packet length of 0 is not allowed")?
+            Error::malformed("A data packet length of 0 is not allowed")?
         }
-        if packet_length % 4 != 0 {
-            Error::invalid("Index packet length is not aligned and a multiple of four")?
+        if !packet_length.is_multiple_of(4) {
+            Error::malformed("Index packet length is not aligned and a multiple of four")?
         }
 
         Ok(Self {
@@ -70,8 +81,10 @@ pub struct DataPacketHeader {
 }
 
 impl DataPacketHeader {
+    #[cfg(feature = "write")]
     pub const SIZE: usize = 6;
 
+    #[cfg(feature = "read")]
     pub fn read(reader: &mut dyn Read) -> Result<Self> {
         let mut buffer = [0_u8; 5];
         reader
@@ -87,13 +100,13 @@ impl DataPacketHeader {
 
         // Validate values
         if packet_length == 0 {
-            Error::invalid("A data packet length of 0 is not allowed")?
+            Error::malformed("A data packet length of 0 is not allowed")?
         }
-        if packet_length % 4 != 0 {
-            Error::invalid("Data packet length is not aligned and a multiple of four")?
+        if !packet_length.is_multiple_of(4) {
+            Error::malformed("Data packet length is not aligned and a multiple of four")?
         }
         if bytestream_count == 0 {
-            Error::invalid("A byte stream count of 0 is not allowed")?
+            Error::malformed("A byte stream count of 0 is not allowed")?
         }
 
         Ok(Self {
@@ -103,6 +116,7 @@ impl DataPacketHeader {
         })
     }
 
+    #[cfg(feature = "write")]
     pub fn write(&self, writer: &mut dyn Write) -> Result<()> {
         let mut buffer = [0_u8; Self::SIZE];
         buffer[0] = 1;
@@ -117,10 +131,12 @@ impl DataPacketHeader {
     }
 }
 
+#[cfg(feature = "read")]
 pub struct IgnoredPacketHeader {
     pub packet_length: u64,
 }
 
+#[cfg(feature = "read")]
 impl IgnoredPacketHeader {
     pub fn read(reader: &mut dyn Read) -> Result<Self> {
         // Read Ignored Packet
@@ -135,10 +151,10 @@ impl IgnoredPacketHeader {
 
         // Validate values
         if packet_length == 0 {
-            Error::invalid("A ignored packet length of 0 is not allowed")?
+            Error::malformed("A ignored packet length of 0 is not allowed")?
         }
-        if packet_length % 4 != 0 {
-            Error::invalid("Ignored packet length is not aligned and a multiple of four")?
+        if !packet_length.is_multiple_of(4) {
+            Error::malformed("Ignored packet length is not aligned and a multiple of four")?
         }
 
         Ok(Self { packet_length })
@@ -0,0 +1,210 @@
+use crate::cv_section::CompressedVectorSectionHeader;
+use crate::error::Converter;
+use crate::packet::PacketHeader;
+use crate::paged_reader::PagedReader;
+use crate::Error;
+use crate::PointCloud;
+use crate::ResourceLimits;
+use crate::Result;
+use std::io::{Read, Seek};
+use std::iter::FusedIterator;
+
+/// Size of the data packet header in bytes, without the buffer sizes of the byte streams.
+const DATA_HEADER_SIZE: u64 = 6;
+
+/// Size of the index packet header in bytes, including the packet type byte.
+const INDEX_HEADER_SIZE: u64 = 16;
+
+/// Size of the ignored packet header in bytes, including the packet type byte.
+const IGNORED_HEADER_SIZE: u64 = 4;
+
+/// Raw packet of a compressed vector section with its header values and unparsed payload.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum RawPacket {
+    /// Data packet with the buffers of all byte streams.
+    Data {
+        /// Physical offset of the packet in the file.
+        physical_offset: u64,
+        /// Logical length of the whole packet in bytes, including header and padding.
+        packet_length: u64,
+        /// Compressor restart flag of the packet header.
+        comp_restart_flag: bool,
+        /// One buffer with the bit packed values per byte stream.
+        /// The order of the byte streams corresponds to the records of the prototype.
+        bytestreams: Vec<Vec<u8>>,
+    },
+    /// Index packet with its unparsed entries.
+    Index {
+        /// Physical offset of the packet in the file.
+        physical_offset: u64,
+        /// Logical length of the whole packet in bytes, including header and padding.
+        packet_length: u64,
+        /// Number of entries in the packet.
+        entry_count: u16,
+        /// Level of the index packet in the index tree.
+        index_level: u8,
+        /// Raw payload of the packet following the header.
+        payload: Vec<u8>,
+    },
+    /// Ignored packet.
+    Ignored {
+        /// Physical offset of the packet in the file.
+        physical_offset: u64,
+        /// Logical length of the whole packet in bytes, including header.
+        packet_length: u64,
+        /// Raw payload of the packet following the header.
+        payload: Vec<u8>,
+    },
+}
+
+/// Iterate over all raw packets of the compressed vector section of a point cloud.
+///
+/// This is a low level interface for custom decoders or tools that need to inspect the binary data.
+/// No values are decoded and the packets are returned as stored in the file.
+/// The iterator will stop and return only None after the first error.
+pub struct PacketReader<'a, T: Read + Seek> {
+    reader: &'a mut PagedReader<T>,
+    limits: ResourceLimits,
+    next_offset: u64,
+    end_offset: u64,
+    packets: u64,
+    failed: bool,
+}
+
+impl<'a, T: Read + Seek> PacketReader<'a, T> {
+    pub(crate) fn new(
+        pc: &PointCloud,
+        reader: &'a mut PagedReader<T>,
+        limits: ResourceLimits,
+    ) -> Result<Self> {
+        let section_start = reader
+            .seek_physical(pc.file_offset)
+            .read_err("Cannot seek to compressed vector header")?;
+        let section_header = CompressedVectorSectionHeader::read(reader)?;
+        let end_offset = section_start + section_header.section_length;
+        // Sections without any data packets might use a data offset of zero
+        let next_offset = if section_header.data_offset == 0 {
+            end_offset
+        } else {
+            reader
+                .seek_physical(section_header.data_offset)
+                .read_err("Cannot seek to packet header")?
+        };
+        Ok(Self {
+            reader,
+            limits,
+            next_offset,
+            end_offset,
+            packets: 0,
+            failed: false,
+        })
+    }
+
+    fn read_packet(&mut self) -> Result<RawPacket> {
+        if self.packets >= self.limits.max_packet_count {
+            Error::limit_exceeded(format!(
+                "Binary section exceeds the limit of {} packets",
+                self.limits.max_packet_count
+            ))?
+        }
+        self.packets += 1;
+
+        let start = self.next_offset;
+        self.reader
+            .seek_logical(start)
+            .read_err("Cannot seek to packet header")?;
+        let physical_offset = self.reader.physical_position();
+        let packet = match PacketHeader::read(self.reader)? {
+            PacketHeader::Data(header) => {
+                let mut sizes = vec![0_usize; header.bytestream_count as usize];
+                for size in &mut sizes {
+                    let mut buf = [0_u8; 2];
+                    self.reader
+                        .read_exact(&mut buf)
+                        .read_err("Failed to read data packet buffer sizes")?;
+                    *size = u16::from_le_bytes(buf) as usize;
+                }
+                let content_size = DATA_HEADER_SIZE
+                    + 2 * sizes.len() as u64
+                    + sizes.iter().map(|s| *s as u64).sum::<u64>();
+                if content_size > header.packet_length {
+                    Error::malformed(format!(
+                        "Data packet header and byte streams with {content_size} bytes exceed the packet length of {} bytes",
+                        header.packet_length
+                    ))?
+                }
+                let mut bytestreams = Vec::with_capacity(sizes.len());
+                for size in sizes {
+                    let mut buffer = vec![0_u8; size];
+                    self.reader
+                        .read_exact(&mut buffer)
+                        .read_err("Failed to read data packet buffers")?;
+                    bytestreams.push(buffer);
+                }
+                RawPacket::Data {
+                    physical_offset,
+                    packet_length: header.packet_length,
+                    comp_restart_flag: header.comp_restart_flag,
+                    bytestreams,
+                }
+            }
+            PacketHeader::Index(header) => RawPacket::Index {
+                physical_offset,
+                packet_length: header.packet_length,
+                entry_count: header.entry_count,
+                index_level: header.index_level,
+                payload: self.read_payload(header.packet_length, INDEX_HEADER_SIZE)?,
+            },
+            PacketHeader::Ignored(header) => RawPacket::Ignored {
+                physical_offset,
+                packet_length: header.packet_length,
+                payload: self.read_payload(header.packet_length, IGNORED_HEADER_SIZE)?,
+            },
+        };
+
+        let packet_length = match &packet {
+            RawPacket::Data { packet_length, .. }
+            | RawPacket::Index { packet_length, .. }
+            | RawPacket::Ignored { packet_length, .. } => *packet_length,
+        };
+        self.next_offset = start + packet_length;
+        if self.next_offset > self.end_offset {
+            Error::malformed("Packet extends beyond the end of the compressed vector section")?
+        }
+        Ok(packet)
+    }
+
+    fn read_payload(&mut self, packet_length: u64, header_size: u64) -> Result<Vec<u8>> {
+        let size = packet_length
+            .checked_sub(header_size)
+            .invalid_err("Packet length is smaller than its header")?;
+        let mut payload = vec![0_u8; size as usize];
+        self.reader
+            .read_exact(&mut payload)
+            .read_err("Failed to read packet payload")?;
+        Ok(payload)
+    }
+}
+
+impl<T: Read + Seek> Iterator for PacketReader<'_, T> {
+    /// Each iterator item is a result for a raw packet.
+    type Item = Result<RawPacket>;
+
+    /// Returns the next packet or None if the end of the section was reached.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.next_offset >= self.end_offset {
+            return None;
+        }
+        let packet_index = self.packets;
+        let result = self
+            .read_packet()
+            .map_err(|e| e.with_packet_index(packet_index));
+        if result.is_err() {
+            self.failed = true;
+        }
+        Some(result)
+    }
+}
+
+impl<T: Read + Seek> FusedIterator for PacketReader<'_, T> {}
@@ -1,3 +1,4 @@
+use crate::error::ChecksumError;
 use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
 
 #[cfg(not(feature = "crc32c"))]
@@ -13,9 +14,15 @@ pub struct PagedReader<T: Read + Seek> {
     log_file_size: u64,
     pages: u64,
     reader: T,
+    reader_position: Option<u64>,
     offset: u64,
     page_num: Option<u64>,
-    page_buffer: Vec<u8>,
+    buffer: Vec<u8>,
+    buffer_start: Option<u64>,
+    buffer_pages: u64,
+    verified_pages: u64,
+    pages_per_read: u64,
+    verify: bool,
 
     #[cfg(not(feature = "crc32c"))]
     crc: Crc32,
@@ -53,19 +60,52 @@ impl<T: Read + Seek> PagedReader<T> {
 
         Ok(Self {
             reader,
+            reader_position: None,
             page_size,
             pages,
             phy_file_size,
             log_file_size: pages * (page_size - CHECKSUM_SIZE),
-            page_buffer: vec![0_u8; page_size as usize],
+            buffer: Vec::new(),
+            buffer_start: None,
+            buffer_pages: 0,
+            verified_pages: 0,
+            pages_per_read: 1,
             page_num: None,
             offset: 0,
+            verify: true,
 
             #[cfg(not(feature = "crc32c"))]
             crc: Crc32::new(),
         })
     }
 
+    /// Enables or disables the checksum verification when reading pages.
+    /// Verification is enabled by default.
+    pub fn set_verify_checksums(&mut self, verify: bool) {
+        self.verify = verify;
+        self.page_num = None;
+    }
+
+    /// Sets the number of pages that are fetched from the underlying reader at once.
+    /// The checksums of all fetched pages are verified together.
+    /// Values below one are treated as one, which is the default.
+    pub fn set_pages_per_read(&mut self, pages: u64) {
+        self.pages_per_read = pages.max(1);
+        self.buffer_start = None;
+        self.page_num = None;
+    }
+
+    /// Verifies the checksums of all pages, independent of the verification setting.
+    /// Returns the number of verified pages.
+    pub fn verify_all_pages(&mut self) -> Result<u64> {
+        let verify = self.verify;
+        self.verify = true;
+        self.page_num = None;
+        let result = (0..self.pages).try_for_each(|page| self.read_page(page));
+        self.verify = verify;
+        result.map(|_| self.pages)
+    }
+
     /// Seeking to a physical file address as offset relative to the start of the file.
     /// Will return the new logical offset inside the file or an error.
     pub fn seek_physical(&mut self, offset: u64) -> Result<u64> {
@@ -81,6 +121,24 @@ impl<T: Read + Seek> PagedReader<T> {
         Ok(self.offset)
     }
 
+    /// Seeking to a logical file address as offset relative to the start of the file.
+    pub fn seek_logical(&mut self, offset: u64) -> Result<()> {
+        if offset >= self.log_file_size {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Logical offset {offset} is behind end of file"),
+            ))?;
+        }
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Moves the logical offset forward without reading or validating the skipped bytes.
+    /// Pages are only loaded when reading from them.
+    pub fn skip(&mut self, bytes: u64) {
+        self.offset += bytes;
+    }
+
     fn read_page(&mut self, page: u64) -> Result<()> {
         if page >= self.pages {
             let max = self.pages - 1;
@@ -89,19 +147,68 @@ impl<T: Read + Seek> PagedReader<T> {
                 format!("Page {page} does not exist, only page numbers 0..{max} are valid"),
             ))?;
         }
+        let start = match self.buffer_start {
+            Some(start) if page >= start && page < start + self.buffer_pages => start,
+            _ => self.fill_buffer(page)?,
+        };
+        let index = page - start;
+        if self.verify && index >= self.verified_pages {
+            // Only pages behind an invalid page end up here
+            self.verify_page(index, page)?;
+            if index == self.verified_pages {
+                self.verified_pages += 1;
+            }
+        }
+        self.page_num = Some(page);
+        Ok(())
+    }
+
+    /// Fetches multiple pages starting with the given page into the buffer.
+    /// When verification is enabled, the checksums of all fetched pages are checked
+    /// until the first invalid page. Returns the first page in the buffer.
+    fn fill_buffer(&mut self, page: u64) -> Result<u64> {
+        self.page_num = None;
+        self.buffer_start = None;
+        let pages = self.pages_per_read.min(self.pages - page);
         let offset = page * self.page_size;
-        self.reader.seek(SeekFrom::Start(offset))?;
-        self.reader.read_exact(&mut self.page_buffer)?;
+        self.buffer.resize((pages * self.page_size) as usize, 0);
+        // Avoid seeking for sequential reads, since it would discard the buffers of readers
+        if self.reader_position != Some(offset) {
+            self.reader.seek(SeekFrom::Start(offset))?;
+        }
+        self.reader_position = None;
+        self.reader.read_exact(&mut self.buffer)?;
+        self.reader_position = Some(offset + pages * self.page_size);
+        self.buffer_start = Some(page);
+        self.buffer_pages = pages;
+        self.verified_pages = 0;
+        if self.verify {
+            while self.verified_pages < pages
+                && self
+                    .verify_page(self.verified_pages, page + self.verified_pages)
+                    .is_ok()
+            {
+                self.verified_pages += 1;
+            }
+        }
+        Ok(page)
+    }
+
+    /// Checks the checksum of the page at the given index inside the buffer.
+    fn verify_page(&mut self, index: u64, page: u64) -> Result<()> {
         let data_size = self.page_size - CHECKSUM_SIZE;
-        let expected_checksum = &self.page_buffer[data_size as usize..];
+        let start = (index * self.page_size) as usize;
+        let page_buffer = &self.buffer[start..start + self.page_size as usize];
+        let mut expected_checksum = [0_u8; CHECKSUM_SIZE as usize];
+        expected_checksum.copy_from_slice(&page_buffer[data_size as usize..]);
 
         // Simple & slower default included SW implementation
         #[cfg(not(feature = "crc32c"))]
-        let crc = self.crc.calculate(&self.page_buffer[0..data_size as usize]);
+        let crc = self.crc.calculate(&page_buffer[0..data_size as usize]);
 
         // Optional faster external crate with HW support
         #[cfg(feature = "crc32c")]
-        let crc = crc32c::crc32c(&self.page_buffer[0..data_size as usize]);
+        let crc = crc32c::crc32c(&page_buffer[0..data_size as usize]);
 
         // The standard says all binary values are stored as little endian,
         // but for some reason E57 files contain the checksum in big endian order.
@@ -110,17 +217,39 @@ impl<T: Read + Seek> PagedReader<T> {
         let calculated_checksum = crc.to_be_bytes();
 
         if expected_checksum != calculated_checksum {
-            self.page_num = None;
+            let offset = page * self.page_size;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                page,
+                physical_offset = offset,
+                "Detected invalid page checksum"
+            );
             Err(Error::new(
                 ErrorKind::InvalidData,
-                format!("Detected invalid checksum (expected: {expected_checksum:?}, actual: {calculated_checksum:?}) for page {page}")
+                ChecksumError {
+                    page,
+                    physical_offset: offset,
+                    expected: expected_checksum,
+                    calculated: calculated_checksum,
+                },
             ))
         } else {
-            self.page_num = Some(page);
             Ok(())
         }
     }
 
+    /// Returns the current logical offset inside the file.
+    pub fn logical_position(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the current physical offset inside the file.
+    pub fn physical_position(&self) -> u64 {
+        let data_size = self.page_size - CHECKSUM_SIZE;
+        let page = self.offset / data_size;
+        page * self.page_size + self.offset % data_size
+    }
+
     /// Do some skipping to next 4-byte-aligned offset, if needed.
     pub fn align(&mut self) -> Result<()> {
         let off_alignment = self.offset % 4;
@@ -150,9 +279,9 @@ impl<T: Read + Seek> Read for PagedReader<T> {
         let page_offset = self.offset % (self.page_size - CHECKSUM_SIZE);
         let page_readable = self.page_size - CHECKSUM_SIZE - page_offset;
         let read_size = usize::min(buf.len(), page_readable as usize);
-        buf[..read_size].copy_from_slice(
-            &self.page_buffer[page_offset as usize..page_offset as usize + read_size],
-        );
+        let start = page - self.buffer_start.unwrap_or(page);
+        let start = (start * self.page_size + page_offset) as usize;
+        buf[..read_size].copy_from_slice(&self.buffer[start..start + read_size]);
         self.offset += read_size as u64;
         Ok(read_size)
     }
@@ -209,6 +338,19 @@ mod tests {
         assert_eq!(buf.len(), 0);
     }
 
+    #[test]
+    fn corrupt_page_without_verification() {
+        let data = vec![0_u8; 256];
+        let cursor = Cursor::new(data);
+        let mut reader = PagedReader::new(cursor, 128).unwrap();
+        reader.set_verify_checksums(false);
+
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf.len(), 248);
+        assert!(reader.verify_all_pages().is_err());
+    }
+
     #[test]
     fn physical_seek() {
         let file = File::open("testdata/bunnyDouble.e57").unwrap();
@@ -239,6 +381,22 @@ mod tests {
         assert_eq!(reader.read_to_end(&mut buffer).unwrap(), 0);
     }
 
+    #[test]
+    fn skip_corrupt_page() {
+        let mut data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+        data[PAGE_SIZE as usize + 10] ^= 0xFF;
+        let mut reader = PagedReader::new(Cursor::new(data), PAGE_SIZE).unwrap();
+
+        // Skipped pages are not read and validated
+        let mut buffer = [0_u8; 4];
+        reader.read_exact(&mut buffer).unwrap();
+        reader.skip(2 * (PAGE_SIZE - CHECKSUM_SIZE));
+        reader.read_exact(&mut buffer).unwrap();
+
+        reader.seek_physical(PAGE_SIZE).unwrap();
+        assert!(reader.read_exact(&mut buffer).is_err());
+    }
+
     #[test]
     fn align() {
         let data = vec![0_u8; 128];
@@ -255,4 +413,27 @@ mod tests {
         reader.align().unwrap();
         assert_eq!(reader.offset, ALIGNMENT_SIZE);
     }
+
+    #[test]
+    fn corrupt_page_inside_batch() {
+        let file = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+        let mut data = file.clone();
+        data[2 * PAGE_SIZE as usize + 10] ^= 0xFF;
+        let mut reader = PagedReader::new(Cursor::new(data), PAGE_SIZE).unwrap();
+        reader.set_pages_per_read(4);
+
+        // Pages before the corrupt page are still readable
+        let data_size = (PAGE_SIZE - CHECKSUM_SIZE) as usize;
+        let mut buffer = vec![0_u8; 2 * data_size];
+        reader.read_exact(&mut buffer).unwrap();
+        assert_eq!(buffer[..100], file[..100]);
+        let error = reader.read(&mut buffer).unwrap_err();
+        let inner = error.get_ref().unwrap();
+        assert_eq!(inner.downcast_ref::<ChecksumError>().unwrap().page, 2);
+
+        // Pages behind the corrupt page are verified individually
+        reader.seek_logical(3 * data_size as u64).unwrap();
+        reader.read_exact(&mut buffer[..data_size]).unwrap();
+        assert_eq!(buffer[..100], file[3 * PAGE_SIZE as usize..][..100]);
+    }
 }
@@ -2,20 +2,40 @@ use crate::paged_reader::PagedReader;
 use crate::queue_reader::QueueReader;
 use crate::PointCloud;
 use crate::RawValues;
+use crate::ResourceLimits;
 use crate::Result;
 use std::io::{Read, Seek};
+use std::iter::FusedIterator;
 
 /// Iterate over all raw points of a point cloud for reading.
+///
+/// The iterator will stop and return only None after the first error.
 pub struct PointCloudReaderRaw<'a, T: Read + Seek> {
     queue_reader: QueueReader<'a, T>,
     prototype_len: usize,
     records: u64,
     read: u64,
+    failed: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 impl<'a, T: Read + Seek> PointCloudReaderRaw<'a, T> {
-    pub(crate) fn new(pc: &PointCloud, reader: &'a mut PagedReader<T>) -> Result<Self> {
-        let queue_reader = QueueReader::new(pc, reader)?;
+    pub(crate) fn new(
+        pc: &PointCloud,
+        reader: &'a mut PagedReader<T>,
+        limits: ResourceLimits,
+    ) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "read_pointcloud",
+            guid = pc.guid.as_deref(),
+            offset = pc.file_offset,
+            records = pc.records
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let queue_reader = QueueReader::new(pc, reader, limits)?;
         let prototype_len = pc.prototype.len();
         let records = pc.records;
         Ok(Self {
@@ -23,42 +43,74 @@ impl<'a, T: Read + Seek> PointCloudReaderRaw<'a, T> {
             prototype_len,
             records,
             read: 0,
+            failed: false,
+            #[cfg(feature = "tracing")]
+            span: span.clone(),
         })
     }
 }
 
-impl<'a, T: Read + Seek> Iterator for PointCloudReaderRaw<'a, T> {
-    /// Each iterator item is a result for an extracted point.
-    type Item = Result<RawValues>;
-
-    /// Returns the next available point or None if the end was reached.
-    fn next(&mut self) -> Option<Self::Item> {
-        // Already read all points?
-        if self.read >= self.records {
+impl<T: Read + Seek> PointCloudReaderRaw<'_, T> {
+    /// Reads the next point into an existing vector of values.
+    ///
+    /// This works like [`Iterator::next()`], but reuses the supplied vector
+    /// instead of allocating a new vector for every point.
+    /// The values are stored in the same order as the records of the prototype.
+    /// Returns None if the end was reached or after the first error.
+    pub fn next_into(&mut self, values: &mut RawValues) -> Option<Result<()>> {
+        // Already read all points or failed before?
+        if self.read >= self.records || self.failed {
             return None;
         }
+        #[cfg(feature = "tracing")]
+        let _entered = self.span.enter();
 
         // Refill property queues if required
         if self.queue_reader.available() < 1 {
             if let Err(err) = self.queue_reader.advance() {
+                self.failed = true;
                 return Some(Err(err));
             }
         }
 
         // Extract next point
-        let mut point = RawValues::with_capacity(self.prototype_len);
-        match self.queue_reader.pop_point(&mut point) {
+        match self.queue_reader.pop_point(values) {
             Ok(()) => {
                 self.read += 1;
-                Some(Ok(point))
+                #[cfg(feature = "tracing")]
+                if self.read == self.records {
+                    let packets = self.queue_reader.packets();
+                    tracing::debug!(records = self.read, packets, "Finished reading point cloud");
+                }
+                Some(Ok(()))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
             }
-            Err(err) => Some(Err(err)),
         }
     }
+}
+
+impl<'a, T: Read + Seek> Iterator for PointCloudReaderRaw<'a, T> {
+    /// Each iterator item is a result for an extracted point.
+    type Item = Result<RawValues>;
+
+    /// Returns the next available point or None if the end was reached.
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut point = RawValues::with_capacity(self.prototype_len);
+        self.next_into(&mut point)
+            .map(|result| result.map(|_| point))
+    }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            return (0, Some(0));
+        }
         let overall = self.records;
         let remaining = overall - self.read;
         (remaining as usize, Some(remaining as usize))
     }
 }
+
+impl<T: Read + Seek> FusedIterator for PointCloudReaderRaw<'_, T> {}
@@ -1,11 +1,13 @@
 use crate::paged_reader::PagedReader;
 use crate::queue_reader::QueueReader;
+use crate::sampling::Sampler;
 use crate::{
-    CartesianCoordinate, Color, Error, Point, PointCloud, RecordName, RecordValue, Result,
-    SphericalCoordinate, Transform, Translation,
+    CartesianCoordinate, Color, Error, Point, PointCloud, RecordDataType, RecordName, RecordValue,
+    ResourceLimits, Result, SphericalCoordinate, Subsampling, Translation,
 };
 use std::collections::VecDeque;
 use std::io::{Read, Seek};
+use std::iter::FusedIterator;
 
 struct Indices {
     cartesian: Option<(usize, usize, usize)>,
@@ -21,42 +23,125 @@ struct Indices {
 }
 
 /// Iterate over all normalized points of a point cloud for reading.
-pub struct PointCloudReaderSimple<'a, T: Read + Seek> {
+///
+/// The generic parameter `F` defines the floating point type of the coordinates.
+/// By default the reader will return points with double precision coordinates.
+///
+/// The iterator will stop and return only None after the first error.
+pub struct PointCloudReaderSimple<'a, T: Read + Seek, F = f64> {
     pc: PointCloud,
     queue_reader: QueueReader<'a, T>,
     transform: bool,
     s2c: bool,
     c2s: bool,
     i2c: bool,
-    rotation: [f64; 9], // Rotation to be applied to all points in post-processing
-    translation: Translation, // Translation to be applied to all points in post-processing
-    indices: Indices,   // Lookup table for point attriutes to index in raw values
-    read: u64,          // Number of points that were already consumed by the client
-    values: Vec<RecordValue>, // Reusable buffer for a set of raw values for a single point
-    points: VecDeque<Point>, // Queue with finished points ready for reading
+    intensity_range: Option<(f64, f64)>, // Intensity limits used for normalization, if enabled
+    color_ranges: Option<[Option<(f64, f64)>; 3]>, // Color limits used for normalization, if enabled
+    range_limits: Option<(f64, f64)>, // Range limits used for the Cartesian conversion, if enabled
+    rotation: [[f64; 3]; 3],          // Rotation to be applied to all points in post-processing
+    translation: Translation,         // Translation to be applied to all points in post-processing
+    unit_scale: f64,                  // Scale factor applied to all decoded lengths
+    indices: Indices,                 // Lookup table for point attriutes to index in raw values
+    sampler: Sampler,                 // Decides which of the decoded points are returned
+    decoded: u64,                     // Number of points that were already decoded from the file
+    read: u64,                        // Number of points that were already consumed by the client
+    values: Vec<RecordValue>,         // Reusable buffer for a set of raw values for a single point
+    points: VecDeque<Point<F>>,       // Queue with finished points ready for reading
     buffer: Vec<Point>, // Reusable buffer for extracting new points and transforming them
+    reprojection: Option<Reprojection>, // Optional user transformation of Cartesian coordinates
+    failed: bool,       // Set after the first error to stop the iteration
+    #[cfg(feature = "tracing")]
+    span: tracing::Span, // Span covering the decoding of this point cloud
 }
 
+/// Function to transform valid Cartesian coordinates into another coordinate system,
+/// see [`PointCloudReaderSimple::set_reprojection()`].
+pub type Reprojection = Box<dyn FnMut([f64; 3]) -> Result<[f64; 3]>>;
+
 impl<'a, T: Read + Seek> PointCloudReaderSimple<'a, T> {
-    pub(crate) fn new(pc: &PointCloud, reader: &'a mut PagedReader<T>) -> Result<Self> {
+    pub(crate) fn new(
+        pc: &PointCloud,
+        reader: &'a mut PagedReader<T>,
+        limits: ResourceLimits,
+    ) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "read_pointcloud",
+            guid = pc.guid.as_deref(),
+            offset = pc.file_offset,
+            records = pc.records
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
         let (rotation, translation) = Self::prepare_transform(pc);
         Ok(Self {
             rotation,
             translation,
+            unit_scale: 1.0,
             pc: pc.clone(),
             indices: Self::prepare_indices(pc),
-            queue_reader: QueueReader::new(pc, reader)?,
+            queue_reader: QueueReader::new(pc, reader, limits)?,
             transform: true,
             s2c: true,
             c2s: false,
             i2c: true,
+            intensity_range: None,
+            color_ranges: None,
+            range_limits: None,
+            sampler: Sampler::new(Subsampling::All, pc.records),
+            decoded: 0,
             read: 0,
             values: Vec::with_capacity(pc.prototype.len()),
             points: VecDeque::new(),
             buffer: Vec::new(),
+            reprojection: None,
+            failed: false,
+            #[cfg(feature = "tracing")]
+            span: span.clone(),
         })
     }
 
+    /// Converts the reader into a reader that returns points with single precision coordinates.
+    ///
+    /// This halves the memory required for the coordinates of each point,
+    /// which might be useful for viewers and other cases where a limited precision is sufficient.
+    /// All decoding, conversions and the pose transformation are still done with double precision,
+    /// only the final coordinates are converted.
+    ///
+    /// This is a lossy conversion, see the `From<Point>` implementation of [`Point<f32>`](crate::Point)
+    /// for details about the remaining precision.
+    /// If the pose of the point cloud moves it far away from the origin,
+    /// consider disabling the pose application to keep more precision.
+    pub fn single_precision(self) -> PointCloudReaderSimple<'a, T, f32> {
+        PointCloudReaderSimple {
+            pc: self.pc,
+            queue_reader: self.queue_reader,
+            transform: self.transform,
+            s2c: self.s2c,
+            c2s: self.c2s,
+            i2c: self.i2c,
+            intensity_range: self.intensity_range,
+            color_ranges: self.color_ranges,
+            range_limits: self.range_limits,
+            rotation: self.rotation,
+            translation: self.translation,
+            unit_scale: self.unit_scale,
+            indices: self.indices,
+            sampler: self.sampler,
+            decoded: self.decoded,
+            read: self.read,
+            values: self.values,
+            points: self.points.into_iter().map(Point::from).collect(),
+            buffer: self.buffer,
+            reprojection: self.reprojection,
+            failed: self.failed,
+            #[cfg(feature = "tracing")]
+            span: self.span,
+        }
+    }
+}
+
+impl<'a, T: Read + Seek, F> PointCloudReaderSimple<'a, T, F> {
     /// If enabled, the iterator will automatically convert spherical to Cartesian coordinates.
     /// Will only replace fully invalid Cartesian coordinates and do nothing otherwise.
     /// Default setting is enabled.
@@ -78,33 +163,78 @@ impl<'a, T: Read + Seek> PointCloudReaderSimple<'a, T> {
         self.i2c = enable;
     }
 
+    /// If enabled, the iterator will normalize intensities with the intensity limits of the point cloud.
+    /// Values outside of the limits are clamped to the range from 0 to 1.
+    /// Point clouds without valid limits will fall back to the minimum and maximum of the intensity record,
+    /// which is always used when this option is disabled.
+    /// Default setting is disabled.
+    pub fn use_intensity_limits(&mut self, enable: bool) {
+        self.intensity_range = if enable {
+            intensity_range(&self.pc)
+        } else {
+            None
+        };
+    }
+
+    /// If enabled, the iterator will normalize colors with the color limits of the point cloud.
+    /// Values outside of the limits are clamped to the range from 0 to 1.
+    /// Color channels without valid limits will fall back to the minimum and maximum of their record,
+    /// which is always used when this option is disabled.
+    /// Use [`Color::to_rgb8()`] to convert the normalized colors into 8-bit values.
+    /// Default setting is disabled.
+    pub fn use_color_limits(&mut self, enable: bool) {
+        self.color_ranges = if enable { color_ranges(&self.pc) } else { None };
+    }
+
+    /// If enabled, spherical coordinates with a range outside of the range limits
+    /// stored in the spherical bounds of the point cloud are not converted to Cartesian coordinates.
+    /// The spherical coordinates are still returned unchanged.
+    /// Point clouds without valid range limits are not affected.
+    /// Default setting is disabled.
+    pub fn use_range_limits(&mut self, enable: bool) {
+        self.range_limits = if enable { range_limits(&self.pc) } else { None };
+    }
+
     /// If enabled, the iterator will apply the point cloud pose to the Cartesian coordinates.
     /// Default setting is enabled.
     pub fn apply_pose(&mut self, enable: bool) {
         self.transform = enable;
     }
 
-    fn prepare_transform(pc: &PointCloud) -> ([f64; 9], Translation) {
-        let t = if let Some(t) = &pc.transform {
-            t.clone()
-        } else {
-            Transform::default()
-        };
-        let q = &t.rotation;
-        (
-            [
-                q.w * q.w + q.x * q.x - q.y * q.y - q.z * q.z,
-                2.0 * (q.x * q.y + q.w * q.z),
-                2.0 * (q.x * q.z - q.w * q.y),
-                2.0 * (q.x * q.y - q.w * q.z),
-                q.w * q.w + q.y * q.y - q.x * q.x - q.z * q.z,
-                2.0 * (q.y * q.z + q.w * q.x),
-                2.0 * (q.x * q.z + q.w * q.y),
-                2.0 * (q.y * q.z - q.w * q.x),
-                q.w * q.w + q.z * q.z - q.x * q.x - q.y * q.y,
-            ],
-            t.translation,
-        )
+    /// Sets a function to transform the Cartesian coordinates of all points into another coordinate system.
+    ///
+    /// The function is called for each valid Cartesian coordinate after the pose was applied,
+    /// so it receives coordinates in the coordinate system of the file.
+    /// See [`E57Reader::coordinate_reference_system()`](crate::E57Reader::coordinate_reference_system)
+    /// for the coordinate system of the file.
+    /// This allows to plug in a projection library like PROJ to convert points on the fly,
+    /// the library itself does not include or depend on any projection library.
+    /// Errors returned from the function will stop the iteration.
+    /// Default setting is no reprojection.
+    pub fn set_reprojection(&mut self, reprojection: Option<Reprojection>) {
+        self.reprojection = reprojection;
+    }
+
+    /// Defines which points of the point cloud are returned by the iterator.
+    /// Skipped points are still decoded, but are discarded before any further processing.
+    /// The selection is based on the position of the points in the point cloud,
+    /// so this should be set before reading the first point.
+    /// Default setting is to return all points.
+    pub fn subsampling(&mut self, subsampling: Subsampling) -> Result<()> {
+        subsampling.validate()?;
+        self.sampler = Sampler::new(subsampling, self.pc.records);
+        Ok(())
+    }
+
+    /// The scale factor is configured for the whole file with the reader options.
+    /// The pose translation in the descriptor is expected to be already scaled.
+    pub(crate) fn set_unit_scale(&mut self, factor: f64) {
+        self.unit_scale = factor;
+    }
+
+    fn prepare_transform(pc: &PointCloud) -> ([[f64; 3]; 3], Translation) {
+        let t = pc.transform.clone().unwrap_or_default();
+        (t.rotation.to_rotation_matrix(), t.translation)
     }
 
     fn prepare_indices(pc: &PointCloud) -> Indices {
@@ -146,10 +276,7 @@ impl<'a, T: Read + Seek> PointCloudReaderSimple<'a, T> {
         }
     }
 
-    fn pop_point(&mut self) -> Result<Point> {
-        // Read raw values of the point from queue
-        self.queue_reader.pop_point(&mut self.values)?;
-
+    fn create_point(&self) -> Result<Point> {
         // Some shortcuts for better readability
         let proto = &self.pc.prototype;
         let values = &self.values;
@@ -228,19 +355,11 @@ impl<'a, T: Read + Seek> PointCloudReaderSimple<'a, T> {
         };
         let color = if let Some(ind) = indices.color {
             if color_invalid == 0 {
+                let ranges = self.color_ranges.unwrap_or_default();
                 Some(Color {
-                    // Use unwrap_or() to make the simple iterator
-                    // more robust against weird files that forgot
-                    // to add proper min/max values.
-                    red: values[ind.0]
-                        .to_unit_f32(&proto[ind.0].data_type)
-                        .unwrap_or(0.0),
-                    green: values[ind.1]
-                        .to_unit_f32(&proto[ind.1].data_type)
-                        .unwrap_or(0.0),
-                    blue: values[ind.2]
-                        .to_unit_f32(&proto[ind.2].data_type)
-                        .unwrap_or(0.0),
+                    red: normalize(&values[ind.0], &proto[ind.0].data_type, ranges[0])?,
+                    green: normalize(&values[ind.1], &proto[ind.1].data_type, ranges[1])?,
+                    blue: normalize(&values[ind.2], &proto[ind.2].data_type, ranges[2])?,
                 })
             } else if color_invalid == 1 {
                 None
@@ -263,14 +382,11 @@ impl<'a, T: Read + Seek> PointCloudReaderSimple<'a, T> {
         };
         let intensity = if let Some(ind) = indices.intensity {
             if intensity_invalid == 0 {
-                Some(
-                    // Use unwrap_or() to make the simple iterator
-                    // more robust against weird files that forgot
-                    // to add proper min/max values.
-                    values[ind]
-                        .to_unit_f32(&proto[ind].data_type)
-                        .unwrap_or(0.0),
-                )
+                Some(normalize(
+                    &values[ind],
+                    &proto[ind].data_type,
+                    self.intensity_range,
+                )?)
             } else if intensity_invalid == 1 {
                 None
             } else {
@@ -305,45 +421,49 @@ impl<'a, T: Read + Seek> PointCloudReaderSimple<'a, T> {
             column,
         })
     }
-}
-
-impl<'a, T: Read + Seek> Iterator for PointCloudReaderSimple<'a, T> {
-    /// Each iterator item is a result for an extracted point.
-    type Item = Result<Point>;
-
-    /// Returns the next available point or None if the end was reached.
-    fn next(&mut self) -> Option<Self::Item> {
-        // Already read all points?
-        if self.read >= self.pc.records {
-            return None;
-        }
 
-        // Is there a point available in the output queue?
-        if let Some(point) = self.points.pop_front() {
-            self.read += 1;
-            return Some(Ok(point));
-        }
+    fn refill(&mut self) -> Result<()>
+    where
+        Point<F>: From<Point>,
+    {
+        #[cfg(feature = "tracing")]
+        let _entered = self.span.enter();
 
         // Refill queues with raw point values
-        if let Err(err) = self.queue_reader.advance() {
-            return Some(Err(err));
-        }
+        self.queue_reader.advance()?;
 
         // Read raw point values as simple point, add to buffer
+        // (bit packed byte streams might contain some padding values at the end)
         let available = self.queue_reader.available();
-        self.buffer.reserve(available);
-        for _ in 0..available {
-            let p = match self.pop_point() {
-                Ok(p) => p,
-                Err(err) => return Some(Err(err)),
-            };
-            self.buffer.push(p);
+        let remaining = (self.pc.records - self.decoded) as usize;
+        for _ in 0..available.min(remaining) {
+            self.queue_reader.pop_point(&mut self.values)?;
+            let index = self.decoded;
+            self.decoded += 1;
+            if self.sampler.keep(index) {
+                let p = self.create_point()?;
+                self.buffer.push(p);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        if self.decoded == self.pc.records {
+            let packets = self.queue_reader.packets();
+            tracing::debug!(
+                records = self.decoded,
+                packets,
+                "Finished reading point cloud"
+            );
         }
 
         // Post-processing of the points in the buffer
+        if self.unit_scale != 1.0 {
+            for p in self.buffer.iter_mut() {
+                scale_point(p, self.unit_scale);
+            }
+        }
         if self.s2c {
             for p in self.buffer.iter_mut() {
-                convert_to_cartesian(p);
+                convert_to_cartesian(p, self.range_limits);
             }
         }
         if self.c2s {
@@ -361,36 +481,164 @@ impl<'a, T: Read + Seek> Iterator for PointCloudReaderSimple<'a, T> {
                 transform_point(p, &self.rotation, &self.translation);
             }
         }
+        if let Some(reprojection) = &mut self.reprojection {
+            for p in self.buffer.iter_mut() {
+                if let CartesianCoordinate::Valid { x, y, z } = &mut p.cartesian {
+                    [*x, *y, *z] = reprojection([*x, *y, *z])?;
+                }
+            }
+        }
 
         // Move points from buffer to output queue
-        self.points.reserve(available);
+        self.points.reserve(self.buffer.len());
         for p in self.buffer.drain(..) {
-            self.points.push_back(p);
+            self.points.push_back(p.into());
         }
 
-        // Get and return one of the new points
-        if let Some(point) = self.points.pop_front() {
-            self.read += 1;
-            Some(Ok(point))
+        if available == 0 {
+            Error::internal("Cannot read next point because of logic error")
         } else {
-            Some(Error::internal(
-                "Cannot read next point because of logic error",
-            ))
+            Ok(())
+        }
+    }
+}
+
+impl<'a, T: Read + Seek, F> Iterator for PointCloudReaderSimple<'a, T, F>
+where
+    Point<F>: From<Point>,
+{
+    /// Each iterator item is a result for an extracted point.
+    type Item = Result<Point<F>>;
+
+    /// Returns the next available point or None if the end was reached.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Is there a point available in the output queue?
+            if let Some(point) = self.points.pop_front() {
+                self.read += 1;
+                return Some(Ok(point));
+            }
+
+            // Already decoded all points or failed before?
+            if self.decoded >= self.pc.records || self.failed {
+                return None;
+            }
+
+            // Decode more points into the output queue
+            if let Err(err) = self.refill() {
+                self.failed = true;
+                self.points.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else if let Some(overall) = self.sampler.selected() {
+            let remaining = overall - self.read;
+            (remaining as usize, Some(remaining as usize))
+        } else {
+            let undecoded = self.pc.records - self.decoded;
+            let queued = self.points.len();
+            (queued, Some(undecoded as usize + queued))
+        }
+    }
+}
+
+impl<T: Read + Seek, F> FusedIterator for PointCloudReaderSimple<'_, T, F> where
+    Point<F>: From<Point>
+{
+}
+
+impl<'a, T: Read + Seek, F> PointCloudReaderSimple<'a, T, F>
+where
+    Point<F>: From<Point>,
+{
+    /// Reads up to `max` points and appends them to the supplied vector.
+    ///
+    /// Reusing the same vector for multiple calls avoids allocations for each chunk.
+    /// Returns the number of appended points, which is only smaller than `max`
+    /// when the end of the point cloud was reached.
+    pub fn read_chunk(&mut self, points: &mut Vec<Point<F>>, max: usize) -> Result<usize> {
+        let len = points.len();
+        for p in self.by_ref().take(max) {
+            points.push(p?);
+        }
+        Ok(points.len() - len)
+    }
+
+    /// Turns the reader into an iterator that returns chunks of points with a fixed size.
+    ///
+    /// All chunks contain exactly `chunk_size` points, except for the last chunk,
+    /// which contains the remaining points. The chunk size must be greater than zero.
+    /// The iterator will stop and return only None after the first error.
+    pub fn read_chunks(self, chunk_size: usize) -> Result<PointChunks<'a, T, F>> {
+        if chunk_size == 0 {
+            Error::invalid("Chunk size must be greater than zero")?
+        }
+        Ok(PointChunks {
+            reader: self,
+            chunk_size,
+        })
+    }
+}
+
+/// Iterate over chunks of normalized points of a point cloud.
+///
+/// Created by [`PointCloudReaderSimple::read_chunks()`].
+pub struct PointChunks<'a, T: Read + Seek, F = f64> {
+    reader: PointCloudReaderSimple<'a, T, F>,
+    chunk_size: usize,
+}
+
+impl<T: Read + Seek, F> Iterator for PointChunks<'_, T, F>
+where
+    Point<F>: From<Point>,
+{
+    /// Each iterator item is a result for a chunk of points.
+    type Item = Result<Vec<Point<F>>>;
+
+    /// Returns the next chunk of points or None if the end was reached.
+    fn next(&mut self) -> Option<Self::Item> {
+        // Avoid allocating a full chunk for the last few points or when reading small point clouds
+        let remaining = self.reader.size_hint().1.unwrap_or(self.chunk_size);
+        let mut chunk = Vec::with_capacity(self.chunk_size.min(remaining));
+        match self.reader.read_chunk(&mut chunk, self.chunk_size) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(chunk)),
+            Err(err) => Some(Err(err)),
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let overall = self.pc.records;
-        let remaining = overall - self.read;
-        (remaining as usize, Some(remaining as usize))
+        let (min, max) = self.reader.size_hint();
+        (
+            min.div_ceil(self.chunk_size),
+            max.map(|m| m.div_ceil(self.chunk_size)),
+        )
     }
 }
 
-fn transform_point(p: &mut Point, rotation: &[f64; 9], translation: &Translation) {
+impl<T: Read + Seek, F> FusedIterator for PointChunks<'_, T, F> where Point<F>: From<Point> {}
+
+fn scale_point(p: &mut Point, factor: f64) {
+    if let CartesianCoordinate::Valid { x, y, z } = &mut p.cartesian {
+        *x *= factor;
+        *y *= factor;
+        *z *= factor;
+    }
+    if let SphericalCoordinate::Valid { range, .. } = &mut p.spherical {
+        *range *= factor;
+    }
+}
+
+fn transform_point(p: &mut Point, rotation: &[[f64; 3]; 3], translation: &Translation) {
     if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
-        let nx = rotation[0] * x + rotation[3] * y + rotation[6] * z;
-        let ny = rotation[1] * x + rotation[4] * y + rotation[7] * z;
-        let nz = rotation[2] * x + rotation[5] * y + rotation[8] * z;
+        let nx = rotation[0][0] * x + rotation[0][1] * y + rotation[0][2] * z;
+        let ny = rotation[1][0] * x + rotation[1][1] * y + rotation[1][2] * z;
+        let nz = rotation[2][0] * x + rotation[2][1] * y + rotation[2][2] * z;
         p.cartesian = CartesianCoordinate::Valid {
             x: nx + translation.x,
             y: ny + translation.y,
@@ -399,65 +647,118 @@ fn transform_point(p: &mut Point, rotation: &[f64; 9], translation: &Translation
     }
 }
 
-fn convert_to_cartesian(p: &mut Point) {
-    if let CartesianCoordinate::Valid { .. } = p.cartesian {
-        // Abort if there is already a valid coordinate
-        return;
-    } else if let SphericalCoordinate::Valid {
-        range,
-        azimuth,
-        elevation,
-    } = p.spherical
+fn convert_to_cartesian(p: &mut Point, range_limits: Option<(f64, f64)>) {
+    if let (Some((min, max)), SphericalCoordinate::Valid { range, .. }) =
+        (range_limits, &p.spherical)
     {
-        // Convert valid spherical coordinate to valid Cartesian coordinate
-        let cos_ele = f64::cos(elevation);
-        p.cartesian = CartesianCoordinate::Valid {
-            x: range * cos_ele * f64::cos(azimuth),
-            y: range * cos_ele * f64::sin(azimuth),
-            z: range * f64::sin(elevation),
-        };
-        return;
-    }
-
-    if let CartesianCoordinate::Direction { .. } = p.cartesian {
-        // Do nothing if there is already a valid direction
-    } else if let SphericalCoordinate::Direction { azimuth, elevation } = p.spherical {
-        // Convert spherical direction coordinate to Cartesian direction
-        let cos_ele = f64::cos(elevation);
-        p.cartesian = CartesianCoordinate::Direction {
-            x: 1.0 * cos_ele * f64::cos(azimuth),
-            y: 1.0 * cos_ele * f64::sin(azimuth),
-            z: 1.0 * f64::sin(elevation),
-        };
+        if *range < min || *range > max {
+            return;
+        }
+    }
+    let converted = p.spherical.to_cartesian();
+    match (&p.cartesian, &converted) {
+        // Never replace a valid coordinate or a direction with less information
+        (CartesianCoordinate::Valid { .. }, _) => {}
+        (CartesianCoordinate::Direction { .. }, CartesianCoordinate::Valid { .. }) => {
+            p.cartesian = converted;
+        }
+        (CartesianCoordinate::Direction { .. }, _) => {}
+        (CartesianCoordinate::Invalid, _) => p.cartesian = converted,
     }
 }
 
 fn convert_to_spherical(p: &mut Point) {
-    if let SphericalCoordinate::Valid { .. } = p.spherical {
-        // Abort if there is already a valid coordinate
-        return;
-    } else if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
-        // Convert valid Cartesian coordinate to valid spherical coordinate
-        let r = f64::sqrt(x * x + y * y + z * z);
-        p.spherical = SphericalCoordinate::Valid {
-            range: r,
-            azimuth: f64::atan2(y, x),
-            elevation: f64::asin(z / r),
-        };
-        return;
+    let converted = p.cartesian.to_spherical();
+    match (&p.spherical, &converted) {
+        // Never replace a valid coordinate or a direction with less information
+        (SphericalCoordinate::Valid { .. }, _) => {}
+        (SphericalCoordinate::Direction { .. }, SphericalCoordinate::Valid { .. }) => {
+            p.spherical = converted;
+        }
+        (SphericalCoordinate::Direction { .. }, _) => {}
+        (SphericalCoordinate::Invalid, _) => p.spherical = converted,
+    }
+}
+
+/// Maps a raw value to the range from 0 to 1 using the supplied limits.
+/// Without limits the minimum and maximum of the record data type are used.
+fn normalize(
+    value: &RecordValue,
+    data_type: &RecordDataType,
+    range: Option<(f64, f64)>,
+) -> Result<f32> {
+    if let Some((min, max)) = range {
+        let value = value.to_f64(data_type)?;
+        Ok(((value - min) / (max - min)).clamp(0.0, 1.0) as f32)
+    } else {
+        // Use unwrap_or() to make the simple iterator
+        // more robust against weird files that forgot
+        // to add proper min/max values.
+        Ok(value.to_unit_f32(data_type).unwrap_or(0.0))
     }
+}
 
-    if let SphericalCoordinate::Direction { .. } = p.spherical {
-        // Do nothing if there is already a valid direction
-    } else if let CartesianCoordinate::Direction { x, y, z } = p.cartesian {
-        // Convert Cartesian direction coordinate to spherical direction
-        p.spherical = SphericalCoordinate::Direction {
-            azimuth: f64::atan2(y, x),
-            elevation: f64::asin(z / f64::sqrt(x * x + y * y + z * z)),
-        };
+/// Returns the limits of a record as floating point values, if they are usable.
+fn limits_range(
+    pc: &PointCloud,
+    name: RecordName,
+    min: Option<&RecordValue>,
+    max: Option<&RecordValue>,
+) -> Option<(f64, f64)> {
+    let record = pc.prototype.iter().find(|r| r.name == name)?;
+    let min = min?.to_f64(&record.data_type).ok()?;
+    let max = max?.to_f64(&record.data_type).ok()?;
+    if min.is_finite() && max.is_finite() && max > min {
+        Some((min, max))
+    } else {
+        None
     }
 }
 
+/// Returns the intensity limits of the point cloud as floating point values, if they are usable.
+fn intensity_range(pc: &PointCloud) -> Option<(f64, f64)> {
+    let limits = pc.intensity_limits.as_ref()?;
+    let (min, max) = (&limits.intensity_min, &limits.intensity_max);
+    limits_range(pc, RecordName::Intensity, min.as_ref(), max.as_ref())
+}
+
+/// Returns the range limits of the spherical bounds of the point cloud, if they are usable.
+fn range_limits(pc: &PointCloud) -> Option<(f64, f64)> {
+    let bounds = pc.spherical_bounds.as_ref()?;
+    let (min, max) = (bounds.range_min?, bounds.range_max?);
+    if min.is_finite() && max.is_finite() && max >= min {
+        Some((min, max))
+    } else {
+        None
+    }
+}
+
+/// Returns the color limits of the point cloud as floating point values.
+/// Channels without usable limits fall back to the record limits.
+fn color_ranges(pc: &PointCloud) -> Option<[Option<(f64, f64)>; 3]> {
+    let l = pc.color_limits.as_ref()?;
+    Some([
+        limits_range(
+            pc,
+            RecordName::ColorRed,
+            l.red_min.as_ref(),
+            l.red_max.as_ref(),
+        ),
+        limits_range(
+            pc,
+            RecordName::ColorGreen,
+            l.green_min.as_ref(),
+            l.green_max.as_ref(),
+        ),
+        limits_range(
+            pc,
+            RecordName::ColorBlue,
+            l.blue_min.as_ref(),
+            l.blue_max.as_ref(),
+        ),
+    ])
+}
+
 fn convert_intensity(p: &mut Point) {
     if p.color.is_some() {
         // Do nothing if there is already valid color
@@ -514,16 +815,52 @@ mod tests {
             row: -1,
             column: -1,
         };
-        convert_to_cartesian(&mut p);
+        convert_to_cartesian(&mut p, None);
         if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
             assert!(x.abs() < 0.000001);
             assert_eq!(y, 0.0);
             assert_eq!(z, 10.0);
         } else {
-            assert!(false)
+            panic!("Expected valid cartesian coordinate")
         }
     }
 
+    #[test]
+    fn to_cartesian_with_range_limits() {
+        let mut pc = PointCloud::default();
+        assert_eq!(range_limits(&pc), None);
+        pc.spherical_bounds = Some(crate::SphericalBounds {
+            range_min: Some(1.0),
+            range_max: Some(5.0),
+            ..Default::default()
+        });
+        let limits = range_limits(&pc);
+        assert_eq!(limits, Some((1.0, 5.0)));
+
+        let point = |range: f64| Point {
+            cartesian: CartesianCoordinate::Invalid,
+            spherical: SphericalCoordinate::Valid {
+                range,
+                azimuth: 0.0,
+                elevation: 0.0,
+            },
+            color: None,
+            intensity: None,
+            row: -1,
+            column: -1,
+        };
+        let mut inside = point(5.0);
+        convert_to_cartesian(&mut inside, limits);
+        assert!(matches!(
+            inside.cartesian,
+            CartesianCoordinate::Valid { .. }
+        ));
+        let mut outside = point(10.0);
+        convert_to_cartesian(&mut outside, limits);
+        assert_eq!(outside.cartesian, CartesianCoordinate::Invalid);
+        assert_eq!(outside.spherical, point(10.0).spherical);
+    }
+
     #[test]
     fn roundtrip_conversion() {
         let cartesian = [1.0, 2.0, 3.0];
@@ -541,13 +878,66 @@ mod tests {
         };
         convert_to_spherical(&mut point);
         point.cartesian = CartesianCoordinate::Invalid;
-        convert_to_cartesian(&mut point);
+        convert_to_cartesian(&mut point, None);
         if let CartesianCoordinate::Valid { x, y, z } = point.cartesian {
             assert!((x - cartesian[0]).abs() < 0.00001);
             assert!((y - cartesian[1]).abs() < 0.00001);
             assert!((z - cartesian[2]).abs() < 0.00001);
         } else {
-            assert!(false);
+            panic!("Expected valid cartesian coordinate");
         }
     }
+
+    #[test]
+    fn intensity_limits_range() {
+        let mut pc = PointCloud {
+            prototype: vec![crate::Record::INTENSITY_U16],
+            ..Default::default()
+        };
+        assert_eq!(intensity_range(&pc), None);
+
+        pc.intensity_limits = Some(crate::IntensityLimits {
+            intensity_min: Some(RecordValue::Integer(100)),
+            intensity_max: Some(RecordValue::Integer(1100)),
+        });
+        assert_eq!(intensity_range(&pc), Some((100.0, 1100.0)));
+
+        // Empty or inverted limits cannot be used
+        pc.intensity_limits = Some(crate::IntensityLimits {
+            intensity_min: Some(RecordValue::Integer(100)),
+            intensity_max: Some(RecordValue::Integer(100)),
+        });
+        assert_eq!(intensity_range(&pc), None);
+    }
+
+    #[test]
+    fn color_limits_ranges() {
+        let mut pc = PointCloud {
+            prototype: vec![
+                crate::Record::COLOR_RED_U8,
+                crate::Record::COLOR_GREEN_U8,
+                crate::Record::COLOR_BLUE_U8,
+            ],
+            ..Default::default()
+        };
+        assert_eq!(color_ranges(&pc), None);
+
+        // Missing limits of individual channels fall back to the record limits
+        pc.color_limits = Some(crate::ColorLimits {
+            red_min: Some(RecordValue::Integer(0)),
+            red_max: Some(RecordValue::Integer(100)),
+            green_min: None,
+            green_max: None,
+            blue_min: Some(RecordValue::Integer(10)),
+            blue_max: Some(RecordValue::Integer(20)),
+        });
+        let ranges = color_ranges(&pc).unwrap();
+        assert_eq!(ranges, [Some((0.0, 100.0)), None, Some((10.0, 20.0))]);
+
+        let dt = &pc.prototype[0].data_type;
+        let value = RecordValue::Integer(50);
+        assert_eq!(normalize(&value, dt, ranges[0]).unwrap(), 0.5);
+        assert_eq!(normalize(&value, dt, ranges[1]).unwrap(), 50.0 / 255.0);
+        assert_eq!(normalize(&value, dt, ranges[2]).unwrap(), 1.0);
+    }
 }
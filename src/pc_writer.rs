@@ -1,14 +1,26 @@
 use crate::bs_write::ByteStreamWriteBuffer;
 use crate::cv_section::CompressedVectorSectionHeader;
+use crate::dedup::DuplicateFilter;
 use crate::error::Converter;
+use crate::grouping::LineGrouping;
+use crate::morton::MortonSorter;
 use crate::packet::DataPacketHeader;
+#[cfg(feature = "read")]
+use crate::packet_reader::{PacketReader, RawPacket};
 use crate::paged_writer::PagedWriter;
+use crate::record::check_integer_range;
 use crate::CartesianBounds;
+use crate::CartesianCoordinate;
 use crate::ColorLimits;
 use crate::DateTime;
+use crate::DuplicateRemoval;
 use crate::Error;
+use crate::Extension;
 use crate::IndexBounds;
 use crate::IntensityLimits;
+use crate::LineGroup;
+use crate::MortonOrder;
+use crate::Point;
 use crate::PointCloud;
 use crate::RawValues;
 use crate::Record;
@@ -17,14 +29,23 @@ use crate::RecordName;
 use crate::RecordValue;
 use crate::Result;
 use crate::SphericalBounds;
+use crate::SphericalCoordinate;
 use crate::Transform;
 use std::collections::VecDeque;
 use std::io::{Read, Seek, Write};
 
 /// Creates a new point cloud by taking points and writing them into an E57 file.
+///
+/// Call [`PointCloudWriter::finalize()`] after adding all points to complete the point cloud.
+/// If the writer is dropped without being finalized, the point cloud is discarded
+/// as if [`PointCloudWriter::abort()`] was called, as soon as the E57 writer continues
+/// with the next section or is finalized itself.
+/// This is reported as warning with the `tracing` feature or on the standard error output of debug builds.
 pub struct PointCloudWriter<'a, T: Read + Write + Seek> {
     writer: &'a mut PagedWriter<T>,
     pointclouds: &'a mut Vec<PointCloud>,
+    open_section: &'a mut Option<u64>,
+    extensions: &'a [Extension],
     guid: String,
     section_offset: u64,
     section_header: CompressedVectorSectionHeader,
@@ -32,7 +53,16 @@ pub struct PointCloudWriter<'a, T: Read + Write + Seek> {
     prototype: Vec<Record>,
     point_count: u64,
     buffer: VecDeque<RawValues>,
+    byte_streams: Vec<ByteStreamWriteBuffer>,
     max_points_per_packet: usize,
+    restart_interval: u64,
+    packets_since_restart: u64,
+    points_since_restart: u64,
+    restart_pending: bool,
+    strict_float_bounds: bool,
+    baked_transform: Option<BakedTransform>,
+    duplicates: Option<DuplicateFilter>,
+    morton: Option<(MortonOrder, MortonSorter)>,
     cartesian_bounds: Option<CartesianBounds>,
     spherical_bounds: Option<SphericalBounds>,
     index_bounds: Option<IndexBounds>,
@@ -52,29 +82,44 @@ pub struct PointCloudWriter<'a, T: Read + Write + Seek> {
     temperature: Option<f64>,
     humidity: Option<f64>,
     atmospheric_pressure: Option<f64>,
+    line_groups: Option<(String, Vec<LineGroup>)>,
+    custom_xml: Vec<String>,
+    custom_attributes: Vec<String>,
+    unit_scale: f64,
+    scaled_values: Vec<usize>,
+    done: bool,
 }
 
 impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
     pub(crate) fn new(
         writer: &'a mut PagedWriter<T>,
         pointclouds: &'a mut Vec<PointCloud>,
+        open_section: &'a mut Option<u64>,
+        extensions: &'a [Extension],
         guid: &str,
-        prototype: Vec<Record>,
+        mut prototype: Vec<Record>,
+        unit_scale: f64,
     ) -> Result<Self> {
         // Make sure the prototype is not invalid or incomplete
         Self::validate_prototype(&prototype)?;
 
+        // Adjust the length records for the unit scale
+        let scaled_values = scale_prototype(&mut prototype, unit_scale)?;
+
         let section_offset = writer.physical_position()?;
+        *open_section = Some(section_offset);
 
         let mut section_header = CompressedVectorSectionHeader::default();
-        section_header.data_offset = section_offset + CompressedVectorSectionHeader::SIZE;
         section_header.section_length = CompressedVectorSectionHeader::SIZE;
         section_header.write(writer)?;
+        // The header might cross a page boundary, so the data offset must include the checksum
+        section_header.data_offset = writer.physical_position()?;
 
         // Each data packet can contain up to 2^16 bytes and we need some reserved
         // space for header and bytes that are not yet filled and need to be included later.
         let point_size: usize = prototype.iter().map(|p| p.data_type.bit_size()).sum();
-        let max_points_per_packet = (64000 * 8) / point_size;
+        // Records with a bit size of zero do not need any space at all.
+        let max_points_per_packet = (64000 * 8) / point_size.max(1);
 
         // Prepare bounds
         let has_cartesian = prototype.iter().any(|p| p.name == RecordName::CartesianX);
@@ -128,9 +173,12 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
         let intensity = prototype.iter().find(|p| p.name == RecordName::Intensity);
         let intensity_limits = intensity.map(|i| IntensityLimits::from_record_type(&i.data_type));
 
+        let byte_streams = vec![ByteStreamWriteBuffer::new(); prototype.len()];
         Ok(PointCloudWriter {
             writer,
             pointclouds,
+            open_section,
+            extensions,
             guid: guid.to_owned(),
             section_offset,
             section_header,
@@ -138,7 +186,16 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
             prototype,
             point_count: 0,
             buffer: VecDeque::new(),
+            byte_streams,
             max_points_per_packet,
+            restart_interval: 0,
+            packets_since_restart: 0,
+            points_since_restart: 0,
+            restart_pending: false,
+            strict_float_bounds: false,
+            baked_transform: None,
+            duplicates: None,
+            morton: None,
             cartesian_bounds,
             spherical_bounds,
             index_bounds,
@@ -158,9 +215,146 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
             temperature: None,
             humidity: None,
             atmospheric_pressure: None,
+            line_groups: None,
+            custom_xml: Vec::new(),
+            custom_attributes: Vec::new(),
+            unit_scale,
+            scaled_values,
+            done: false,
         })
     }
 
+    /// Set the interval in packets for writing data packets with the compressor restart flag.
+    ///
+    /// Every n-th data packet will have the flag set and start with fresh byte streams,
+    /// which allows consumers to resynchronize in very long sections.
+    /// The packet before a restart packet contains a multiple of eight points since the last restart,
+    /// so that all byte streams end with complete bytes and no padding bits.
+    /// If a packet cannot be aligned like that, the restart is delayed to a later packet.
+    /// Zero disables the restart flag, which is the default.
+    pub fn set_compressor_restart_interval(&mut self, packets: u64) {
+        self.restart_interval = packets;
+    }
+
+    /// Enable or disable the validation of floating point values against
+    /// the minimum and maximum declared in the prototype (disabled by default).
+    ///
+    /// When enabled, adding a point with a float value outside of the declared bounds
+    /// or a NaN value for a bounded record will return an error.
+    /// This is useful for debugging, but adds some overhead for each point.
+    pub fn set_strict_float_bounds(&mut self, enabled: bool) {
+        self.strict_float_bounds = enabled;
+    }
+
+    /// Set a transformation that is applied to the Cartesian coordinates of all added points.
+    ///
+    /// The row-major 4x4 matrix is applied before encoding the points,
+    /// so the stored coordinates and the bounds of the point cloud are already transformed.
+    /// This is useful for consumers that ignore the pose of point clouds,
+    /// but can also be combined with [`PointCloudWriter::set_transform()`].
+    /// Use [`Transform::to_matrix()`] to bake a pose into the coordinates.
+    ///
+    /// The translation uses the same unit as the coordinates of the added points.
+    /// Directions are only rotated and invalid coordinates are left unchanged.
+    /// The matrix must be affine and needs to be set before adding any points.
+    /// Point clouds with spherical coordinates are not supported,
+    /// because they could no longer be consistent with the transformed Cartesian coordinates.
+    pub fn set_baked_transform(&mut self, matrix: Option<[[f64; 4]; 4]>) -> Result<()> {
+        self.check_not_done()?;
+        if self.point_count > 0 {
+            Error::invalid("The baked transform must be set before adding points")?
+        }
+        let Some(mut matrix) = matrix else {
+            self.baked_transform = None;
+            return Ok(());
+        };
+        if matrix.iter().flatten().any(|v| !v.is_finite()) {
+            Error::invalid("The baked transform must only contain finite values")?
+        }
+        if matrix[3] != [0.0, 0.0, 0.0, 1.0] {
+            Error::invalid("The baked transform must be affine with a last row of 0, 0, 0, 1")?
+        }
+        if contains(&self.prototype, RecordName::SphericalAzimuth) {
+            Error::invalid("The baked transform cannot be used with spherical coordinates")?
+        }
+        let find = |name: RecordName| self.prototype.iter().position(|p| p.name == name);
+        let xyz = [
+            find(RecordName::CartesianX),
+            find(RecordName::CartesianY),
+            find(RecordName::CartesianZ),
+        ];
+        let [Some(x), Some(y), Some(z)] = xyz else {
+            Error::invalid("The baked transform requires Cartesian coordinates")?
+        };
+        // Incoming values are already scaled, so the translation needs to be scaled as well
+        for row in &mut matrix[0..3] {
+            row[3] *= self.unit_scale;
+        }
+        self.baked_transform = Some(BakedTransform {
+            matrix,
+            xyz: [x, y, z],
+            state: find(RecordName::CartesianInvalidState),
+        });
+        Ok(())
+    }
+
+    /// Set the mode for dropping duplicate points while they are added (disabled by default).
+    ///
+    /// Duplicates are detected after applying the baked transform.
+    /// The number of dropped points is available with [`PointCloudWriter::dropped_duplicates()`].
+    /// The mode needs to be set before adding any points.
+    pub fn set_duplicate_removal(&mut self, mode: DuplicateRemoval) -> Result<()> {
+        self.check_not_done()?;
+        if self.point_count > 0 {
+            Error::invalid("The duplicate removal must be set before adding points")?
+        }
+        self.duplicates = match mode {
+            DuplicateRemoval::Disabled => None,
+            DuplicateRemoval::Tolerance(t) => Some(DuplicateFilter::new(
+                DuplicateRemoval::Tolerance(t * self.unit_scale),
+                &self.prototype,
+            )?),
+            mode => Some(DuplicateFilter::new(mode, &self.prototype)?),
+        };
+        Ok(())
+    }
+
+    /// Returns the number of points that were dropped as duplicates so far.
+    ///
+    /// The count stays available after finalizing the point cloud.
+    pub fn dropped_duplicates(&self) -> u64 {
+        self.duplicates.as_ref().map_or(0, |d| d.dropped())
+    }
+
+    /// Set options for writing the points sorted by their Morton code (disabled by default).
+    ///
+    /// See [`MortonOrder`] for details about the sorting and the memory usage.
+    /// The chosen order is stored as custom XML element in the metadata of the point cloud,
+    /// see [`PointCloud::morton_resolution()`].
+    /// Requires Cartesian coordinates and cannot be combined with line groups,
+    /// since the points are reordered. The options need to be set before adding any points.
+    pub fn set_morton_order(&mut self, order: Option<MortonOrder>) -> Result<()> {
+        self.check_not_done()?;
+        if self.point_count > 0 {
+            Error::invalid("The Morton order must be set before adding points")?
+        }
+        if order.is_some() && self.line_groups.is_some() {
+            Error::invalid("The Morton order cannot be combined with line groups")?
+        }
+        self.morton = match order {
+            Some(order) => {
+                order.validate()?;
+                let scaled = MortonOrder {
+                    resolution: order.resolution * self.unit_scale,
+                    ..order.clone()
+                };
+                Some((order, MortonSorter::new(scaled, &self.prototype)?))
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
     /// Set optional user-defined name for the point cloud (empty by default).
     pub fn set_name(&mut self, value: Option<String>) {
         self.name = value;
@@ -248,6 +442,57 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
         self.atmospheric_pressure = value;
     }
 
+    /// Set optional grouping of the points into lines (empty by default).
+    /// The ID element name must be the name of a point attribute in the prototype,
+    /// typically `columnIndex` or `rowIndex`, with the same value for all points of a line.
+    /// Each group must describe a range of consecutive points.
+    pub fn set_line_groups(&mut self, id_element_name: &str, groups: Vec<LineGroup>) -> Result<()> {
+        let exists = self.prototype.iter().any(|r| {
+            let tag_name = r.name.tag_name();
+            match r.name.namespace() {
+                Some(ns) => id_element_name == format!("{ns}:{tag_name}"),
+                None => id_element_name == tag_name,
+            }
+        });
+        if !exists {
+            Error::invalid(format!(
+                "Line group ID element name '{id_element_name}' is not part of the prototype"
+            ))?
+        }
+        if groups
+            .iter()
+            .any(|g| g.start_point_index < 0 || g.point_count < 0)
+        {
+            Error::invalid("Start point index and point count of line groups cannot be negative")?
+        }
+        if self.morton.is_some() {
+            Error::invalid("Line groups cannot be combined with the Morton order")?
+        }
+        self.line_groups = Some((id_element_name.to_owned(), groups));
+        Ok(())
+    }
+
+    /// Adds a custom XML element as child of the point cloud element.
+    ///
+    /// The element must use the namespace of a registered extension and needs to be well-formed XML.
+    /// It will be included unchanged in the XML section of the file.
+    /// This can be used to preserve the custom XML of a point cloud read from another file.
+    pub fn add_custom_xml(&mut self, xml: &str) -> Result<()> {
+        Extension::validate_custom_xml(xml, self.extensions)?;
+        self.custom_xml.push(xml.to_owned());
+        Ok(())
+    }
+
+    /// Adds a custom XML attribute to the point cloud element, for example `ven:id="42"`.
+    ///
+    /// The attribute must use the namespace of a registered extension and needs to be well-formed XML.
+    /// This can be used to preserve the custom attributes of a point cloud read from another file.
+    pub fn add_custom_attribute(&mut self, attribute: &str) -> Result<()> {
+        Extension::validate_custom_attribute(attribute, self.extensions)?;
+        self.custom_attributes.push(attribute.to_owned());
+        Ok(())
+    }
+
     fn validate_prototype(prototype: &[Record]) -> Result<()> {
         // Helpers to check and look up records
         let contains = |n: RecordName| prototype.iter().any(|p| p.name == n);
@@ -263,6 +508,12 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
         validate_color(prototype)?;
         validate_return(prototype)?;
 
+        // Float bounds check
+        for record in prototype {
+            let context = format!("of record {:?}", record.name);
+            record.data_type.check_float_bounds(&context)?;
+        }
+
         // Row & column check
         if let Some(record) = get(RecordName::RowIndex) {
             match record.data_type {
@@ -303,13 +554,34 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
     }
 
     fn write_buffer_to_disk(&mut self, last_write: bool) -> Result<()> {
-        let packet_points = self.max_points_per_packet.min(self.buffer.len());
-        if packet_points == 0 {
+        let mut packet_points = self.max_points_per_packet.min(self.buffer.len());
+        if packet_points == 0 && !(last_write && self.has_pending_bytes()) {
             return Ok(());
         }
 
-        let prototype_len = self.prototype.len();
-        let mut buffers = vec![ByteStreamWriteBuffer::new(); prototype_len];
+        // Packets before a restart packet must not leave any bits behind.
+        // Padding bits would be indistinguishable from values with less than eight bits,
+        // so the packet is shortened to end with a multiple of eight points since the last restart.
+        let restart = self.restart_pending;
+        if restart {
+            self.packets_since_restart = 0;
+            self.points_since_restart = 0;
+        }
+        let interval = self.restart_interval;
+        let mut flush = last_write;
+        if interval > 0 && !last_write && self.packets_since_restart + 1 >= interval {
+            let total = self.points_since_restart + packet_points as u64;
+            let excess = (total % 8) as usize;
+            if excess < packet_points {
+                packet_points -= excess;
+                flush = true;
+            }
+        }
+        self.restart_pending = flush && !last_write;
+
+        // The byte streams are kept between packets, since incomplete bytes
+        // are not written and values might continue in the next packet.
+        let buffers = &mut self.byte_streams;
         for _ in 0..packet_points {
             let p = self
                 .buffer
@@ -323,88 +595,71 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
             }
         }
 
-        // Check and prepare buffer sizes
-        let mut sum_buffer_sizes = 0;
-        let mut buffer_sizes = Vec::with_capacity(prototype_len);
-        for buffer in &buffers {
-            let len = if last_write {
-                buffer.all_bytes()
-            } else {
-                buffer.full_bytes()
-            };
-            sum_buffer_sizes += len;
-            buffer_sizes.push(len as u16);
-        }
+        // Write data packet and add its length to section length for later
+        let packet_length = write_data_packet(self.writer, buffers, flush, restart)?;
+        self.section_header.section_length += packet_length;
+        self.packets_since_restart += 1;
+        self.points_since_restart += packet_points as u64;
 
-        // Calculate packet length for header
-        let mut packet_length = DataPacketHeader::SIZE + prototype_len * 2 + sum_buffer_sizes;
-        if packet_length % 4 != 0 {
-            let missing = 4 - (packet_length % 4);
-            packet_length += missing;
-        }
-        if packet_length > u16::MAX as usize {
-            Error::internal("Invalid data packet length")?
-        }
-
-        // Add data packet length to section length for later
-        self.section_header.section_length += packet_length as u64;
-
-        // Write data packet header
-        DataPacketHeader {
-            comp_restart_flag: false,
-            packet_length: packet_length as u64,
-            bytestream_count: prototype_len as u16,
-        }
-        .write(&mut self.writer)?;
+        Ok(())
+    }
 
-        // Write bytestream sizes as u16 values
-        for size in buffer_sizes {
-            let bytes = size.to_le_bytes();
-            self.writer
-                .write_all(&bytes)
-                .write_err("Cannot write data packet buffer size")?;
+    /// Adds a new point to the point cloud.
+    pub fn add_point(&mut self, mut values: RawValues) -> Result<()> {
+        if values.len() != self.prototype.len() {
+            Error::invalid("Number of values does not match prototype length")?
         }
 
-        // Write actual bytestream buffers with data
-        for buffer in &mut buffers {
-            let data = if last_write {
-                buffer.get_all_bytes()
-            } else {
-                buffer.get_full_bytes()
-            };
-            self.writer
-                .write_all(&data)
-                .write_err("Cannot write bytestream buffer into data packet")?;
+        // Floating point lengths need to be scaled before encoding
+        for i in &self.scaled_values {
+            match &mut values[*i] {
+                RecordValue::Single(v) => *v = (*v as f64 * self.unit_scale) as f32,
+                RecordValue::Double(v) => *v *= self.unit_scale,
+                _ => {}
+            }
         }
 
-        self.writer
-            .align()
-            .write_err("Failed to align writer on next 4-byte offset after writing data packet")?;
+        self.push_values(values)
+    }
 
-        Ok(())
+    /// Adds a new point to the point cloud from a simple point, as returned by the simple reader.
+    ///
+    /// The point attributes are converted into the types of the prototype records.
+    /// Colors and intensities between 0 and 1 are mapped to the limits of their record types.
+    /// Invalid coordinates, colors or intensities require the corresponding invalid state record.
+    /// Only coordinates, invalid states, colors, intensities, row and column indices are supported.
+    pub fn add_simple_point(&mut self, point: &Point) -> Result<()> {
+        let values = simple_point_to_values(&self.prototype, point, self.unit_scale)?;
+        self.push_values(values)
     }
 
-    /// Adds a new point to the point cloud.
-    pub fn add_point(&mut self, values: RawValues) -> Result<()> {
-        if values.len() != self.prototype.len() {
-            Error::invalid("Number of values does not match prototype length")?
+    fn push_values(&mut self, mut values: RawValues) -> Result<()> {
+        self.check_not_done()?;
+
+        if let Some(baked) = &self.baked_transform {
+            baked.apply(&self.prototype, &mut values)?;
         }
 
         for (i, p) in self.prototype.iter().enumerate() {
-            let value = &values[i];
-            if !match p.data_type {
-                RecordDataType::Single { .. } => matches!(value, RecordValue::Single(..)),
-                RecordDataType::Double { .. } => matches!(value, RecordValue::Double(..)),
-                RecordDataType::ScaledInteger { .. } => {
-                    matches!(value, RecordValue::ScaledInteger(..))
-                }
-                RecordDataType::Integer { .. } => matches!(value, RecordValue::Integer(..)),
-            } {
-                Error::invalid(format!(
-                    "Type mismatch at index {i}: value type does not match prototype"
-                ))?
+            validate_value(i, p, &values[i], self.strict_float_bounds)?;
+        }
+
+        if let Some(duplicates) = &mut self.duplicates {
+            if duplicates.is_duplicate(&self.prototype, &values)? {
+                return Ok(());
             }
+        }
+
+        self.point_count += 1;
+        if let Some((_, sorter)) = &mut self.morton {
+            return sorter.push(&self.prototype, values);
+        }
+        self.write_values(values)
+    }
 
+    /// Updates the bounds and queues the values for writing.
+    fn write_values(&mut self, values: RawValues) -> Result<()> {
+        for (i, p) in self.prototype.iter().enumerate() {
             if p.name == RecordName::CartesianX
                 || p.name == RecordName::CartesianY
                 || p.name == RecordName::CartesianZ
@@ -474,7 +729,6 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
         }
 
         self.buffer.push_back(values);
-        self.point_count += 1;
         if self.buffer.len() >= self.max_points_per_packet {
             self.write_buffer_to_disk(false)?;
         }
@@ -482,12 +736,34 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
     }
 
     /// Called after all points have been added to finalize the creation of the new point cloud.
+    ///
+    /// If duplicate removal is enabled, the number of dropped points is logged when
+    /// the `tracing` feature is enabled and stays available with [`PointCloudWriter::dropped_duplicates()`].
     pub fn finalize(&mut self) -> Result<()> {
-        // Flush remaining points from buffer
-        while !self.buffer.is_empty() {
+        self.check_not_done()?;
+
+        // Write all buffered points in Morton order
+        if let Some((order, sorter)) = self.morton.take() {
+            for values in sorter.into_sorted()? {
+                self.write_values(values?)?;
+            }
+            self.custom_xml.push(order.xml_string());
+        }
+
+        // Flush remaining points and incomplete bytes from buffers
+        while !self.buffer.is_empty() || self.has_pending_bytes() {
             self.write_buffer_to_disk(true)?;
         }
 
+        // Empty point clouds have no data packets and no meaningful bounds.
+        // Like the reference implementation we use a data offset of zero in this case.
+        if self.point_count == 0 {
+            self.section_header.data_offset = 0;
+            self.cartesian_bounds = None;
+            self.spherical_bounds = None;
+            self.index_bounds = None;
+        }
+
         // We need to write the section header again with the final length
         // which was previously unknown and is now available.
         let end_offset = self
@@ -502,6 +778,30 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
             .physical_seek(end_offset)
             .write_err("Failed to seek behind finalized section")?;
 
+        // Write optional line groups as separate binary section
+        let line_grouping = if let Some((id_element_name, mut groups)) = self.line_groups.take() {
+            let end = groups
+                .iter()
+                .map(|g| g.start_point_index as u64 + g.point_count as u64)
+                .max()
+                .unwrap_or(0);
+            if end > self.point_count {
+                Error::invalid("Line groups reference points that are not part of the point cloud")?
+            }
+            if self.unit_scale != 1.0 {
+                for g in &mut groups {
+                    g.scale(self.unit_scale);
+                }
+            }
+            Some(LineGrouping::write_groups(
+                self.writer,
+                &id_element_name,
+                &groups,
+            )?)
+        } else {
+            None
+        };
+
         // prepare point cloud metadata
         let pc = PointCloud {
             guid: Some(self.guid.clone()),
@@ -516,7 +816,10 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
             intensity_limits: self.intensity_limits.take(),
             name: self.name.take(),
             description: self.description.take(),
-            transform: self.transform.take(),
+            transform: self.transform.take().map(|mut t| {
+                t.translation.scale(self.unit_scale);
+                t
+            }),
             acquisition_start: self.acquisition_start.take(),
             acquisition_end: self.acquisition_end.take(),
             sensor_vendor: self.sensor_vendor.take(),
@@ -528,13 +831,471 @@ impl<'a, T: Read + Write + Seek> PointCloudWriter<'a, T> {
             temperature: self.temperature.take(),
             humidity: self.humidity.take(),
             atmospheric_pressure: self.atmospheric_pressure.take(),
+            line_grouping,
+            custom_xml: std::mem::take(&mut self.custom_xml),
+            custom_attributes: std::mem::take(&mut self.custom_attributes),
         };
 
+        #[cfg(feature = "tracing")]
+        if let Some(duplicates) = &self.duplicates {
+            tracing::info!(
+                guid = self.guid,
+                dropped = duplicates.dropped(),
+                kept = self.point_count,
+                "Removed duplicate points"
+            );
+        }
+
         // Add metadata for XML generation later, when the file is completed.
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            offset = self.section_offset,
+            records = self.point_count,
+            dropped_duplicates = self.dropped_duplicates(),
+            section_length = self.section_header.section_length,
+            "Finalized point cloud section"
+        );
         self.pointclouds.push(pc);
+        *self.open_section = None;
+        self.done = true;
 
         Ok(())
     }
+
+    /// Writes all buffered points as data packets and flushes them to the underlying writer.
+    ///
+    /// Points are usually buffered until there are enough points to fill a complete data packet.
+    /// Calling this method regularly allows to bound the latency and memory usage when writing
+    /// live data, at the cost of smaller data packets and more overhead in the file.
+    /// The file remains incomplete until this point cloud and the E57 writer are finalized.
+    /// Points that are buffered for sorting in Morton order are not affected.
+    pub fn flush(&mut self) -> Result<()> {
+        self.check_not_done()?;
+        while !self.buffer.is_empty() {
+            self.write_buffer_to_disk(false)?;
+        }
+        self.writer
+            .flush()
+            .write_err("Failed to flush data packets to the writer")
+    }
+
+    /// Returns the number of added points that were not yet written into a data packet.
+    /// Points that are buffered for sorting in Morton order are not included.
+    pub fn buffered_points(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the approximate number of bytes used by points and encoded values
+    /// that were not yet written into a data packet.
+    /// Points that are buffered for sorting in Morton order are not included.
+    pub fn buffered_bytes(&self) -> usize {
+        let point_size = self.prototype.len() * std::mem::size_of::<RecordValue>();
+        let streams: usize = self.byte_streams.iter().map(|b| b.all_bytes()).sum();
+        self.buffer.len() * point_size + streams
+    }
+
+    /// Discards the point cloud instead of finalizing it.
+    ///
+    /// The file is rolled back to the start of the point cloud section,
+    /// so that any following sections will overwrite the already written point data.
+    /// The point cloud will not be part of the XML section and the E57 file remains valid.
+    /// The discarded data is not removed from the file, because the underlying writer cannot be truncated.
+    /// If no following section overwrites it completely, the remaining bytes stay in the file
+    /// behind the XML section and increase the file size, but are never referenced.
+    pub fn abort(&mut self) -> Result<()> {
+        self.check_not_done()?;
+        self.done = true;
+        self.buffer.clear();
+        self.morton = None;
+        self.byte_streams.fill(ByteStreamWriteBuffer::new());
+        *self.open_section = None;
+        rollback_section(self.writer, self.section_offset)
+    }
+
+    fn has_pending_bytes(&self) -> bool {
+        self.byte_streams.iter().any(|b| b.all_bytes() > 0)
+    }
+
+    fn check_not_done(&self) -> Result<()> {
+        if self.done {
+            Error::invalid("Point cloud writer was already finalized or aborted")?
+        }
+        Ok(())
+    }
+}
+
+/// Rolls the writer back to the start of an unfinished section,
+/// so that following sections will overwrite its data.
+pub(crate) fn rollback_section<T: Read + Write + Seek>(
+    writer: &mut PagedWriter<T>,
+    section_offset: u64,
+) -> Result<()> {
+    writer
+        .physical_seek(section_offset)
+        .write_err("Failed to seek back to the start of the unfinished section")
+}
+
+/// Writes a new compressed vector section with unchanged copies of all data packets of another section.
+/// Index and ignored packets are skipped, since they are optional and index packets would contain wrong offsets.
+/// Returns the physical offset of the new section.
+#[cfg(feature = "read")]
+pub(crate) fn copy_section<R: Read + Seek, W: Read + Write + Seek>(
+    packets: PacketReader<'_, R>,
+    writer: &mut PagedWriter<W>,
+) -> Result<u64> {
+    let section_offset = writer.physical_position()?;
+    let mut section_header = CompressedVectorSectionHeader::default();
+    section_header.section_length = CompressedVectorSectionHeader::SIZE;
+    section_header.write(writer)?;
+
+    for packet in packets {
+        let (packet_length, comp_restart_flag, bytestreams) = match packet? {
+            RawPacket::Data {
+                packet_length,
+                comp_restart_flag,
+                bytestreams,
+                ..
+            } => (packet_length, comp_restart_flag, bytestreams),
+            _ => continue,
+        };
+        if section_header.data_offset == 0 {
+            section_header.data_offset = writer.physical_position()?;
+        }
+
+        DataPacketHeader {
+            comp_restart_flag,
+            packet_length,
+            bytestream_count: bytestreams.len() as u16,
+        }
+        .write(writer)?;
+        for bs in &bytestreams {
+            writer
+                .write_all(&(bs.len() as u16).to_le_bytes())
+                .write_err("Cannot write data packet buffer size")?;
+        }
+        for bs in &bytestreams {
+            writer
+                .write_all(bs)
+                .write_err("Cannot write bytestream buffer into data packet")?;
+        }
+
+        // Padding is not copied, but replaced with zeros
+        let content = DataPacketHeader::SIZE
+            + bytestreams.len() * 2
+            + bytestreams.iter().map(|bs| bs.len()).sum::<usize>();
+        let padding = (packet_length as usize)
+            .checked_sub(content)
+            .invalid_err("Data packet length is smaller than its content")?;
+        writer
+            .write_all(&vec![0_u8; padding])
+            .write_err("Cannot write data packet padding")?;
+        section_header.section_length += packet_length;
+    }
+
+    // Update section header with the final length
+    let end_offset = writer.physical_position()?;
+    writer.physical_seek(section_offset)?;
+    section_header.write(writer)?;
+    writer.physical_seek(end_offset)?;
+
+    Ok(section_offset)
+}
+
+/// Writes a single data packet with the content of the supplied byte stream buffers.
+/// Only full bytes are written, unless this is the last packet of the section.
+/// Returns the length of the written packet in bytes.
+pub(crate) fn write_data_packet<T: Read + Write + Seek>(
+    writer: &mut PagedWriter<T>,
+    buffers: &mut [ByteStreamWriteBuffer],
+    last_write: bool,
+    comp_restart: bool,
+) -> Result<u64> {
+    // Check and prepare buffer sizes
+    let mut sum_buffer_sizes = 0;
+    let mut buffer_sizes = Vec::with_capacity(buffers.len());
+    for buffer in buffers.iter() {
+        let len = if last_write {
+            buffer.all_bytes()
+        } else {
+            buffer.full_bytes()
+        };
+        sum_buffer_sizes += len;
+        buffer_sizes.push(len as u16);
+    }
+
+    // Calculate packet length for header
+    let mut packet_length = DataPacketHeader::SIZE + buffers.len() * 2 + sum_buffer_sizes;
+    if !packet_length.is_multiple_of(4) {
+        let missing = 4 - (packet_length % 4);
+        packet_length += missing;
+    }
+    if packet_length > u16::MAX as usize {
+        Error::internal("Invalid data packet length")?
+    }
+
+    // Write data packet header
+    DataPacketHeader {
+        comp_restart_flag: comp_restart,
+        packet_length: packet_length as u64,
+        bytestream_count: buffers.len() as u16,
+    }
+    .write(writer)?;
+
+    // Write bytestream sizes as u16 values
+    for size in buffer_sizes {
+        let bytes = size.to_le_bytes();
+        writer
+            .write_all(&bytes)
+            .write_err("Cannot write data packet buffer size")?;
+    }
+
+    // Write actual bytestream buffers with data
+    for buffer in buffers.iter_mut() {
+        let data = if last_write {
+            buffer.get_all_bytes()
+        } else {
+            buffer.get_full_bytes()
+        };
+        writer
+            .write_all(&data)
+            .write_err("Cannot write bytestream buffer into data packet")?;
+    }
+
+    writer
+        .align()
+        .write_err("Failed to align writer on next 4-byte offset after writing data packet")?;
+
+    Ok(packet_length as u64)
+}
+
+/// Converts a simple point into raw values matching the prototype.
+/// The unit scale is applied to all lengths, the prototype is expected to be already adjusted.
+fn simple_point_to_values(prototype: &[Record], p: &Point, unit_scale: f64) -> Result<RawValues> {
+    let (cartesian, cartesian_state) = match p.cartesian {
+        CartesianCoordinate::Valid { x, y, z } => (Some([x, y, z].map(|v| v * unit_scale)), 0),
+        CartesianCoordinate::Direction { x, y, z } => (Some([x, y, z]), 1),
+        CartesianCoordinate::Invalid => (None, 2),
+    };
+    let (spherical, spherical_state) = match p.spherical {
+        SphericalCoordinate::Valid {
+            range,
+            azimuth,
+            elevation,
+        } => (Some([range * unit_scale, azimuth, elevation]), 0),
+        SphericalCoordinate::Direction { azimuth, elevation } => {
+            (Some([0.0, azimuth, elevation]), 1)
+        }
+        SphericalCoordinate::Invalid => (None, 2),
+    };
+    let color = p.color.as_ref().map(|c| [c.red, c.green, c.blue]);
+
+    // Checks that the invalid state record exists if a state other than zero is required
+    let state = |name: RecordName, state: i64| -> Result<()> {
+        if state != 0 && !contains(prototype, name.clone()) {
+            Error::invalid(format!(
+                "Point requires the record {name:?} to store its invalid state"
+            ))?
+        }
+        Ok(())
+    };
+    if contains(prototype, RecordName::CartesianX) {
+        state(RecordName::CartesianInvalidState, cartesian_state)?;
+    }
+    if contains(prototype, RecordName::SphericalAzimuth) {
+        state(RecordName::SphericalInvalidState, spherical_state)?;
+    }
+    if contains(prototype, RecordName::ColorRed) {
+        state(RecordName::IsColorInvalid, color.is_none() as i64)?;
+    }
+    if contains(prototype, RecordName::Intensity) {
+        state(RecordName::IsIntensityInvalid, p.intensity.is_none() as i64)?;
+    }
+
+    // Values for invalid attributes are irrelevant, but still need to fit into the type
+    let placeholder = |dt: &RecordDataType| -> Result<RecordValue> {
+        match (dt.value_from_f64(0.0), dt.limits()) {
+            (Ok(zero), _) => Ok(zero),
+            (Err(_), (Some(min), _)) => Ok(min),
+            (Err(err), _) => Err(err),
+        }
+    };
+    let coordinate = |values: Option<[f64; 3]>, index: usize, dt: &RecordDataType| match values {
+        Some(v) => dt.value_from_f64(v[index]),
+        None => placeholder(dt),
+    };
+
+    let mut values = Vec::with_capacity(prototype.len());
+    for record in prototype {
+        let dt = &record.data_type;
+        let value = match record.name {
+            RecordName::CartesianX => coordinate(cartesian, 0, dt)?,
+            RecordName::CartesianY => coordinate(cartesian, 1, dt)?,
+            RecordName::CartesianZ => coordinate(cartesian, 2, dt)?,
+            RecordName::CartesianInvalidState => dt.value_from_f64(cartesian_state as f64)?,
+            RecordName::SphericalRange => coordinate(spherical, 0, dt)?,
+            RecordName::SphericalAzimuth => coordinate(spherical, 1, dt)?,
+            RecordName::SphericalElevation => coordinate(spherical, 2, dt)?,
+            RecordName::SphericalInvalidState => dt.value_from_f64(spherical_state as f64)?,
+            RecordName::ColorRed => match color {
+                Some(c) => dt.value_from_unit_f32(c[0])?,
+                None => placeholder(dt)?,
+            },
+            RecordName::ColorGreen => match color {
+                Some(c) => dt.value_from_unit_f32(c[1])?,
+                None => placeholder(dt)?,
+            },
+            RecordName::ColorBlue => match color {
+                Some(c) => dt.value_from_unit_f32(c[2])?,
+                None => placeholder(dt)?,
+            },
+            RecordName::IsColorInvalid => dt.value_from_f64(color.is_none() as u8 as f64)?,
+            RecordName::Intensity => match p.intensity {
+                Some(i) => dt.value_from_unit_f32(i)?,
+                None => placeholder(dt)?,
+            },
+            RecordName::IsIntensityInvalid => {
+                dt.value_from_f64(p.intensity.is_none() as u8 as f64)?
+            }
+            RecordName::RowIndex => dt.value_from_f64(p.row as f64)?,
+            RecordName::ColumnIndex => dt.value_from_f64(p.column as f64)?,
+            _ => Error::not_implemented(format!(
+                "Record {:?} cannot be filled from simple points",
+                record.name
+            ))?,
+        };
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// Adjusts all length records of the prototype to the unit scale.
+/// Returns the indices of floating point records, which need their values to be scaled.
+fn scale_prototype(prototype: &mut [Record], factor: f64) -> Result<Vec<usize>> {
+    let mut scaled_values = Vec::new();
+    if factor == 1.0 {
+        return Ok(scaled_values);
+    }
+    for (i, record) in prototype.iter_mut().enumerate() {
+        let is_length = matches!(
+            record.name,
+            RecordName::CartesianX
+                | RecordName::CartesianY
+                | RecordName::CartesianZ
+                | RecordName::SphericalRange
+        );
+        if !is_length {
+            continue;
+        }
+        match &mut record.data_type {
+            RecordDataType::Single { min, max } => {
+                for v in [min, max].into_iter().flatten() {
+                    *v = (*v as f64 * factor) as f32;
+                }
+                scaled_values.push(i);
+            }
+            RecordDataType::Double { min, max } => {
+                for v in [min, max].into_iter().flatten() {
+                    *v *= factor;
+                }
+                scaled_values.push(i);
+            }
+            RecordDataType::ScaledInteger { scale, offset, .. } => {
+                *scale *= factor;
+                *offset *= factor;
+            }
+            RecordDataType::Integer { .. } => Error::invalid(format!(
+                "Cannot apply unit scale to integer record {:?}, use a scaled integer instead",
+                record.name
+            ))?,
+        }
+    }
+    Ok(scaled_values)
+}
+
+/// Transformation matrix with the record indices of the affected Cartesian coordinates.
+struct BakedTransform {
+    matrix: [[f64; 4]; 4],
+    xyz: [usize; 3],
+    state: Option<usize>,
+}
+
+impl BakedTransform {
+    fn apply(&self, prototype: &[Record], values: &mut RawValues) -> Result<()> {
+        for i in self.xyz.into_iter().chain(self.state) {
+            validate_value(i, &prototype[i], &values[i], false)?;
+        }
+        let state = match self.state {
+            Some(i) => values[i].to_i64(&prototype[i].data_type)?,
+            None => 0,
+        };
+        if state != 0 && state != 1 {
+            return Ok(());
+        }
+        let mut v = [0.0; 3];
+        for (v, i) in v.iter_mut().zip(self.xyz) {
+            *v = values[i].to_f64(&prototype[i].data_type)?;
+        }
+        for (row, i) in self.matrix.iter().zip(self.xyz) {
+            // Directions are not translated
+            let translation = if state == 0 { row[3] } else { 0.0 };
+            let transformed = row[0] * v[0] + row[1] * v[1] + row[2] * v[2] + translation;
+            values[i] = prototype[i].data_type.value_from_f64(transformed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks if the value matches the type and range of the record at the given index.
+fn validate_value(
+    i: usize,
+    p: &Record,
+    value: &RecordValue,
+    strict_float_bounds: bool,
+) -> Result<()> {
+    if !match p.data_type {
+        RecordDataType::Single { .. } => matches!(value, RecordValue::Single(..)),
+        RecordDataType::Double { .. } => matches!(value, RecordValue::Double(..)),
+        RecordDataType::ScaledInteger { .. } => {
+            matches!(value, RecordValue::ScaledInteger(..))
+        }
+        RecordDataType::Integer { .. } => matches!(value, RecordValue::Integer(..)),
+    } {
+        Error::invalid(format!(
+            "Type mismatch at index {i}: value type does not match prototype"
+        ))?
+    }
+    if let (
+        RecordDataType::Integer { min, max } | RecordDataType::ScaledInteger { min, max, .. },
+        RecordValue::Integer(v) | RecordValue::ScaledInteger(v),
+    ) = (&p.data_type, value)
+    {
+        check_integer_range(
+            *v,
+            *min,
+            *max,
+            &format_args!("at index {i} of the prototype"),
+        )?;
+    }
+    if strict_float_bounds {
+        let (v, min, max) = match (&p.data_type, value) {
+            (RecordDataType::Single { min, max }, RecordValue::Single(v)) => {
+                (*v as f64, min.map(f64::from), max.map(f64::from))
+            }
+            (RecordDataType::Double { min, max }, RecordValue::Double(v)) => (*v, *min, *max),
+            _ => (0.0, None, None),
+        };
+        let below = min.is_some_and(|min| v.is_nan() || v < min);
+        let above = max.is_some_and(|max| v.is_nan() || v > max);
+        if below || above {
+            Error::invalid(format!(
+                "Value {v} at index {i} is outside of the range {} to {} of the prototype",
+                min.unwrap_or(f64::NEG_INFINITY),
+                max.unwrap_or(f64::INFINITY)
+            ))?
+        }
+    }
+
+    Ok(())
 }
 
 fn update_min<T: PartialOrd>(value: T, min: &mut Option<T>) {
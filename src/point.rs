@@ -1,26 +1,28 @@
 /// Structure for Cartesian coordinates with an X, Y and Z value.
+///
+/// Coordinates are double precision by default.
+/// A single precision variant can be requested from the simple point cloud reader.
 #[derive(Clone, Debug, PartialEq)]
-pub enum CartesianCoordinate {
+pub enum CartesianCoordinate<F = f64> {
     /// The Cartesian coordinate is fully valid.
-    Valid { x: f64, y: f64, z: f64 },
+    Valid { x: F, y: F, z: F },
     /// The Cartesian coordinate only contains a direction vector.
     /// Be careful, the vector might not be normalized!
-    Direction { x: f64, y: f64, z: f64 },
+    Direction { x: F, y: F, z: F },
     /// The Cartesian coordinate is fully invalid and has no meaning or the point cloud had no cartesian coordinates in general.
     Invalid,
 }
 
 /// Spherical coordinates with range, azimuth and elevation.
+///
+/// Coordinates are double precision by default.
+/// A single precision variant can be requested from the simple point cloud reader.
 #[derive(Clone, Debug, PartialEq)]
-pub enum SphericalCoordinate {
+pub enum SphericalCoordinate<F = f64> {
     /// The spherical coordinate is fully valid.
-    Valid {
-        range: f64,
-        azimuth: f64,
-        elevation: f64,
-    },
+    Valid { range: F, azimuth: F, elevation: F },
     /// The spherical coordinate only defines direction and has no valid range.
-    Direction { azimuth: f64, elevation: f64 },
+    Direction { azimuth: F, elevation: F },
     /// The spherical coordinate is fully invalid and has no meaning or the point cloud had no spherical coordinates in general.
     Invalid,
 }
@@ -34,15 +36,19 @@ pub struct Color {
 }
 
 /// Represents a high level point with its different attributes.
+///
+/// The coordinates are double precision by default.
+/// Use `Point<f32>` for single precision coordinates, see
+/// [`PointCloudReaderSimple::single_precision()`](crate::PointCloudReaderSimple::single_precision).
 #[derive(Clone, Debug)]
-pub struct Point {
+pub struct Point<F = f64> {
     /// Cartesian coordinates.
     /// Might be always invalid if the point cloud does only contain spherical coordinates and the automatic conversion from spherical to Cartesian is disabled.
-    pub cartesian: CartesianCoordinate,
+    pub cartesian: CartesianCoordinate<F>,
 
     /// Spherical coordinates.
     /// Might be always invalid if the point cloud does only contain Cartesian coordinates.
-    pub spherical: SphericalCoordinate,
+    pub spherical: SphericalCoordinate<F>,
 
     /// RGB point colors.
     /// None means the whole point cloud has no colors or the color of this individual point is invalid.
@@ -67,3 +73,242 @@ pub struct Point {
     /// have a column index or not.
     pub column: i64,
 }
+
+impl Color {
+    /// Converts the color into red, green and blue values between 0 and 255.
+    ///
+    /// Values are rounded to the nearest integer and clamped to the valid range.
+    /// Combine this with [`PointCloudReaderSimple::use_color_limits()`](crate::PointCloudReaderSimple::use_color_limits)
+    /// to get comparable colors for files with different color representations.
+    pub fn to_rgb8(&self) -> (u8, u8, u8) {
+        let convert = |v: f32| (v * 255.0).round().clamp(0.0, 255.0) as u8;
+        (convert(self.red), convert(self.green), convert(self.blue))
+    }
+}
+
+impl CartesianCoordinate {
+    /// Converts the Cartesian coordinate into a spherical coordinate.
+    ///
+    /// Valid coordinates become valid spherical coordinates and direction vectors
+    /// become spherical directions, which do not need to be normalized.
+    /// Direction vectors with zero length or non-finite values cannot be converted
+    /// and result in an invalid coordinate, just like invalid input coordinates.
+    /// The origin is converted to a zero range with azimuth and elevation of zero.
+    pub fn to_spherical(&self) -> SphericalCoordinate {
+        match *self {
+            CartesianCoordinate::Valid { x, y, z } => {
+                if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+                    return SphericalCoordinate::Invalid;
+                }
+                let range = f64::sqrt(x * x + y * y + z * z);
+                let elevation = if range > 0.0 {
+                    f64::asin(z / range)
+                } else {
+                    0.0
+                };
+                SphericalCoordinate::Valid {
+                    range,
+                    azimuth: f64::atan2(y, x),
+                    elevation,
+                }
+            }
+            CartesianCoordinate::Direction { x, y, z } => {
+                let length = f64::sqrt(x * x + y * y + z * z);
+                if !length.is_finite() || length <= 0.0 {
+                    return SphericalCoordinate::Invalid;
+                }
+                SphericalCoordinate::Direction {
+                    azimuth: f64::atan2(y, x),
+                    elevation: f64::asin(z / length),
+                }
+            }
+            CartesianCoordinate::Invalid => SphericalCoordinate::Invalid,
+        }
+    }
+}
+
+impl SphericalCoordinate {
+    /// Converts the spherical coordinate into a Cartesian coordinate.
+    ///
+    /// Valid coordinates become valid Cartesian coordinates and spherical directions
+    /// become normalized Cartesian direction vectors.
+    /// Coordinates with a negative or non-finite range violate the E57 specification
+    /// and result in an invalid coordinate, just like invalid input coordinates.
+    pub fn to_cartesian(&self) -> CartesianCoordinate {
+        match *self {
+            SphericalCoordinate::Valid {
+                range,
+                azimuth,
+                elevation,
+            } => {
+                if !range.is_finite() || range < 0.0 {
+                    return CartesianCoordinate::Invalid;
+                }
+                let (x, y, z) = unit_vector(azimuth, elevation);
+                CartesianCoordinate::Valid {
+                    x: range * x,
+                    y: range * y,
+                    z: range * z,
+                }
+            }
+            SphericalCoordinate::Direction { azimuth, elevation } => {
+                let (x, y, z) = unit_vector(azimuth, elevation);
+                CartesianCoordinate::Direction { x, y, z }
+            }
+            SphericalCoordinate::Invalid => CartesianCoordinate::Invalid,
+        }
+    }
+}
+
+fn unit_vector(azimuth: f64, elevation: f64) -> (f64, f64, f64) {
+    let cos_ele = f64::cos(elevation);
+    (
+        cos_ele * f64::cos(azimuth),
+        cos_ele * f64::sin(azimuth),
+        f64::sin(elevation),
+    )
+}
+
+impl From<CartesianCoordinate> for CartesianCoordinate<f32> {
+    /// Converts double precision to single precision Cartesian coordinates.
+    /// This is a lossy conversion, see [`Point<f32>`] for details.
+    fn from(value: CartesianCoordinate) -> Self {
+        match value {
+            CartesianCoordinate::Valid { x, y, z } => Self::Valid {
+                x: x as f32,
+                y: y as f32,
+                z: z as f32,
+            },
+            CartesianCoordinate::Direction { x, y, z } => Self::Direction {
+                x: x as f32,
+                y: y as f32,
+                z: z as f32,
+            },
+            CartesianCoordinate::Invalid => Self::Invalid,
+        }
+    }
+}
+
+impl From<SphericalCoordinate> for SphericalCoordinate<f32> {
+    /// Converts double precision to single precision spherical coordinates.
+    /// This is a lossy conversion, see [`Point<f32>`] for details.
+    fn from(value: SphericalCoordinate) -> Self {
+        match value {
+            SphericalCoordinate::Valid {
+                range,
+                azimuth,
+                elevation,
+            } => Self::Valid {
+                range: range as f32,
+                azimuth: azimuth as f32,
+                elevation: elevation as f32,
+            },
+            SphericalCoordinate::Direction { azimuth, elevation } => Self::Direction {
+                azimuth: azimuth as f32,
+                elevation: elevation as f32,
+            },
+            SphericalCoordinate::Invalid => Self::Invalid,
+        }
+    }
+}
+
+impl From<Point> for Point<f32> {
+    /// Converts a point with double precision coordinates to single precision.
+    ///
+    /// This is a lossy conversion!
+    /// Single precision floats have only 24 bits of mantissa, which equals about seven significant decimal digits.
+    /// Cartesian coordinates with an absolute value below 8192 meters still have a resolution
+    /// of one millimeter or better, but the resolution halves with every doubling of the value.
+    fn from(value: Point) -> Self {
+        Self {
+            cartesian: value.cartesian.into(),
+            spherical: value.spherical.into(),
+            color: value.color,
+            intensity: value.intensity,
+            row: value.row,
+            column: value.column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn spherical_cartesian_round_trip() {
+        let c = CartesianCoordinate::Valid {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        };
+        let s = c.to_spherical();
+        if let CartesianCoordinate::Valid { x, y, z } = s.to_cartesian() {
+            assert!((x - 1.0).abs() < 1e-12);
+            assert!((y - 2.0).abs() < 1e-12);
+            assert!((z - 3.0).abs() < 1e-12);
+        } else {
+            panic!("Expected valid Cartesian coordinate");
+        }
+    }
+
+    #[test]
+    fn special_cases() {
+        let origin = CartesianCoordinate::Valid {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        assert_eq!(
+            origin.to_spherical(),
+            SphericalCoordinate::Valid {
+                range: 0.0,
+                azimuth: 0.0,
+                elevation: 0.0
+            }
+        );
+
+        let zero_dir = CartesianCoordinate::Direction {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        assert_eq!(zero_dir.to_spherical(), SphericalCoordinate::Invalid);
+
+        let negative = SphericalCoordinate::Valid {
+            range: -1.0,
+            azimuth: 0.0,
+            elevation: 0.0,
+        };
+        assert_eq!(negative.to_cartesian(), CartesianCoordinate::Invalid);
+
+        let up = SphericalCoordinate::Direction {
+            azimuth: 0.0,
+            elevation: FRAC_PI_2,
+        };
+        if let CartesianCoordinate::Direction { x, y, z } = up.to_cartesian() {
+            assert!(x.abs() < 1e-12);
+            assert!(y.abs() < 1e-12);
+            assert!((z - 1.0).abs() < 1e-12);
+        } else {
+            panic!("Expected Cartesian direction");
+        }
+    }
+
+    #[test]
+    fn color_to_rgb8() {
+        let color = Color {
+            red: 0.0,
+            green: 254.6 / 255.0,
+            blue: 1.5,
+        };
+        assert_eq!(color.to_rgb8(), (0, 255, 255));
+        let color = Color {
+            red: -0.1,
+            green: 0.5,
+            blue: 1.0,
+        };
+        assert_eq!(color.to_rgb8(), (0, 128, 255));
+    }
+}
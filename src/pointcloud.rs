@@ -1,10 +1,20 @@
+#[cfg(feature = "read")]
 use crate::error::Converter;
+use crate::grouping::LineGrouping;
+#[cfg(any(feature = "read", feature = "write"))]
 use crate::xml;
+#[cfg(any(feature = "read", feature = "write"))]
+use crate::Result;
 use crate::{
-    CartesianBounds, ColorLimits, DateTime, IndexBounds, IntensityLimits, Record, RecordDataType,
-    RecordName, Result, SphericalBounds, Transform,
+    CartesianBounds, ColorLimits, DateTime, IndexBounds, IntensityLimits, MortonOrder, Record,
+    RecordName, ResourceEstimate, SphericalBounds, Transform,
 };
+#[cfg(feature = "read")]
+use crate::{Error, RecordDataType};
+#[cfg(feature = "read")]
 use roxmltree::{Document, Node};
+#[cfg(feature = "read")]
+use std::collections::HashMap;
 
 /// Descriptor with metadata for a single point cloud.
 ///
@@ -63,26 +73,42 @@ pub struct PointCloud {
     pub humidity: Option<f64>,
     /// Optional atmospheric pressure in Pascals, measured at the sensor at the time of capturing.
     pub atmospheric_pressure: Option<f64>,
+    /// Optional grouping of the points into lines.
+    pub line_grouping: Option<LineGrouping>,
+    /// Unchanged XML elements of extensions that are children of the point cloud element.
+    /// They are written back as they are, which allows to preserve vendor specific metadata.
+    pub custom_xml: Vec<String>,
+    /// XML attributes of extensions on the point cloud element, for example `ven:id="42"`.
+    /// They are written back as they are, like the custom XML elements.
+    pub custom_attributes: Vec<String>,
 }
 
 impl PointCloud {
-    pub(crate) fn vec_from_document(document: &Document) -> Result<Vec<Self>> {
-        let data3d_node = document
-            .descendants()
-            .find(|n| n.has_tag_name("data3D"))
-            .invalid_err("Cannot find 'data3D' tag in XML document")?;
+    #[cfg(feature = "read")]
+    pub(crate) fn vec_from_document(
+        document: &Document,
+        aliases: &HashMap<String, RecordName>,
+    ) -> Result<Vec<Self>> {
+        // Some files only contain images and omit the point cloud vector
+        let data3d_node =
+            if let Some(node) = document.descendants().find(|n| n.has_tag_name("data3D")) {
+                node
+            } else {
+                return Ok(Vec::new());
+            };
 
         let mut pointclouds = Vec::new();
         for n in data3d_node.children() {
             if n.has_tag_name("vectorChild") && n.attribute("type") == Some("Structure") {
-                let pointcloud = Self::from_node(&n)?;
+                let pointcloud = Self::from_node(&n, aliases)?;
                 pointclouds.push(pointcloud);
             }
         }
         Ok(pointclouds)
     }
 
-    pub(crate) fn from_node(node: &Node) -> Result<Self> {
+    #[cfg(feature = "read")]
+    pub(crate) fn from_node(node: &Node, aliases: &HashMap<String, RecordName>) -> Result<Self> {
         let guid = xml::opt_string(node, "guid")?;
         let name = xml::opt_string(node, "name")?;
         let description = xml::opt_string(node, "description")?;
@@ -101,8 +127,11 @@ impl PointCloud {
         let cartesian_bounds = node.children().find(|n| n.has_tag_name("cartesianBounds"));
         let spherical_bounds = node.children().find(|n| n.has_tag_name("sphericalBounds"));
         let index_bounds = node.children().find(|n| n.has_tag_name("indexBounds"));
-        let intensity_limits = node.children().find(|n| n.has_tag_name("colorLimits"));
+        let intensity_limits = node.children().find(|n| n.has_tag_name("intensityLimits"));
         let color_limits = node.children().find(|n| n.has_tag_name("colorLimits"));
+        let line_grouping = LineGrouping::from_node(node)?;
+        let custom_xml = xml::extension_elements(node);
+        let custom_attributes = xml::extension_attributes(node);
 
         // Read optional vector of original GUIDs
         let original_guids = if let Some(original_guids_node) =
@@ -144,17 +173,41 @@ impl PointCloud {
 
         // Parse point prototype records
         let mut prototype = Vec::new();
+        let mut aliased = Vec::new();
         for n in prototype_tag.children() {
             if !n.is_element() {
                 continue;
             }
             let ns = n.lookup_prefix(n.tag_name().namespace().unwrap_or_default());
             let tag = n.tag_name().name();
-            let name = RecordName::from_namespace_and_tag_name(ns, tag)?;
+            let qualified = match ns {
+                Some(ns) if !ns.is_empty() => format!("{ns}:{tag}"),
+                _ => tag.to_owned(),
+            };
+            let name = if let Some(alias) = aliases.get(&qualified) {
+                aliased.push((prototype.len(), qualified));
+                alias.clone()
+            } else {
+                RecordName::from_namespace_and_tag_name(ns, tag)?
+            };
             let data_type = RecordDataType::from_node(&n)?;
             prototype.push(Record { name, data_type });
         }
 
+        // Aliased records must not collide with any other record, also not with following ones
+        for (index, qualified) in aliased {
+            let name = &prototype[index].name;
+            let duplicate = prototype
+                .iter()
+                .enumerate()
+                .any(|(i, r)| i != index && &r.name == name);
+            if duplicate {
+                Error::invalid(format!(
+                    "Alias for prototype record '{qualified}' results in a duplicate record name"
+                ))?
+            }
+        }
+
         Ok(Self {
             guid,
             name,
@@ -200,12 +253,19 @@ impl PointCloud {
             temperature,
             humidity,
             atmospheric_pressure,
+            line_grouping,
+            custom_xml,
+            custom_attributes,
         })
     }
 
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> Result<String> {
         let mut xml = String::new();
-        xml += "<vectorChild type=\"Structure\">\n";
+        xml += &format!(
+            "<vectorChild type=\"Structure\"{}>\n",
+            xml::gen_attributes(&self.custom_attributes)
+        );
         if let Some(guid) = &self.guid {
             xml += &xml::gen_string("guid", &guid);
         }
@@ -289,15 +349,32 @@ impl PointCloud {
         }
         xml += "</prototype>\n";
         xml += "</points>\n";
+        if let Some(line_grouping) = &self.line_grouping {
+            xml += &line_grouping.xml_string();
+        }
+        xml += &xml::gen_custom(&self.custom_xml);
 
         xml += "</vectorChild>\n";
         Ok(xml)
     }
 
+    /// Returns the row-major 4x4 matrix of the point cloud pose.
+    /// This is the identity matrix if the point cloud has no transformation.
+    pub fn pose_matrix(&self) -> [[f64; 4]; 4] {
+        self.transform.clone().unwrap_or_default().to_matrix()
+    }
+
+    /// Returns estimates of the memory required for the points and the record layout.
+    /// This does not decode any points and can be used to choose between
+    /// reading all points into memory or processing them as a stream.
+    pub fn resource_estimate(&self) -> ResourceEstimate {
+        ResourceEstimate::from_pointcloud(self)
+    }
+
     fn contains(&self, names: &[RecordName]) -> bool {
-        return names
+        names
             .iter()
-            .all(|searched| self.prototype.iter().any(|actual| &actual.name == searched));
+            .all(|searched| self.prototype.iter().any(|actual| &actual.name == searched))
     }
 
     /// Returns true if the point prototype contains X, Y and Z records for Cartesian coordinates.
@@ -346,4 +423,68 @@ impl PointCloud {
     pub fn has_timestamp(&self) -> bool {
         self.contains(&[RecordName::TimeStamp])
     }
+
+    /// Returns the resolution of the Morton order if the points were written sorted by their Morton code.
+    /// See [`PointCloudWriter::set_morton_order()`](crate::PointCloudWriter::set_morton_order) for details.
+    pub fn morton_resolution(&self) -> Option<f64> {
+        self.custom_xml
+            .iter()
+            .find_map(|xml| MortonOrder::resolution_from_xml(xml))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_data3d() {
+        let xml = "<e57Root type=\"Structure\"><images2D type=\"Vector\"/></e57Root>";
+        let document = Document::parse(xml).unwrap();
+        let pointclouds = PointCloud::vec_from_document(&document, &HashMap::new()).unwrap();
+        assert!(pointclouds.is_empty());
+    }
+
+    #[test]
+    fn record_aliases() {
+        let xml = "<e57Root type=\"Structure\" xmlns=\"http://www.astm.org/COMMIT/E57/2010-e57-v1.0\" xmlns:nrm=\"http://www.libe57.org/E57_NOR_surface_normals.txt\">
+            <data3D type=\"Vector\"><vectorChild type=\"Structure\">
+            <guid type=\"String\">guid</guid>
+            <points type=\"CompressedVector\" fileOffset=\"48\" recordCount=\"0\">
+            <prototype type=\"Structure\">
+            <cartesianX type=\"Float\"/>
+            <color_red type=\"Integer\" minimum=\"0\" maximum=\"255\"/>
+            <nrm:normalX type=\"Float\"/>
+            </prototype><codecs type=\"Vector\"/></points>
+            </vectorChild></data3D></e57Root>";
+        let document = Document::parse(xml).unwrap();
+        assert!(PointCloud::vec_from_document(&document, &HashMap::new()).is_err());
+
+        let mut aliases = HashMap::new();
+        aliases.insert("color_red".to_owned(), RecordName::ColorRed);
+        let normal = RecordName::Unknown {
+            namespace: "nor".to_owned(),
+            name: "normalX".to_owned(),
+        };
+        aliases.insert("nrm:normalX".to_owned(), normal.clone());
+        let pointclouds = PointCloud::vec_from_document(&document, &aliases).unwrap();
+        let names: Vec<_> = pointclouds[0].prototype.iter().map(|r| &r.name).collect();
+        assert_eq!(
+            names,
+            [&RecordName::CartesianX, &RecordName::ColorRed, &normal]
+        );
+
+        aliases.insert("color_red".to_owned(), RecordName::CartesianX);
+        assert!(PointCloud::vec_from_document(&document, &aliases).is_err());
+
+        // Conflicts with records following the aliased record are detected as well
+        let xml = xml.replace(
+            "<nrm:normalX type=\"Float\"/>",
+            "<colorRed type=\"Integer\" minimum=\"0\" maximum=\"255\"/>",
+        );
+        let document = Document::parse(&xml).unwrap();
+        aliases.insert("color_red".to_owned(), RecordName::ColorRed);
+        assert!(PointCloud::vec_from_document(&document, &aliases).is_err());
+    }
 }
@@ -0,0 +1,74 @@
+use crate::{CartesianCoordinate, Point, Result};
+
+/// Decimated single precision copy of a point cloud for thumbnails and fast overviews.
+///
+/// The points are stored as structure of arrays, which can be uploaded directly
+/// into vertex buffers of graphics APIs. All arrays have the same length,
+/// except for the color arrays, which are empty if the point cloud has no color.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct PointCloudPreview {
+    /// X coordinates of the points.
+    pub x: Vec<f32>,
+    /// Y coordinates of the points.
+    pub y: Vec<f32>,
+    /// Z coordinates of the points.
+    pub z: Vec<f32>,
+    /// Red color values of the points.
+    pub red: Vec<u8>,
+    /// Green color values of the points.
+    pub green: Vec<u8>,
+    /// Blue color values of the points.
+    pub blue: Vec<u8>,
+}
+
+impl PointCloudPreview {
+    /// Collects all points with valid Cartesian coordinates from the iterator.
+    /// Points without color get black as color when colors are requested.
+    pub(crate) fn collect<I>(points: I, capacity: usize, color: bool) -> Result<Self>
+    where
+        I: Iterator<Item = Result<Point<f32>>>,
+    {
+        let mut preview = Self {
+            x: Vec::with_capacity(capacity),
+            y: Vec::with_capacity(capacity),
+            z: Vec::with_capacity(capacity),
+            ..Default::default()
+        };
+        if color {
+            preview.red.reserve(capacity);
+            preview.green.reserve(capacity);
+            preview.blue.reserve(capacity);
+        }
+        for p in points {
+            let p = p?;
+            if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
+                preview.x.push(x);
+                preview.y.push(y);
+                preview.z.push(z);
+                if color {
+                    let (r, g, b) = p.color.map(|c| c.to_rgb8()).unwrap_or((0, 0, 0));
+                    preview.red.push(r);
+                    preview.green.push(g);
+                    preview.blue.push(b);
+                }
+            }
+        }
+        Ok(preview)
+    }
+
+    /// Returns the number of points in the preview.
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Returns true if the preview contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    /// Returns true if the preview has color values.
+    pub fn has_color(&self) -> bool {
+        !self.red.is_empty()
+    }
+}
@@ -9,6 +9,7 @@ use crate::PointCloud;
 use crate::RawValues;
 use crate::RecordDataType;
 use crate::RecordValue;
+use crate::ResourceLimits;
 use crate::Result;
 use std::collections::VecDeque;
 use std::io::{Read, Seek};
@@ -21,17 +22,41 @@ pub struct QueueReader<'a, T: Read + Seek> {
     buffer_sizes: Vec<usize>,
     byte_streams: Vec<ByteStreamReadBuffer>,
     queues: Vec<VecDeque<RecordValue>>,
+    decode: Vec<bool>,
+    packet_index: u64,
+    limits: ResourceLimits,
 }
 
 impl<'a, T: Read + Seek> QueueReader<'a, T> {
-    pub fn new(pc: &PointCloud, reader: &'a mut PagedReader<T>) -> Result<Self> {
+    pub fn new(
+        pc: &PointCloud,
+        reader: &'a mut PagedReader<T>,
+        limits: ResourceLimits,
+    ) -> Result<Self> {
+        if pc.prototype.len() > limits.max_prototype_len {
+            Error::limit_exceeded(format!(
+                "Prototype with {} records exceeds the limit of {} records",
+                pc.prototype.len(),
+                limits.max_prototype_len
+            ))?
+        }
         reader
             .seek_physical(pc.file_offset)
             .read_err("Cannot seek to compressed vector header")?;
         let section_header = CompressedVectorSectionHeader::read(reader)?;
-        reader
-            .seek_physical(section_header.data_offset)
-            .read_err("Cannot seek to packet header")?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            offset = pc.file_offset,
+            section_length = section_header.section_length,
+            records = pc.records,
+            "Reading compressed vector section"
+        );
+        // Empty sections might have no valid data offset
+        if pc.records > 0 {
+            reader
+                .seek_physical(section_header.data_offset)
+                .read_err("Cannot seek to packet header")?;
+        }
 
         Ok(Self {
             pc: pc.clone(),
@@ -40,9 +65,24 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
             buffer_sizes: vec![0; pc.prototype.len()],
             byte_streams: vec![ByteStreamReadBuffer::new(); pc.prototype.len()],
             queues: vec![VecDeque::new(); pc.prototype.len()],
+            decode: vec![true; pc.prototype.len()],
+            packet_index: 0,
+            limits,
         })
     }
 
+    /// Defines which records are decoded, all other byte streams are skipped.
+    /// Pages that contain only skipped byte streams are not read from the file at all.
+    /// The mask must have one entry per record of the prototype.
+    /// Only use this together with `pop_value()`, since `pop_point()` requires all records.
+    pub fn set_decoded_records(&mut self, mask: Vec<bool>) -> Result<()> {
+        if mask.len() != self.decode.len() {
+            Error::internal("Record mask size does not match prototype size")?
+        }
+        self.decode = mask;
+        Ok(())
+    }
+
     /// Returns the number of complete and available points across all queues.
     pub fn available(&self) -> usize {
         if self.queues.is_empty() {
@@ -50,13 +90,17 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
         }
 
         let mut av = usize::MAX;
-        for q in &self.queues {
+        for (q, decode) in self.queues.iter().zip(&self.decode) {
             let len = q.len();
-            if len < av {
+            if *decode && len < av {
                 av = len;
             }
         }
-        av
+        if av == usize::MAX {
+            0
+        } else {
+            av
+        }
     }
 
     /// Return values for the next point by popping one value from each queue.
@@ -72,8 +116,46 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
         Ok(())
     }
 
+    /// Return the next value of a single record by popping it from its queue.
+    pub fn pop_value(&mut self, index: usize) -> Result<RecordValue> {
+        self.queues
+            .get_mut(index)
+            .internal_err("Invalid record index")?
+            .pop_front()
+            .internal_err("Failed to pop value for next point")
+    }
+
     /// Reads the next packet from the compressed vector and decodes it into the queues.
+    /// Errors contain the index and offsets of the affected packet.
     pub fn advance(&mut self) -> Result<()> {
+        let packet_index = self.packet_index;
+        if packet_index >= self.limits.max_packet_count {
+            Error::limit_exceeded(format!(
+                "Binary section exceeds the limit of {} packets",
+                self.limits.max_packet_count
+            ))?
+        }
+        let physical_offset = self.reader.physical_position();
+        let logical_offset = self.reader.logical_position();
+        self.packet_index += 1;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(packet_index, physical_offset, "Decoding packet");
+        self.advance_packet().map_err(|e| {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(packet_index, physical_offset, error = %e, "Failed to decode packet");
+            e.with_packet_index(packet_index)
+                .with_physical_offset(physical_offset)
+                .with_logical_offset(logical_offset)
+        })
+    }
+
+    /// Returns the number of packets that were read so far.
+    #[cfg(feature = "tracing")]
+    pub fn packets(&self) -> u64 {
+        self.packet_index
+    }
+
+    fn advance_packet(&mut self) -> Result<()> {
         let packet_header = PacketHeader::read(self.reader)?;
         match packet_header {
             PacketHeader::Index(_) => {
@@ -84,7 +166,7 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
             }
             PacketHeader::Data(header) => {
                 if header.bytestream_count as usize != self.byte_streams.len() {
-                    Error::invalid("Bytestream count does not match prototype size")?
+                    Error::malformed("Bytestream count does not match prototype size")?
                 }
 
                 // Read byte stream sizes
@@ -97,13 +179,25 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
                     self.buffer_sizes[i] = len;
                 }
 
+                // Leftover bits of previous packets are discarded when the compressor restarts
+                if header.comp_restart_flag {
+                    for byte_stream in &mut self.byte_streams {
+                        *byte_stream = ByteStreamReadBuffer::new();
+                    }
+                }
+
                 // Read byte streams into memory
                 for (i, bs) in self.buffer_sizes.iter().enumerate() {
+                    if !self.decode[i] {
+                        self.reader.skip(*bs as u64);
+                        continue;
+                    }
                     self.buffer.resize(*bs, 0_u8);
                     self.reader
                         .read_exact(&mut self.buffer)
                         .read_err("Failed to read data packet buffers")?;
                     self.byte_streams[i].append(&self.buffer);
+                    self.check_buffer_limit(i, 0)?;
                 }
 
                 // Find smallest number of expected items in any queue after stream unpacking.
@@ -111,10 +205,12 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
                 // is zero and we don't know how many items to "unpack" from an empty buffer.
                 let mut min_queue_size = usize::MAX;
                 for (i, bs) in self.buffer_sizes.iter().enumerate() {
+                    if !self.decode[i] {
+                        continue;
+                    }
                     let bit_size = self.pc.prototype[i].data_type.bit_size();
                     // We can only check records with a non-zero bit size
-                    if bit_size != 0 {
-                        let bs_items = (bs * 8) / bit_size;
+                    if let Some(bs_items) = (bs * 8).checked_div(bit_size) {
                         let queue_items = self.queues[i].len();
                         let items = bs_items + queue_items;
                         if items < min_queue_size {
@@ -132,10 +228,46 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
             .read_err("Failed to align reader on next 4-byte offset after reading packet")
     }
 
+    /// Checks if the buffered data of a byte stream and its queue, including
+    /// the given number of additional values, would exceed the configured limit.
+    fn check_buffer_limit(&self, index: usize, additional: usize) -> Result<()> {
+        let values = self.queues[index].len().saturating_add(additional);
+        let buffered = values
+            .saturating_mul(std::mem::size_of::<RecordValue>())
+            .saturating_add(self.byte_streams[index].available() / 8);
+        if buffered > self.limits.max_bytestream_buffer {
+            Error::limit_exceeded(format!(
+                "Byte stream {index} exceeds the buffer limit of {} bytes",
+                self.limits.max_bytestream_buffer
+            ))?
+        }
+        Ok(())
+    }
+
+    /// Fills the queue of a record with a bit size of zero with its only possible value.
+    fn fill_queue(
+        &mut self,
+        index: usize,
+        value: RecordValue,
+        min_queue_size: usize,
+    ) -> Result<()> {
+        // Without any other byte streams limiting the number of values,
+        // there is no need to generate more values than records.
+        let target = min_queue_size.min(self.pc.records.try_into().unwrap_or(usize::MAX));
+        let missing = target.saturating_sub(self.queues[index].len());
+        self.check_buffer_limit(index, missing)?;
+        self.queues[index].extend(std::iter::repeat_n(value, missing));
+        Ok(())
+    }
+
     /// Extracts raw values from byte streams into queues.
     fn parse_byte_streams(&mut self, min_queue_size: usize) -> Result<()> {
-        for (i, r) in self.pc.prototype.iter().enumerate() {
-            match r.data_type {
+        for i in 0..self.pc.prototype.len() {
+            if !self.decode[i] {
+                continue;
+            }
+            let data_type = self.pc.prototype[i].data_type.clone();
+            match data_type {
                 RecordDataType::Single { .. } => {
                     BitPack::unpack_singles(&mut self.byte_streams[i], &mut self.queues[i])?
                 }
@@ -143,14 +275,12 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
                     BitPack::unpack_doubles(&mut self.byte_streams[i], &mut self.queues[i])?
                 }
                 RecordDataType::ScaledInteger { min, max, .. } => {
-                    if r.data_type.bit_size() == 0 {
+                    if data_type.bit_size() == 0 {
                         // If the bit size of an record is zero, we don't know how many items to unpack.
                         // Thats because they are not really unpacked, but instead generated with a zero value.
                         // We use the supplied minimal size to ensure that we create enough items
                         // to fill the queue enough to not be the limiting queue between all records.
-                        while self.queues[i].len() < min_queue_size {
-                            self.queues[i].push_back(RecordValue::ScaledInteger(min));
-                        }
+                        self.fill_queue(i, RecordValue::ScaledInteger(min), min_queue_size)?;
                     } else {
                         BitPack::unpack_scaled_ints(
                             &mut self.byte_streams[i],
@@ -161,11 +291,9 @@ impl<'a, T: Read + Seek> QueueReader<'a, T> {
                     }
                 }
                 RecordDataType::Integer { min, max } => {
-                    if r.data_type.bit_size() == 0 {
+                    if data_type.bit_size() == 0 {
                         // See comment above for scaled integers!
-                        while self.queues[i].len() < min_queue_size {
-                            self.queues[i].push_back(RecordValue::Integer(min));
-                        }
+                        self.fill_queue(i, RecordValue::Integer(min), min_queue_size)?;
                     } else {
                         BitPack::unpack_ints(
                             &mut self.byte_streams[i],
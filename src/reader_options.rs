@@ -0,0 +1,105 @@
+use crate::{QuaternionHandling, RecordName};
+use std::collections::HashMap;
+
+/// Options to configure how an E57 file is opened and interpreted by the [`crate::E57Reader`].
+///
+/// Use the default options to get the same behavior as [`crate::E57Reader::new`]
+/// and then modify the fields you are interested in.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ReaderOptions {
+    /// Defines how the rotation quaternions of point cloud and image poses are validated.
+    /// By default, quaternions are used as stored in the file.
+    pub quaternions: QuaternionHandling,
+    /// Linear scale factor applied to all lengths, for example 0.3048 to convert feet to meters.
+    ///
+    /// Affects the coordinates returned by the simple point cloud reader,
+    /// the bounds and pose translations in the point cloud and image descriptors,
+    /// the bounds of line groups and the point cloud statistics.
+    /// The prototype and the raw point cloud reader still describe the values as stored in the file.
+    /// Default value is 1.0, meaning no scaling.
+    pub unit_scale: f64,
+    /// Limits for the resources used while parsing the file.
+    pub limits: ResourceLimits,
+    /// Defines if the CRC checksums of the pages are verified when reading them.
+    ///
+    /// Disabling the verification increases the decoding speed
+    /// and might be useful for trusted local files.
+    /// Corrupt data will not be detected anymore in that case, so use with care!
+    /// Default value is true.
+    pub verify_checksums: bool,
+    /// Number of pages that are fetched from the underlying reader with a single read call.
+    ///
+    /// Fetching multiple pages at once reduces the number of read calls and round trips,
+    /// which can speed up reading from network filesystems or other slow sources considerably.
+    /// The checksums of all fetched pages are verified together.
+    /// Each fetch requires a buffer of this number of pages times the page size of the file,
+    /// which is usually 1024 bytes.
+    /// Must be at least one, default value is 1.
+    pub pages_per_read: u64,
+    /// Maps non-standard record names in point cloud prototypes to the names they should have.
+    ///
+    /// Allows to read files from writers that use names like `color_red` instead of `colorRed`
+    /// or extension records with an unexpected namespace prefix through the simple point cloud reader.
+    /// The keys are the tag names as stored in the XML section, including the prefix
+    /// for records of extensions, for example `color_red` or `nrm:normalX`.
+    /// The renamed records are visible in the point cloud prototype and all readers.
+    /// Renaming a record to the name of another record in the same prototype results in an error.
+    /// By default, no records are renamed.
+    pub record_aliases: HashMap<String, RecordName>,
+    /// Defines if the file is rejected when the minimum or maximum of a floating point record
+    /// in a point cloud prototype is NaN or when the maximum is smaller than the minimum.
+    ///
+    /// Some scanner software writes such bounds, so they are accepted by default
+    /// and the affected prototypes are returned as stored in the file.
+    /// Default value is false.
+    pub strict_float_bounds: bool,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            quaternions: QuaternionHandling::default(),
+            unit_scale: 1.0,
+            limits: ResourceLimits::default(),
+            verify_checksums: true,
+            pages_per_read: 1,
+            record_aliases: HashMap::new(),
+            strict_float_bounds: false,
+        }
+    }
+}
+
+/// Limits enforced while parsing a file to protect against maliciously crafted or broken files.
+///
+/// Exceeding any limit results in an error with the kind [`crate::ErrorKind::Limit`].
+/// The default limits are generous enough for all regular E57 files.
+/// Applications handling untrusted input might want to choose stricter limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResourceLimits {
+    /// Maximum size of the XML section in bytes.
+    /// Default value is 10 MiB.
+    pub max_xml_size: usize,
+    /// Maximum number of packets read from a single binary section.
+    /// Default value is 2^32, which allows binary sections of up to 256 TiB.
+    pub max_packet_count: u64,
+    /// Maximum number of bytes buffered for a single byte stream while reading a binary section,
+    /// including already decoded values that were not yet consumed.
+    /// Default value is 128 MiB.
+    pub max_bytestream_buffer: usize,
+    /// Maximum number of records in a point cloud prototype.
+    /// Default value is 1024.
+    pub max_prototype_len: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_xml_size: 10 * 1024 * 1024,
+            max_packet_count: 1 << 32,
+            max_bytestream_buffer: 128 * 1024 * 1024,
+            max_prototype_len: 1024,
+        }
+    }
+}
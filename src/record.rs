@@ -1,9 +1,14 @@
+#[cfg(feature = "write")]
 use crate::bs_write::ByteStreamWriteBuffer;
+#[cfg(feature = "read")]
 use crate::error::Converter;
 use crate::{Error, Result};
+#[cfg(feature = "read")]
 use roxmltree::Node;
+#[cfg(feature = "read")]
 use std::error::Error as StdError;
 use std::fmt::{Debug, Display};
+#[cfg(feature = "read")]
 use std::str::FromStr;
 
 /// Describes a record inside a E57 file with name and data type.
@@ -109,6 +114,7 @@ pub enum RecordValue {
 }
 
 impl Record {
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self) -> String {
         let namespace = self
             .name
@@ -122,6 +128,7 @@ impl Record {
 }
 
 impl RecordName {
+    #[cfg(any(feature = "read", feature = "write"))]
     pub(crate) fn tag_name(&self) -> &str {
         match self {
             RecordName::CartesianX => "cartesianX",
@@ -148,6 +155,7 @@ impl RecordName {
         }
     }
 
+    #[cfg(any(feature = "read", feature = "write"))]
     pub(crate) fn namespace(&self) -> Option<&str> {
         match self {
             RecordName::Unknown { namespace, .. } => Some(namespace),
@@ -155,6 +163,7 @@ impl RecordName {
         }
     }
 
+    #[cfg(feature = "read")]
     pub(crate) fn from_namespace_and_tag_name(
         namespace: Option<&str>,
         tag_name: &str,
@@ -193,6 +202,7 @@ impl RecordName {
 }
 
 impl RecordDataType {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         let tag_name = node.tag_name().name();
         let type_name = node
@@ -206,8 +216,8 @@ impl RecordDataType {
                     let max = optional_attribute(node, "maximum", tag_name, type_name)?;
                     RecordDataType::Double { min, max }
                 } else if precision == "single" {
-                    let min = optional_attribute(node, "minimum", tag_name, type_name)?;
-                    let max = optional_attribute(node, "maximum", tag_name, type_name)?;
+                    let min = optional_attribute::<f32>(node, "minimum", tag_name, type_name)?;
+                    let max = optional_attribute::<f32>(node, "maximum", tag_name, type_name)?;
                     RecordDataType::Single { min, max }
                 } else {
                     Error::invalid(format!(
@@ -262,6 +272,21 @@ impl RecordDataType {
         }
     }
 
+    /// Makes sure that the optional bounds of floating point types are not NaN
+    /// and that the maximum is not smaller than the minimum.
+    /// Other types are not checked.
+    #[cfg(any(feature = "read", feature = "write"))]
+    pub(crate) fn check_float_bounds(&self, context: &str) -> Result<()> {
+        match self {
+            RecordDataType::Single { min, max } => {
+                check_float_bounds(min.map(f64::from), max.map(f64::from), context)
+            }
+            RecordDataType::Double { min, max } => check_float_bounds(*min, *max, context),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "write")]
     pub(crate) fn write(
         &self,
         value: &RecordValue,
@@ -286,14 +311,14 @@ impl RecordDataType {
             }
             RecordDataType::ScaledInteger { min, max, .. } => {
                 if let RecordValue::ScaledInteger(int) = value {
-                    serialize_integer(*int, *min, *max, buffer);
+                    serialize_integer(*int, *min, *max, buffer)?;
                 } else {
                     Error::invalid("Data type scaled integer only supports scaled integer values")?
                 }
             }
             RecordDataType::Integer { min, max } => {
                 if let RecordValue::Integer(int) = value {
-                    serialize_integer(*int, *min, *max, buffer);
+                    serialize_integer(*int, *min, *max, buffer)?;
                 } else {
                     Error::invalid("Data type integer only supports integer values")?
                 }
@@ -302,6 +327,70 @@ impl RecordDataType {
         Ok(())
     }
 
+    /// Creates a value of this type from a floating point number.
+    /// Integer types are rounded and must contain the value in their range.
+    #[cfg(feature = "write")]
+    pub(crate) fn value_from_f64(&self, value: f64) -> Result<RecordValue> {
+        match self {
+            RecordDataType::Single { .. } => Ok(RecordValue::Single(value as f32)),
+            RecordDataType::Double { .. } => Ok(RecordValue::Double(value)),
+            RecordDataType::ScaledInteger {
+                min,
+                max,
+                scale,
+                offset,
+            } => {
+                let int = ((value - offset) / scale).round();
+                if !(int >= *min as f64 && int <= *max as f64) {
+                    Error::invalid(format!(
+                        "Value {value} is outside of the range of the scaled integer type"
+                    ))?
+                }
+                Ok(RecordValue::ScaledInteger(int as i64))
+            }
+            RecordDataType::Integer { min, max } => {
+                let int = value.round();
+                if !(int >= *min as f64 && int <= *max as f64) {
+                    Error::invalid(format!(
+                        "Value {value} is outside of the range of the integer type"
+                    ))?
+                }
+                Ok(RecordValue::Integer(int as i64))
+            }
+        }
+    }
+
+    /// Creates a value of this type from a number between 0 and 1 mapped to the type limits.
+    /// This is the inverse operation of [`RecordValue::to_unit_f32()`].
+    #[cfg(feature = "write")]
+    pub(crate) fn value_from_unit_f32(&self, value: f32) -> Result<RecordValue> {
+        let value = value.clamp(0.0, 1.0) as f64;
+        match self {
+            RecordDataType::Single {
+                min: Some(min),
+                max: Some(max),
+            } => Ok(RecordValue::Single(min + (max - min) * value as f32)),
+            RecordDataType::Double {
+                min: Some(min),
+                max: Some(max),
+            } => Ok(RecordValue::Double(min + (max - min) * value)),
+            RecordDataType::ScaledInteger { min, max, .. } => {
+                let range = (*max as i128 - *min as i128) as f64;
+                Ok(RecordValue::ScaledInteger(
+                    (*min as i128 + (range * value).round() as i128) as i64,
+                ))
+            }
+            RecordDataType::Integer { min, max } => {
+                let range = (*max as i128 - *min as i128) as f64;
+                Ok(RecordValue::Integer(
+                    (*min as i128 + (range * value).round() as i128) as i64,
+                ))
+            }
+            _ => Error::invalid("Floating point types need a minimum and maximum for unit values"),
+        }
+    }
+
+    #[cfg(feature = "write")]
     pub(crate) fn limits(&self) -> (Option<RecordValue>, Option<RecordValue>) {
         match self {
             RecordDataType::Single { min, max } => {
@@ -416,11 +505,37 @@ impl Display for RecordValue {
 }
 
 #[inline]
-fn serialize_integer(value: i64, min: i64, max: i64, buffer: &mut ByteStreamWriteBuffer) {
-    let uint = (value - min) as u64;
+#[cfg(feature = "write")]
+fn serialize_integer(
+    value: i64,
+    min: i64,
+    max: i64,
+    buffer: &mut ByteStreamWriteBuffer,
+) -> Result<()> {
+    check_integer_range(value, min, max, &"of the serialized record")?;
+    let uint = (value as i128 - min as i128) as u64;
     let data = uint.to_le_bytes();
     let bits = integer_bits(min, max);
     buffer.add_bits(&data, bits);
+    Ok(())
+}
+
+/// Makes sure that an integer or scaled integer value is inside the range of its record type.
+/// The context is only formatted in case of an error and describes the location of the value.
+#[inline]
+#[cfg(feature = "write")]
+pub(crate) fn check_integer_range(
+    value: i64,
+    min: i64,
+    max: i64,
+    context: &dyn Display,
+) -> Result<()> {
+    if value < min || value > max {
+        Error::invalid(format!(
+            "Integer value {value} {context} is outside of the range {min} to {max}"
+        ))?
+    }
+    Ok(())
 }
 
 #[inline]
@@ -433,6 +548,7 @@ fn integer_bits(min: i64, max: i64) -> usize {
     }
 }
 
+#[cfg(feature = "read")]
 fn optional_attribute<T>(
     node: &Node,
     attribute: &str,
@@ -453,7 +569,27 @@ where
     })
 }
 
-fn serialize_record_type(rt: &RecordDataType) -> (String, String) {
+/// Makes sure that the optional bounds of a floating point type are not NaN
+/// and that the maximum is not smaller than the minimum.
+#[cfg(any(feature = "read", feature = "write"))]
+fn check_float_bounds(min: Option<f64>, max: Option<f64>, context: &str) -> Result<()> {
+    if min.is_some_and(f64::is_nan) || max.is_some_and(f64::is_nan) {
+        Error::invalid(format!(
+            "Minimum and maximum values of type 'Float' {context} must not be NaN"
+        ))?
+    }
+    if let (Some(min), Some(max)) = (min, max) {
+        if max < min {
+            Error::invalid(format!(
+                "Maximum value '{max}' and minimum value '{min}' of type 'Float' {context} are invalid"
+            ))?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "write")]
+pub(crate) fn serialize_record_type(rt: &RecordDataType) -> (String, String) {
     match rt {
         RecordDataType::Single { min, max } => {
             let mut str = String::from("type=\"Float\" precision=\"single\"");
@@ -0,0 +1,127 @@
+use crate::{Error, PointCloud, Result, Transform};
+
+/// Absolute tolerance used to detect identity poses.
+const IDENTITY_TOLERANCE: f64 = 1e-9;
+
+/// Common coordinate frame for the poses of multiple scans.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegistrationFrame {
+    /// The world frame of the E57 file, the poses are used as stored in the file.
+    #[default]
+    World,
+    /// The local frame of the point cloud with the given index.
+    /// The pose of this reference scan becomes the identity.
+    Scan(usize),
+}
+
+/// Describes the pose of a point cloud as stored in the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoseStatus {
+    /// The point cloud has no pose, which means it is already in the world frame.
+    Missing,
+    /// The point cloud has a pose that does not change the points.
+    Identity,
+    /// The point cloud has a pose that moves the points into the world frame.
+    Defined,
+}
+
+/// Transform of a single scan into a common coordinate frame.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ScanPose {
+    /// Index of the point cloud in the file.
+    pub index: usize,
+    /// GUID of the point cloud.
+    pub guid: Option<String>,
+    /// Status of the pose stored in the file.
+    pub status: PoseStatus,
+    /// Transform from the local coordinates of the scan into the common frame.
+    pub transform: Transform,
+}
+
+/// Returns the transforms of all point clouds into the chosen common frame.
+///
+/// Missing poses are treated as identity, as defined by the E57 standard.
+/// The status of each scan allows to detect files where the scans were never registered,
+/// because all poses are missing or identity.
+/// The returned transforms can be modified by an external registration step
+/// and written with `E57Writer::copy_with_poses()`.
+pub fn scan_poses(pointclouds: &[PointCloud], frame: RegistrationFrame) -> Result<Vec<ScanPose>> {
+    let world = |pc: &PointCloud| pc.transform.clone().unwrap_or_default();
+    let to_frame = match frame {
+        RegistrationFrame::World => Transform::default(),
+        RegistrationFrame::Scan(index) => match pointclouds.get(index) {
+            Some(pc) => world(pc).inverse(),
+            None => Error::invalid(format!(
+                "Reference scan index {index} is out of range for {} point clouds",
+                pointclouds.len()
+            ))?,
+        },
+    };
+    Ok(pointclouds
+        .iter()
+        .enumerate()
+        .map(|(index, pc)| {
+            let status = match &pc.transform {
+                None => PoseStatus::Missing,
+                Some(t) if t.is_identity(IDENTITY_TOLERANCE) => PoseStatus::Identity,
+                Some(_) => PoseStatus::Defined,
+            };
+            ScanPose {
+                index,
+                guid: pc.guid.clone(),
+                status,
+                transform: to_frame.compose(&world(pc)),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Quaternion, Translation};
+
+    fn pc(transform: Option<Transform>) -> PointCloud {
+        PointCloud {
+            transform,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn frames() {
+        let shifted = Transform {
+            rotation: Quaternion::default(),
+            translation: Translation {
+                x: 10.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+        let pcs = [
+            pc(None),
+            pc(Some(Transform::default())),
+            pc(Some(shifted.clone())),
+        ];
+
+        let poses = scan_poses(&pcs, RegistrationFrame::World).unwrap();
+        let status: Vec<_> = poses.iter().map(|p| p.status).collect();
+        assert_eq!(
+            status,
+            [
+                PoseStatus::Missing,
+                PoseStatus::Identity,
+                PoseStatus::Defined
+            ]
+        );
+        assert_eq!(poses[2].transform.translation.x, 10.0);
+
+        let poses = scan_poses(&pcs, RegistrationFrame::Scan(2)).unwrap();
+        assert!(poses[2].transform.is_identity(1e-12));
+        assert_eq!(poses[0].transform.translation.x, -10.0);
+        assert_eq!(poses[2].status, PoseStatus::Defined);
+
+        assert!(scan_poses(&pcs, RegistrationFrame::Scan(3)).is_err());
+    }
+}
@@ -1,19 +1,29 @@
+#[cfg(feature = "read")]
 use crate::error::Converter;
-use crate::Extension;
-use crate::{xml, DateTime, Error, Image, PointCloud, Result};
+use crate::{xml, DateTime, Result};
+#[cfg(feature = "write")]
+use crate::{Error, Extension, Image, PointCloud};
+#[cfg(feature = "read")]
 use roxmltree::Document;
 
 /// E57 XML Root structure with information shared by all elements in the file.
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct Root {
+    #[cfg_attr(not(feature = "read"), allow(dead_code))]
     pub format: String,
     pub guid: String,
+    #[cfg_attr(not(feature = "write"), allow(dead_code))]
     pub major_version: i64,
+    #[cfg_attr(not(feature = "write"), allow(dead_code))]
     pub minor_version: i64,
     pub library_version: Option<String>,
     pub creation: Option<DateTime>,
     pub coordinate_metadata: Option<String>,
+    #[cfg_attr(not(feature = "read"), allow(dead_code))]
+    pub custom_xml: Vec<String>,
+    #[cfg_attr(not(feature = "read"), allow(dead_code))]
+    pub custom_attributes: Vec<String>,
 }
 
 impl Default for Root {
@@ -26,10 +36,13 @@ impl Default for Root {
             creation: None,
             coordinate_metadata: None,
             library_version: None,
+            custom_xml: Vec::new(),
+            custom_attributes: Vec::new(),
         }
     }
 }
 
+#[cfg(feature = "read")]
 pub fn root_from_document(document: &Document) -> Result<Root> {
     let root = document
         .descendants()
@@ -46,6 +59,8 @@ pub fn root_from_document(document: &Document) -> Result<Root> {
     let creation = xml::opt_date_time(&root, "creationDateTime")?;
     let coordinate_metadata = xml::opt_string(&root, "coordinateMetadata")?;
     let library_version = xml::opt_string(&root, "e57LibraryVersion")?;
+    let custom_xml = xml::extension_elements(&root);
+    let custom_attributes = xml::extension_attributes(&root);
 
     Ok(Root {
         format,
@@ -55,14 +70,19 @@ pub fn root_from_document(document: &Document) -> Result<Root> {
         minor_version,
         coordinate_metadata,
         library_version,
+        custom_xml,
+        custom_attributes,
     })
 }
 
+#[cfg(feature = "write")]
 pub fn serialize_root(
     root: &Root,
     pointclouds: &[PointCloud],
     images: &[Image],
     extensions: &[Extension],
+    custom_xml: &[String],
+    custom_attributes: &[String],
 ) -> Result<String> {
     let mut xml = String::new();
     xml += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
@@ -70,7 +90,11 @@ pub fn serialize_root(
     for ext in extensions {
         xml += &format!("xmlns:{}=\"{}\" ", ext.namespace, ext.url);
     }
-    xml += "xmlns=\"http://www.astm.org/COMMIT/E57/2010-e57-v1.0\">\n";
+    xml += &format!(
+        "xmlns=\"{}\"{}>\n",
+        xml::E57_NAMESPACE,
+        xml::gen_attributes(custom_attributes)
+    );
     xml += "<formatName type=\"String\"><![CDATA[ASTM E57 3D Imaging Data File]]></formatName>\n";
     if root.guid.is_empty() {
         Error::invalid("Empty file GUID is not allowed")?
@@ -105,6 +129,7 @@ pub fn serialize_root(
         xml += &img.xml_string();
     }
     xml += "</images2D>\n";
+    xml += &xml::gen_custom(custom_xml);
     xml += "</e57Root>\n";
     Ok(xml)
 }
@@ -0,0 +1,164 @@
+#[cfg(feature = "read")]
+use crate::{Error, Result};
+
+/// Describes which points of a point cloud are returned by a point cloud reader.
+///
+/// Points that are not selected are skipped right after decoding,
+/// before any high level point is created or post-processed.
+#[cfg(feature = "read")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Subsampling {
+    /// Return all points of the point cloud.
+    #[default]
+    All,
+    /// Return every Nth point, starting with the first point.
+    /// N must be greater than zero.
+    EveryNth(u64),
+    /// Return a specific number of points that are evenly distributed across the point cloud.
+    /// Will return all points if the point cloud has fewer points than requested.
+    TargetCount(u64),
+    /// Return a random selection of points.
+    /// Each point is kept with the given probability, which must be between 0 and 1.
+    /// The same seed will always select the same points.
+    Random { probability: f64, seed: u64 },
+}
+
+#[cfg(feature = "read")]
+impl Subsampling {
+    pub(crate) fn validate(&self) -> Result<()> {
+        match self {
+            Self::EveryNth(0) => Error::invalid("Subsampling step size must be greater than zero"),
+            Self::Random { probability, .. } if !(0.0..=1.0).contains(probability) => {
+                Error::invalid(format!(
+                    "Subsampling probability must be between 0 and 1, but is {probability}"
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Stateful helper to decide which points are selected by a subsampling mode.
+#[cfg(feature = "read")]
+pub struct Sampler {
+    mode: Subsampling,
+    total: u64,
+    random: SplitMix64,
+}
+
+#[cfg(feature = "read")]
+impl Sampler {
+    pub fn new(mode: Subsampling, total: u64) -> Self {
+        let seed = if let Subsampling::Random { seed, .. } = mode {
+            seed
+        } else {
+            0
+        };
+        let random = SplitMix64::new(seed);
+        Self {
+            mode,
+            total,
+            random,
+        }
+    }
+
+    /// Returns true if the point with the given index should be kept.
+    /// Must be called exactly once for every point in ascending order.
+    pub fn keep(&mut self, index: u64) -> bool {
+        match self.mode {
+            Subsampling::All => true,
+            Subsampling::EveryNth(n) => index.is_multiple_of(n),
+            Subsampling::TargetCount(count) => {
+                if count >= self.total {
+                    true
+                } else {
+                    let current = (index as u128 * count as u128) / self.total as u128;
+                    let next = ((index as u128 + 1) * count as u128) / self.total as u128;
+                    next > current
+                }
+            }
+            Subsampling::Random { probability, .. } => self.random.next_random() < probability,
+        }
+    }
+
+    /// Returns the exact number of selected points, if it can be known in advance.
+    pub fn selected(&self) -> Option<u64> {
+        match self.mode {
+            Subsampling::All => Some(self.total),
+            Subsampling::EveryNth(n) => Some(self.total.div_ceil(n)),
+            Subsampling::TargetCount(count) => Some(count.min(self.total)),
+            Subsampling::Random { .. } => None,
+        }
+    }
+}
+
+/// Simple deterministic `SplitMix64` pseudo random number generator.
+/// Used for random subsampling and to generate synthetic test data.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns a pseudo random value between 0 (inclusive) and 1 (exclusive).
+    pub fn next_random(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+
+    fn count(mode: Subsampling, total: u64) -> u64 {
+        let mut sampler = Sampler::new(mode, total);
+        (0..total).filter(|i| sampler.keep(*i)).count() as u64
+    }
+
+    #[test]
+    fn every_nth() {
+        let sampler = Sampler::new(Subsampling::EveryNth(3), 10);
+        assert_eq!(sampler.selected(), Some(4));
+        assert_eq!(count(Subsampling::EveryNth(3), 10), 4);
+        assert_eq!(count(Subsampling::EveryNth(1), 10), 10);
+        assert!(Subsampling::EveryNth(0).validate().is_err());
+    }
+
+    #[test]
+    fn target_count() {
+        for (target, total) in [(0, 10), (1, 10), (3, 10), (7, 10), (10, 10), (20, 10)] {
+            let sampler = Sampler::new(Subsampling::TargetCount(target), total);
+            let expected = target.min(total);
+            assert_eq!(sampler.selected(), Some(expected));
+            assert_eq!(count(Subsampling::TargetCount(target), total), expected);
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mode = Subsampling::Random {
+            probability: 0.25,
+            seed: 42,
+        };
+        assert!(mode.validate().is_ok());
+        assert_eq!(Sampler::new(mode.clone(), 10000).selected(), None);
+        let selected = count(mode.clone(), 10000);
+        assert!(selected > 2000 && selected < 3000);
+        assert_eq!(count(mode, 10000), selected);
+
+        let invalid = Subsampling::Random {
+            probability: 1.5,
+            seed: 0,
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
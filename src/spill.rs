@@ -0,0 +1,45 @@
+use crate::error::Converter;
+use crate::Result;
+use std::fs::{remove_file, File, OpenOptions};
+#[cfg(feature = "write")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Temporary file that is deleted when dropped.
+pub(crate) struct SpillFile {
+    path: PathBuf,
+}
+
+impl SpillFile {
+    /// Creates a new empty file with a unique name and returns it together with a handle
+    /// for reading and writing. Uses the temporary directory of the system if no directory is given.
+    pub fn create(purpose: &str, directory: Option<PathBuf>) -> Result<(Self, File)> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let directory = directory.unwrap_or_else(std::env::temp_dir);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("e57-{purpose}-{}-{id}.tmp", std::process::id());
+        let path = directory.join(name);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .write_err(format!(
+                "Failed to create temporary file {}",
+                path.display()
+            ))?;
+        Ok((Self { path }, file))
+    }
+
+    #[cfg(feature = "write")]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
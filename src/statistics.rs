@@ -0,0 +1,275 @@
+use crate::paged_reader::PagedReader;
+use crate::queue_reader::QueueReader;
+use crate::{
+    CartesianBounds, PointCloud, RecordName, RecordValue, ResourceLimits, Result, SphericalBounds,
+};
+use std::io::{Read, Seek};
+
+/// Number of bins used for intensity and color histograms.
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// Statistics about the actual content of a point cloud.
+///
+/// All values are calculated from data stored in the file, without applying the point cloud pose.
+/// Unlike the optional bounds and limits in the point cloud descriptor, this data can be trusted.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct PointCloudStatistics {
+    /// Number of points that were actually read.
+    pub points: u64,
+    /// Bounds of all valid Cartesian coordinates.
+    /// None if the point cloud has no valid Cartesian coordinates.
+    pub cartesian_bounds: Option<CartesianBounds>,
+    /// Bounds of all valid spherical coordinates.
+    /// None if the point cloud has no valid spherical coordinates.
+    pub spherical_bounds: Option<SphericalBounds>,
+    /// Number of points with Cartesian coordinates that are not fully valid.
+    pub invalid_cartesian: u64,
+    /// Number of points with spherical coordinates that are not fully valid.
+    pub invalid_spherical: u64,
+    /// Number of points with invalid colors.
+    pub invalid_color: u64,
+    /// Number of points with invalid intensity.
+    pub invalid_intensity: u64,
+    /// Histogram of all valid intensity values, normalized to the minimum and maximum of the intensity data type in the prototype.
+    /// The intensity limits of the point cloud are not used.
+    /// Contains [`HISTOGRAM_BINS`] bins evenly distributed between 0 and 1.
+    /// None if the point cloud has no intensity.
+    pub intensity_histogram: Option<Vec<u64>>,
+    /// Histograms of all valid red, green and blue values, normalized to the minimum and maximum of the color data types in the prototype.
+    /// The color limits of the point cloud are not used.
+    /// Contains [`HISTOGRAM_BINS`] bins evenly distributed between 0 and 1.
+    /// None if the point cloud has no colors.
+    pub color_histograms: Option<[Vec<u64>; 3]>,
+}
+
+/// Looks up the indices of a group of records in the prototype.
+fn find<const N: usize>(pc: &PointCloud, names: [RecordName; N]) -> Option<[usize; N]> {
+    let mut indices = [0; N];
+    for (i, name) in names.iter().enumerate() {
+        indices[i] = pc.prototype.iter().position(|r| &r.name == name)?;
+    }
+    Some(indices)
+}
+
+fn find_one(pc: &PointCloud, name: RecordName) -> Option<usize> {
+    pc.prototype.iter().position(|r| r.name == name)
+}
+
+fn update_bounds(min: &mut Option<f64>, max: &mut Option<f64>, value: f64) {
+    if min.is_none_or(|m| value < m) {
+        *min = Some(value);
+    }
+    if max.is_none_or(|m| value > m) {
+        *max = Some(value);
+    }
+}
+
+fn add_to_histogram(histogram: &mut [u64], value: f32) {
+    // All bins have the same width, only the value 1.0 needs to be moved into the last bin
+    let bin = ((value.clamp(0.0, 1.0) * HISTOGRAM_BINS as f32) as usize).min(HISTOGRAM_BINS - 1);
+    histogram[bin] += 1;
+}
+
+impl PointCloudStatistics {
+    pub(crate) fn calculate<T: Read + Seek>(
+        pc: &PointCloud,
+        reader: &mut PagedReader<T>,
+        limits: ResourceLimits,
+    ) -> Result<Self> {
+        let cartesian = find(
+            pc,
+            [
+                RecordName::CartesianX,
+                RecordName::CartesianY,
+                RecordName::CartesianZ,
+            ],
+        );
+        let cartesian_invalid = find_one(pc, RecordName::CartesianInvalidState);
+        let spherical = find(
+            pc,
+            [
+                RecordName::SphericalRange,
+                RecordName::SphericalAzimuth,
+                RecordName::SphericalElevation,
+            ],
+        );
+        let spherical_invalid = find_one(pc, RecordName::SphericalInvalidState);
+        let color = find(
+            pc,
+            [
+                RecordName::ColorRed,
+                RecordName::ColorGreen,
+                RecordName::ColorBlue,
+            ],
+        );
+        let color_invalid = find_one(pc, RecordName::IsColorInvalid);
+        let intensity = find_one(pc, RecordName::Intensity);
+        let intensity_invalid = find_one(pc, RecordName::IsIntensityInvalid);
+
+        // Only decode the byte streams required for the statistics
+        let mut mask = vec![false; pc.prototype.len()];
+        let groups = [cartesian, spherical, color];
+        for i in groups.iter().flatten().flatten() {
+            mask[*i] = true;
+        }
+        let singles = [
+            cartesian_invalid,
+            spherical_invalid,
+            color_invalid,
+            intensity,
+            intensity_invalid,
+        ];
+        for i in singles.iter().flatten() {
+            mask[*i] = true;
+        }
+        let decode_any = mask.iter().any(|d| *d);
+        let mut queue_reader = QueueReader::new(pc, reader, limits)?;
+        queue_reader.set_decoded_records(mask)?;
+
+        let mut stats = Self {
+            intensity_histogram: intensity.map(|_| vec![0; HISTOGRAM_BINS]),
+            color_histograms: color.map(|_| {
+                [
+                    vec![0; HISTOGRAM_BINS],
+                    vec![0; HISTOGRAM_BINS],
+                    vec![0; HISTOGRAM_BINS],
+                ]
+            }),
+            ..Default::default()
+        };
+        let mut cb = CartesianBounds::default();
+        let mut sb = SphericalBounds::default();
+        let proto = &pc.prototype;
+
+        // Helper to read the state of the optional invalid state records
+        let state = |qr: &mut QueueReader<T>, index: Option<usize>| match index {
+            Some(i) => qr.pop_value(i)?.to_i64(&proto[i].data_type),
+            None => Ok(0),
+        };
+        let pop = |qr: &mut QueueReader<T>, indices: [usize; 3]| -> Result<[RecordValue; 3]> {
+            Ok([
+                qr.pop_value(indices[0])?,
+                qr.pop_value(indices[1])?,
+                qr.pop_value(indices[2])?,
+            ])
+        };
+
+        // Point clouds without any relevant records only contribute to the point count
+        if !decode_any {
+            stats.points = pc.records;
+            stats.invalid_cartesian = pc.records;
+            stats.invalid_spherical = pc.records;
+            stats.invalid_color = pc.records;
+            stats.invalid_intensity = pc.records;
+            return Ok(stats);
+        }
+
+        while stats.points < pc.records {
+            queue_reader.advance()?;
+            let available = queue_reader.available() as u64;
+            let count = available.min(pc.records - stats.points);
+            for _ in 0..count {
+                let cs = state(&mut queue_reader, cartesian_invalid)?;
+                if let Some(indices) = cartesian {
+                    let values = pop(&mut queue_reader, indices)?;
+                    if cs == 0 {
+                        let x = values[0].to_f64(&proto[indices[0]].data_type)?;
+                        let y = values[1].to_f64(&proto[indices[1]].data_type)?;
+                        let z = values[2].to_f64(&proto[indices[2]].data_type)?;
+                        update_bounds(&mut cb.x_min, &mut cb.x_max, x);
+                        update_bounds(&mut cb.y_min, &mut cb.y_max, y);
+                        update_bounds(&mut cb.z_min, &mut cb.z_max, z);
+                    }
+                }
+                if cartesian.is_none() || cs != 0 {
+                    stats.invalid_cartesian += 1;
+                }
+
+                let ss = state(&mut queue_reader, spherical_invalid)?;
+                if let Some(indices) = spherical {
+                    let values = pop(&mut queue_reader, indices)?;
+                    if ss == 0 {
+                        let r = values[0].to_f64(&proto[indices[0]].data_type)?;
+                        let a = values[1].to_f64(&proto[indices[1]].data_type)?;
+                        let e = values[2].to_f64(&proto[indices[2]].data_type)?;
+                        update_bounds(&mut sb.range_min, &mut sb.range_max, r);
+                        update_bounds(&mut sb.azimuth_start, &mut sb.azimuth_end, a);
+                        update_bounds(&mut sb.elevation_min, &mut sb.elevation_max, e);
+                    }
+                }
+                if spherical.is_none() || ss != 0 {
+                    stats.invalid_spherical += 1;
+                }
+
+                let is = state(&mut queue_reader, intensity_invalid)?;
+                if let Some(index) = intensity {
+                    let value = queue_reader.pop_value(index)?;
+                    if let Some(histogram) = &mut stats.intensity_histogram {
+                        if is == 0 {
+                            // Use unwrap_or() to be more robust against
+                            // weird files without proper min/max values.
+                            let value = value.to_unit_f32(&proto[index].data_type).unwrap_or(0.0);
+                            add_to_histogram(histogram, value);
+                        }
+                    }
+                }
+                if intensity.is_none() || is != 0 {
+                    stats.invalid_intensity += 1;
+                }
+
+                let cols = state(&mut queue_reader, color_invalid)?;
+                if let Some(indices) = color {
+                    let values = pop(&mut queue_reader, indices)?;
+                    if let Some(histograms) = &mut stats.color_histograms {
+                        if cols == 0 {
+                            for c in 0..3 {
+                                // Use unwrap_or() to be more robust against
+                                // weird files without proper min/max values.
+                                let value = values[c]
+                                    .to_unit_f32(&proto[indices[c]].data_type)
+                                    .unwrap_or(0.0);
+                                add_to_histogram(&mut histograms[c], value);
+                            }
+                        }
+                    }
+                }
+                if color.is_none() || cols != 0 {
+                    stats.invalid_color += 1;
+                }
+
+                stats.points += 1;
+            }
+        }
+
+        if cartesian.is_some() && cb.x_min.is_some() {
+            stats.cartesian_bounds = Some(cb);
+        }
+        if spherical.is_some() && sb.range_min.is_some() {
+            stats.spherical_bounds = Some(sb);
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_bins_have_equal_width() {
+        let mut histogram = vec![0; HISTOGRAM_BINS];
+        let width = 1.0 / HISTOGRAM_BINS as f32;
+        for value in [0.0, width * 0.9, -1.0] {
+            add_to_histogram(&mut histogram, value);
+        }
+        assert_eq!(histogram[0], 3);
+        add_to_histogram(&mut histogram, width * 1.1);
+        assert_eq!(histogram[1], 1);
+        for value in [1.0 - width * 0.9, 1.0, 2.0] {
+            add_to_histogram(&mut histogram, value);
+        }
+        assert_eq!(histogram[HISTOGRAM_BINS - 1], 3);
+        assert_eq!(histogram.iter().sum::<u64>(), 7);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::spill::SpillFile;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// Size of the chunks that are read from the source at once.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Defines where a [`StreamBuffer`] keeps the data read from its source.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SpillPolicy {
+    /// Keep all data in memory.
+    #[default]
+    Memory,
+    /// Keep up to the given number of bytes in memory and move all data
+    /// into a temporary file when the limit is exceeded.
+    /// The file is created in the given directory or in the temporary directory
+    /// of the system and it is deleted when the buffer is dropped.
+    TempFile {
+        memory_limit: usize,
+        directory: Option<PathBuf>,
+    },
+}
+
+/// Adds seeking to sources that can only be read sequentially, like stdin or HTTP bodies.
+///
+/// All data read from the source is buffered, so that any position that was already
+/// reached can be visited again. Seeking forward reads from the source until
+/// the target position is buffered. Since the XML section of an E57 file is located at its end,
+/// the whole file ends up in the buffer when opening it with an [`E57Reader`](crate::E57Reader).
+/// Use the spill policy to decide if the data is kept in memory or in a temporary file.
+pub struct StreamBuffer<R: Read> {
+    source: R,
+    policy: SpillPolicy,
+    memory: Vec<u8>,
+    file: Option<(SpillFile, File)>,
+    length: u64,
+    position: u64,
+    eof: bool,
+}
+
+impl<R: Read> StreamBuffer<R> {
+    /// Creates a new buffer for the given source and spill policy.
+    pub fn new(source: R, policy: SpillPolicy) -> Self {
+        Self {
+            source,
+            policy,
+            memory: Vec::new(),
+            file: None,
+            length: 0,
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the number of bytes read from the source so far.
+    pub fn buffered(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns true if the data was moved into a temporary file.
+    pub fn spilled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Reads from the source until the given number of bytes is buffered or the end is reached.
+    fn fill_to(&mut self, target: u64) -> Result<()> {
+        if self.length >= target || self.eof {
+            return Ok(());
+        }
+        let mut chunk = vec![0_u8; CHUNK_SIZE];
+        while self.length < target && !self.eof {
+            let read = match self.source.read(&mut chunk) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            self.append(&chunk[..read])?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, data: &[u8]) -> Result<()> {
+        if let Some((_, file)) = &mut self.file {
+            file.seek(SeekFrom::Start(self.length))?;
+            file.write_all(data)?;
+        } else {
+            self.memory.extend_from_slice(data);
+            if let SpillPolicy::TempFile {
+                memory_limit,
+                directory,
+            } = &self.policy
+            {
+                if self.memory.len() > *memory_limit {
+                    let (spill, mut file) =
+                        SpillFile::create("stream", directory.clone()).map_err(Error::other)?;
+                    file.write_all(&self.memory)?;
+                    self.memory = Vec::new();
+                    self.file = Some((spill, file));
+                }
+            }
+        }
+        self.length += data.len() as u64;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamBuffer<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.fill_to(self.position.saturating_add(buf.len() as u64))?;
+        if self.position >= self.length {
+            return Ok(0);
+        }
+        let available = (self.length - self.position).min(buf.len() as u64) as usize;
+        if let Some((_, file)) = &mut self.file {
+            file.seek(SeekFrom::Start(self.position))?;
+            file.read_exact(&mut buf[..available])?;
+        } else {
+            let start = self.position as usize;
+            buf[..available].copy_from_slice(&self.memory[start..start + available]);
+        }
+        self.position += available as u64;
+        Ok(available)
+    }
+}
+
+impl<R: Read> Seek for StreamBuffer<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                self.fill_to(u64::MAX)?;
+                self.length.checked_add_signed(offset)
+            }
+        };
+        let target = target.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.position = target;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Source that returns only a few bytes with each read call.
+    struct Trickle(Vec<u8>, usize);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(7).min(self.0.len() - self.1);
+            buf[..n].copy_from_slice(&self.0[self.1..self.1 + n]);
+            self.1 += n;
+            Ok(n)
+        }
+    }
+
+    fn check(policy: SpillPolicy, spilled: bool) {
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let mut buffer = StreamBuffer::new(Trickle(data.clone(), 0), policy);
+
+        let mut bytes = [0_u8; 10];
+        buffer.seek(SeekFrom::Start(500)).unwrap();
+        buffer.read_exact(&mut bytes).unwrap();
+        assert_eq!(bytes, data[500..510]);
+        assert!(buffer.buffered() >= 510 && buffer.buffered() < 1000);
+
+        buffer.seek(SeekFrom::Start(3)).unwrap();
+        buffer.read_exact(&mut bytes).unwrap();
+        assert_eq!(bytes, data[3..13]);
+
+        assert_eq!(buffer.seek(SeekFrom::End(-4)).unwrap(), 996);
+        assert_eq!(buffer.buffered(), 1000);
+        let mut rest = Vec::new();
+        buffer.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, data[996..]);
+        assert!(buffer.seek(SeekFrom::Current(-2000)).is_err());
+        assert_eq!(buffer.spilled(), spilled);
+    }
+
+    #[test]
+    fn memory() {
+        check(SpillPolicy::Memory, false);
+    }
+
+    #[test]
+    fn temp_file() {
+        let policy = SpillPolicy::TempFile {
+            memory_limit: 100,
+            directory: None,
+        };
+        check(policy, true);
+    }
+}
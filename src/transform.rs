@@ -1,7 +1,54 @@
+#[cfg(any(feature = "read", feature = "write"))]
 use crate::xml;
-use crate::Result;
+#[cfg(feature = "read")]
+use crate::{Error, Result};
+#[cfg(feature = "read")]
 use roxmltree::Node;
 
+/// Defines how rotation quaternions of poses are validated when reading a file.
+///
+/// The E57 standard requires unit quaternions, but real-world files
+/// sometimes contain non-normalized or even zero quaternions.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum QuaternionHandling {
+    /// Quaternions are used as stored in the file without any checks.
+    /// This is the default behavior.
+    #[default]
+    Raw,
+    /// Quaternions with a norm deviating more than the tolerance from one are normalized.
+    /// Zero or non-finite quaternions are replaced with the identity rotation.
+    Normalize {
+        /// Maximum deviation of the norm from one that is accepted without changes.
+        tolerance: f64,
+    },
+    /// Quaternions with a norm deviating more than the tolerance from one are rejected with an error.
+    Strict {
+        /// Maximum deviation of the norm from one that is accepted.
+        tolerance: f64,
+    },
+}
+
+/// Describes what was done with an invalid rotation quaternion when reading a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuaternionAction {
+    /// The quaternion was scaled to unit length.
+    Normalized,
+    /// The quaternion could not be normalized and was replaced with the identity rotation.
+    ReplacedWithIdentity,
+}
+
+/// Records a correction of a pose quaternion that was applied when reading a file.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PoseCorrection {
+    /// Path of the corrected pose in the XML section, for example `/data3D/0/pose`.
+    pub path: String,
+    /// Original quaternion as stored in the file.
+    pub original: Quaternion,
+    /// Action that was applied to the quaternion.
+    pub action: QuaternionAction,
+}
+
 /// Describes the rotation of a point cloud.
 #[derive(Clone, Debug)]
 pub struct Quaternion {
@@ -16,6 +63,7 @@ pub struct Quaternion {
 }
 
 impl Quaternion {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         let w = xml::req_f64(node, "w")?;
         let x = xml::req_f64(node, "x")?;
@@ -23,6 +71,105 @@ impl Quaternion {
         let z = xml::req_f64(node, "z")?;
         Ok(Self { w, x, y, z })
     }
+
+    /// Returns the norm (length) of the quaternion.
+    pub fn norm(&self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    /// Returns a copy of the quaternion scaled to unit length.
+    /// Returns None if the quaternion is zero or not finite.
+    pub fn normalized(&self) -> Option<Self> {
+        let norm = self.norm();
+        if !norm.is_finite() || norm <= f64::EPSILON {
+            return None;
+        }
+        Some(Self {
+            w: self.w / norm,
+            x: self.x / norm,
+            y: self.y / norm,
+            z: self.z / norm,
+        })
+    }
+
+    /// Validates the quaternion according to the handling mode and corrects it if necessary.
+    /// Returns the applied action or None if the quaternion was not changed.
+    #[cfg(feature = "read")]
+    pub(crate) fn validate(
+        &mut self,
+        handling: QuaternionHandling,
+    ) -> Result<Option<QuaternionAction>> {
+        let deviation = (self.norm() - 1.0).abs();
+        match handling {
+            QuaternionHandling::Raw => Ok(None),
+            QuaternionHandling::Normalize { tolerance } => {
+                if deviation <= tolerance {
+                    Ok(None)
+                } else if let Some(normalized) = self.normalized() {
+                    *self = normalized;
+                    Ok(Some(QuaternionAction::Normalized))
+                } else {
+                    *self = Self::default();
+                    Ok(Some(QuaternionAction::ReplacedWithIdentity))
+                }
+            }
+            QuaternionHandling::Strict { tolerance } => {
+                if deviation <= tolerance {
+                    Ok(None)
+                } else {
+                    Error::invalid(format!(
+                        "Quaternion {self:?} is not normalized, its norm deviates {deviation} from one"
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Returns the conjugate of the quaternion.
+    /// For unit quaternions this is the inverse rotation.
+    pub fn conjugate(&self) -> Self {
+        Self {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    /// Returns the Hamilton product of this and the other quaternion.
+    /// The resulting rotation applies the other rotation first and then this rotation.
+    pub fn multiply(&self, other: &Self) -> Self {
+        let (a, b) = (self, other);
+        Self {
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        }
+    }
+
+    /// Returns the row-major 3x3 rotation matrix for this quaternion.
+    /// The quaternion is expected to be normalized.
+    pub fn to_rotation_matrix(&self) -> [[f64; 3]; 3] {
+        let Self { w, x, y, z } = *self;
+        [
+            [
+                w * w + x * x - y * y - z * z,
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                w * w + y * y - x * x - z * z,
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                w * w + z * z - x * x - y * y,
+            ],
+        ]
+    }
 }
 
 impl Default for Quaternion {
@@ -48,12 +195,20 @@ pub struct Translation {
 }
 
 impl Translation {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         let x = xml::req_f64(node, "x")?;
         let y = xml::req_f64(node, "y")?;
         let z = xml::req_f64(node, "z")?;
         Ok(Self { x, y, z })
     }
+
+    #[cfg(any(feature = "read", feature = "write"))]
+    pub(crate) fn scale(&mut self, factor: f64) {
+        self.x *= factor;
+        self.y *= factor;
+        self.z *= factor;
+    }
 }
 
 impl Default for Translation {
@@ -76,6 +231,7 @@ pub struct Transform {
 }
 
 impl Transform {
+    #[cfg(feature = "read")]
     pub(crate) fn from_node(node: &Node) -> Result<Self> {
         let translation = match node.children().find(|n| n.has_tag_name("translation")) {
             Some(node) => Translation::from_node(&node)?,
@@ -91,6 +247,68 @@ impl Transform {
         })
     }
 
+    /// Returns the row-major 4x4 homogeneous transformation matrix.
+    /// The last column contains the translation.
+    pub fn to_matrix(&self) -> [[f64; 4]; 4] {
+        let r = self.rotation.to_rotation_matrix();
+        let t = &self.translation;
+        [
+            [r[0][0], r[0][1], r[0][2], t.x],
+            [r[1][0], r[1][1], r[1][2], t.y],
+            [r[2][0], r[2][1], r[2][2], t.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+
+    /// Applies the rotation and then the translation to a point.
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let r = self.rotation.to_rotation_matrix();
+        let t = &self.translation;
+        let [x, y, z] = point;
+        [
+            r[0][0] * x + r[0][1] * y + r[0][2] * z + t.x,
+            r[1][0] * x + r[1][1] * y + r[1][2] * z + t.y,
+            r[2][0] * x + r[2][1] * y + r[2][2] * z + t.z,
+        ]
+    }
+
+    /// Returns the inverse transform, which maps the transformed points back.
+    /// The rotation quaternion is expected to be normalized.
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.conjugate();
+        let t = &self.translation;
+        let inverse = Self {
+            rotation,
+            translation: Translation::default(),
+        };
+        let [x, y, z] = inverse.apply([-t.x, -t.y, -t.z]);
+        Self {
+            translation: Translation { x, y, z },
+            ..inverse
+        }
+    }
+
+    /// Returns the combined transform that applies the other transform first and then this transform.
+    pub fn compose(&self, other: &Self) -> Self {
+        let t = &other.translation;
+        let [x, y, z] = self.apply([t.x, t.y, t.z]);
+        Self {
+            rotation: self.rotation.multiply(&other.rotation),
+            translation: Translation { x, y, z },
+        }
+    }
+
+    /// Returns true if the transform does not change points, allowing the given absolute deviation.
+    /// Both quaternions with the scalar part 1 and -1 represent the identity rotation.
+    pub fn is_identity(&self, tolerance: f64) -> bool {
+        let (q, t) = (&self.rotation, &self.translation);
+        (q.w.abs() - 1.0).abs() <= tolerance
+            && [q.x, q.y, q.z, t.x, t.y, t.z]
+                .iter()
+                .all(|v| v.abs() <= tolerance)
+    }
+
+    #[cfg(feature = "write")]
     pub(crate) fn xml_string(&self, tag_name: &str) -> String {
         let w = xml::gen_float("w", self.rotation.w);
         let x = xml::gen_float("x", self.rotation.x);
@@ -106,3 +324,108 @@ impl Transform {
         format!("<{tag_name} type=\"Structure\">\n{quat}{trans}</{tag_name}>\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quat(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w, x, y, z }
+    }
+
+    #[test]
+    #[cfg(feature = "read")]
+    fn normalize_quaternion() {
+        let handling = QuaternionHandling::Normalize { tolerance: 1e-6 };
+        let mut q = quat(2.0, 0.0, 0.0, 0.0);
+        let action = q.validate(handling).unwrap();
+        assert_eq!(action, Some(QuaternionAction::Normalized));
+        assert_eq!(q.w, 1.0);
+
+        let mut q = quat(0.0, 0.0, 0.0, 0.0);
+        let action = q.validate(handling).unwrap();
+        assert_eq!(action, Some(QuaternionAction::ReplacedWithIdentity));
+        assert_eq!(q.w, 1.0);
+
+        let mut q = quat(1.0 + 1e-9, 0.0, 0.0, 0.0);
+        assert_eq!(q.validate(handling).unwrap(), None);
+        assert_eq!(q.w, 1.0 + 1e-9);
+    }
+
+    #[test]
+    #[cfg(feature = "read")]
+    fn strict_and_raw_quaternion() {
+        let strict = QuaternionHandling::Strict { tolerance: 1e-6 };
+        assert!(quat(0.0, 0.0, 0.0, 0.0).validate(strict).is_err());
+        assert!(quat(0.5, 0.5, 0.5, 0.5).validate(strict).unwrap().is_none());
+
+        let mut q = quat(0.0, 0.0, 0.0, 0.0);
+        assert!(q.validate(QuaternionHandling::Raw).unwrap().is_none());
+        assert_eq!(q.w, 0.0);
+    }
+
+    #[test]
+    fn identity_matrix() {
+        let m = Transform::default().to_matrix();
+        for (r, row) in m.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                assert_eq!(*value, if r == c { 1.0 } else { 0.0 });
+            }
+        }
+    }
+
+    #[test]
+    fn rotation_around_z() {
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let t = Transform {
+            rotation: Quaternion {
+                w: s,
+                x: 0.0,
+                y: 0.0,
+                z: s,
+            },
+            translation: Translation {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        };
+        let m = t.to_matrix();
+        // The X axis is rotated onto the Y axis
+        assert!((m[0][0]).abs() < 1e-12);
+        assert!((m[1][0] - 1.0).abs() < 1e-12);
+        assert!((m[0][1] + 1.0).abs() < 1e-12);
+        assert_eq!([m[0][3], m[1][3], m[2][3]], [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn inverse_and_compose() {
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let a = Transform {
+            rotation: quat(s, 0.0, 0.0, s),
+            translation: Translation {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        };
+        let b = Transform {
+            rotation: quat(s, s, 0.0, 0.0),
+            translation: Translation {
+                x: -4.0,
+                y: 0.5,
+                z: 0.0,
+            },
+        };
+        let p = [0.3, -1.2, 2.5];
+        let expected = a.apply(b.apply(p));
+        let combined = a.compose(&b).apply(p);
+        for (e, c) in expected.iter().zip(combined) {
+            assert!((e - c).abs() < 1e-12);
+        }
+        assert!(a.compose(&a.inverse()).is_identity(1e-12));
+        assert!(a.inverse().compose(&a).is_identity(1e-12));
+        assert!(!a.is_identity(1e-12));
+        assert!(Transform::default().is_identity(0.0));
+    }
+}
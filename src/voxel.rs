@@ -0,0 +1,220 @@
+use crate::{CartesianCoordinate, Color, Error, Point, Result, SphericalCoordinate};
+use std::collections::HashMap;
+
+/// Defines which point is used to represent all points inside a voxel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VoxelPoint {
+    /// The first point of each voxel is used without any modifications.
+    #[default]
+    First,
+    /// A new point is created from the mean values of all points in the voxel.
+    /// Cartesian coordinates, colors and intensities are averaged.
+    /// Spherical coordinates are set to invalid, row and column index are taken from the first point.
+    Centroid,
+}
+
+/// Reduces a stream of points to one representative point per occupied voxel.
+///
+/// The voxels are cubes with the given edge length aligned to the origin of the coordinate system.
+/// Points without valid Cartesian coordinates are skipped.
+/// The input points are consumed one by one, so the memory usage only depends on the number
+/// of occupied voxels and not on the number of input points.
+/// The resulting points are ordered by the first appearance of their voxel in the input.
+///
+/// Any iterator over points can be used as input, for example a [`crate::PointCloudReaderSimple`].
+/// The first error returned by the input iterator will abort the operation.
+pub fn downsample_voxel<I>(points: I, cell_size: f64, mode: VoxelPoint) -> Result<Vec<Point>>
+where
+    I: IntoIterator<Item = Result<Point>>,
+{
+    if !cell_size.is_finite() || cell_size <= 0.0 {
+        Error::invalid(format!(
+            "Voxel cell size must be a positive number, but is {cell_size}"
+        ))?
+    }
+
+    let mut lookup: HashMap<(i64, i64, i64), usize> = HashMap::new();
+    let mut voxels: Vec<Voxel> = Vec::new();
+    for p in points {
+        let p = p?;
+        let (x, y, z) = if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
+            (x, y, z)
+        } else {
+            continue;
+        };
+        let key = (
+            (x / cell_size).floor() as i64,
+            (y / cell_size).floor() as i64,
+            (z / cell_size).floor() as i64,
+        );
+        if let Some(index) = lookup.get(&key) {
+            if mode == VoxelPoint::Centroid {
+                voxels[*index].add(&p, x, y, z);
+            }
+        } else {
+            lookup.insert(key, voxels.len());
+            voxels.push(Voxel::new(p, x, y, z));
+        }
+    }
+
+    Ok(voxels
+        .into_iter()
+        .map(|v| match mode {
+            VoxelPoint::First => v.first,
+            VoxelPoint::Centroid => v.centroid(),
+        })
+        .collect())
+}
+
+/// Accumulates the attributes of all points inside a single voxel.
+struct Voxel {
+    first: Point,
+    count: u64,
+    sum: [f64; 3],
+    color_count: u64,
+    color_sum: [f64; 3],
+    intensity_count: u64,
+    intensity_sum: f64,
+}
+
+impl Voxel {
+    fn new(first: Point, x: f64, y: f64, z: f64) -> Self {
+        let mut voxel = Self {
+            first: first.clone(),
+            count: 0,
+            sum: [0.0; 3],
+            color_count: 0,
+            color_sum: [0.0; 3],
+            intensity_count: 0,
+            intensity_sum: 0.0,
+        };
+        voxel.add(&first, x, y, z);
+        voxel
+    }
+
+    fn add(&mut self, p: &Point, x: f64, y: f64, z: f64) {
+        self.count += 1;
+        self.sum[0] += x;
+        self.sum[1] += y;
+        self.sum[2] += z;
+        if let Some(c) = &p.color {
+            self.color_count += 1;
+            self.color_sum[0] += c.red as f64;
+            self.color_sum[1] += c.green as f64;
+            self.color_sum[2] += c.blue as f64;
+        }
+        if let Some(i) = p.intensity {
+            self.intensity_count += 1;
+            self.intensity_sum += i as f64;
+        }
+    }
+
+    fn centroid(self) -> Point {
+        let n = self.count as f64;
+        let color = if self.color_count > 0 {
+            let n = self.color_count as f64;
+            Some(Color {
+                red: (self.color_sum[0] / n) as f32,
+                green: (self.color_sum[1] / n) as f32,
+                blue: (self.color_sum[2] / n) as f32,
+            })
+        } else {
+            None
+        };
+        let intensity = if self.intensity_count > 0 {
+            Some((self.intensity_sum / self.intensity_count as f64) as f32)
+        } else {
+            None
+        };
+        Point {
+            cartesian: CartesianCoordinate::Valid {
+                x: self.sum[0] / n,
+                y: self.sum[1] / n,
+                z: self.sum[2] / n,
+            },
+            spherical: SphericalCoordinate::Invalid,
+            color,
+            intensity,
+            row: self.first.row,
+            column: self.first.column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64, z: f64, intensity: f32) -> Result<Point> {
+        Ok(Point {
+            cartesian: CartesianCoordinate::Valid { x, y, z },
+            spherical: SphericalCoordinate::Invalid,
+            color: None,
+            intensity: Some(intensity),
+            row: -1,
+            column: -1,
+        })
+    }
+
+    fn input() -> Vec<Result<Point>> {
+        vec![
+            point(0.1, 0.1, 0.1, 0.0),
+            point(0.3, 0.3, 0.3, 1.0),
+            point(-0.1, 0.1, 0.1, 0.5),
+            point(1.5, 0.5, 0.5, 0.5),
+            point(1.7, 0.7, 0.7, 0.5),
+        ]
+    }
+
+    #[test]
+    fn first() {
+        let points = downsample_voxel(input(), 1.0, VoxelPoint::First).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(
+            points[0].cartesian,
+            CartesianCoordinate::Valid {
+                x: 0.1,
+                y: 0.1,
+                z: 0.1
+            }
+        );
+        assert_eq!(
+            points[1].cartesian,
+            CartesianCoordinate::Valid {
+                x: -0.1,
+                y: 0.1,
+                z: 0.1
+            }
+        );
+        assert_eq!(
+            points[2].cartesian,
+            CartesianCoordinate::Valid {
+                x: 1.5,
+                y: 0.5,
+                z: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn centroid() {
+        let points = downsample_voxel(input(), 1.0, VoxelPoint::Centroid).unwrap();
+        assert_eq!(points.len(), 3);
+        if let CartesianCoordinate::Valid { x, y, z } = points[0].cartesian {
+            assert!((x - 0.2).abs() < 0.000001);
+            assert!((y - 0.2).abs() < 0.000001);
+            assert!((z - 0.2).abs() < 0.000001);
+        } else {
+            panic!("Expected valid cartesian coordinate");
+        }
+        assert_eq!(points[0].intensity, Some(0.5));
+        assert_eq!(points[1].intensity, Some(0.5));
+    }
+
+    #[test]
+    fn invalid_cell_size() {
+        assert!(downsample_voxel(input(), 0.0, VoxelPoint::First).is_err());
+        assert!(downsample_voxel(input(), -1.0, VoxelPoint::First).is_err());
+        assert!(downsample_voxel(input(), f64::NAN, VoxelPoint::First).is_err());
+    }
+}
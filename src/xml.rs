@@ -1,12 +1,92 @@
+#[cfg(feature = "read")]
 use crate::error::Converter;
-use crate::{DateTime, Error, Result, Transform};
-use roxmltree::Node;
+#[cfg(feature = "read")]
+use crate::{DateTime, Error, ErrorKind, Result, Transform};
+#[cfg(feature = "read")]
+use roxmltree::{Document, Node};
+#[cfg(feature = "write")]
 use std::fmt::Display;
+#[cfg(feature = "read")]
 use std::str::FromStr;
 
-pub fn opt_string(parent_node: &Node, tag_name: &str) -> Result<Option<String>> {
+/// XML namespace of the E57 standard.
+#[cfg(any(feature = "read", feature = "write"))]
+pub const E57_NAMESPACE: &str = "http://www.astm.org/COMMIT/E57/2010-e57-v1.0";
+
+/// Returns the unchanged XML text of all child elements that belong to the namespace of an extension.
+#[cfg(feature = "read")]
+pub fn extension_elements(parent_node: &Node) -> Vec<String> {
+    let text = parent_node.document().input_text();
+    parent_node
+        .children()
+        .filter(|n| n.is_element())
+        .filter(|n| {
+            n.tag_name()
+                .namespace()
+                .is_some_and(|ns| ns != E57_NAMESPACE)
+        })
+        .map(|n| text[n.range()].to_owned())
+        .collect()
+}
+
+/// Returns the XML text of all attributes of an element that belong to the namespace of an extension.
+/// Each attribute is returned with its prefix and escaped value, for example `ven:id="42"`.
+#[cfg(feature = "read")]
+pub fn extension_attributes(node: &Node) -> Vec<String> {
+    node.attributes()
+        .filter(|a| a.namespace().is_some_and(|ns| ns != E57_NAMESPACE))
+        .map(|a| {
+            let name = qualified_name(node, a.namespace(), a.name());
+            format!("{name}=\"{}\"", escape(a.value(), true))
+        })
+        .collect()
+}
+
+/// Returns the path of an XML node, for example `/data3D/0/pose`.
+/// Children of vectors are identified by their index instead of their tag name.
+#[cfg(feature = "read")]
+pub fn node_path(node: &Node) -> String {
+    let mut parts = Vec::new();
+    let mut current = *node;
+    while let Some(parent) = current.parent_element() {
+        if parent.attribute("type") == Some("Vector") {
+            let index = parent
+                .children()
+                .filter(|n| n.is_element())
+                .position(|n| n == current)
+                .unwrap_or(0);
+            parts.push(index.to_string());
+        } else {
+            parts.push(current.tag_name().name().to_string());
+        }
+        current = parent;
+    }
+    parts.reverse();
+    format!("/{}", parts.join("/"))
+}
+
+/// Adds the XML path of a child tag and the XML error kind to the error of a result.
+#[cfg(feature = "read")]
+fn with_path<T>(parent_node: &Node, tag_name: &str, result: Result<T>) -> Result<T> {
+    result.map_err(|e| {
+        let parent_path = node_path(parent_node);
+        let path = if parent_path == "/" {
+            format!("/{tag_name}")
+        } else {
+            format!("{parent_path}/{tag_name}")
+        };
+        e.with_xml_path(path).with_kind(ErrorKind::Xml)
+    })
+}
+
+/// Finds a child tag and checks its type attribute.
+#[cfg(feature = "read")]
+fn find_tag<'a, 'input>(
+    parent_node: &Node<'a, 'input>,
+    tag_name: &str,
+    expected_type: &str,
+) -> Result<Option<Node<'a, 'input>>> {
     if let Some(tag) = parent_node.children().find(|n| n.has_tag_name(tag_name)) {
-        let expected_type = "String";
         if let Some(found_type) = tag.attribute("type") {
             if found_type != expected_type {
                 Error::invalid(format!(
@@ -16,99 +96,249 @@ pub fn opt_string(parent_node: &Node, tag_name: &str) -> Result<Option<String>>
         } else {
             Error::invalid(format!("XML tag '{tag_name}' has no 'type' attribute"))?
         }
-        let text = tag.text().unwrap_or("");
-        Ok(Some(text.to_string()))
+        Ok(Some(tag))
     } else {
         Ok(None)
     }
 }
 
+#[cfg(feature = "read")]
+pub fn opt_string(parent_node: &Node, tag_name: &str) -> Result<Option<String>> {
+    let tag = with_path(
+        parent_node,
+        tag_name,
+        find_tag(parent_node, tag_name, "String"),
+    )?;
+    Ok(tag.map(|tag| tag.text().unwrap_or("").to_string()))
+}
+
+#[cfg(feature = "read")]
 pub fn req_string(parent_node: &Node, tag_name: &str) -> Result<String> {
     let str = opt_string(parent_node, tag_name)?;
-    str.invalid_err(format!("XML tag '{tag_name}' was not found"))
+    let result = str.invalid_err(format!("XML tag '{tag_name}' was not found"));
+    with_path(parent_node, tag_name, result)
 }
 
+#[cfg(feature = "read")]
 fn opt_num<T: FromStr + Sync + Send>(
     parent_node: &Node,
     tag_name: &str,
     expected_type: &str,
 ) -> Result<Option<T>> {
-    if let Some(tag) = parent_node.children().find(|n| n.has_tag_name(tag_name)) {
-        if let Some(found_type) = tag.attribute("type") {
-            if found_type != expected_type {
-                Error::invalid(format!(
-                    "Found XML tag '{tag_name}' with type '{found_type}' instead of '{expected_type}'"
-                ))?
-            }
-        } else {
-            Error::invalid(format!("XML tag '{tag_name}' has no 'type' attribute"))?
-        }
+    let tag = with_path(
+        parent_node,
+        tag_name,
+        find_tag(parent_node, tag_name, expected_type),
+    )?;
+    if let Some(tag) = tag {
         let text = tag.text().unwrap_or("0");
         if let Ok(parsed) = text.parse::<T>() {
             Ok(Some(parsed))
         } else {
-            Error::invalid(format!(
+            let result = Error::invalid(format!(
                 "Cannot parse value '{text}' of XML tag '{tag_name}' as '{expected_type}'"
-            ))?
+            ));
+            with_path(parent_node, tag_name, result)
         }
     } else {
         Ok(None)
     }
 }
 
+#[cfg(feature = "read")]
 pub fn opt_f64(parent_node: &Node, tag_name: &str) -> Result<Option<f64>> {
     opt_num(parent_node, tag_name, "Float")
 }
 
+#[cfg(feature = "read")]
 pub fn req_f64(parent_node: &Node, tag_name: &str) -> Result<f64> {
     let double = opt_num(parent_node, tag_name, "Float")?;
-    double.invalid_err(format!("XML tag '{tag_name}' was not found"))
+    let result = double.invalid_err(format!("XML tag '{tag_name}' was not found"));
+    with_path(parent_node, tag_name, result)
 }
 
+#[cfg(feature = "read")]
 pub fn opt_int<T: FromStr + Sync + Send>(parent_node: &Node, tag_name: &str) -> Result<Option<T>> {
     opt_num(parent_node, tag_name, "Integer")
 }
 
+#[cfg(feature = "read")]
 pub fn req_int<T: FromStr + Send + Sync>(parent_node: &Node, tag_name: &str) -> Result<T> {
     let integer = opt_num(parent_node, tag_name, "Integer")?;
-    integer.invalid_err(format!("XML tag '{tag_name}' was not found"))
+    let result = integer.invalid_err(format!("XML tag '{tag_name}' was not found"));
+    with_path(parent_node, tag_name, result)
 }
 
+#[cfg(feature = "read")]
 pub fn opt_date_time(parent_node: &Node, tag_name: &str) -> Result<Option<DateTime>> {
-    if let Some(tag) = parent_node.children().find(|n| n.has_tag_name(tag_name)) {
-        let expected_type = "Structure";
-        if let Some(found_type) = tag.attribute("type") {
-            if found_type != expected_type {
-                Error::invalid(format!(
-                    "Found XML tag '{tag_name}' with type '{found_type}' instead of '{expected_type}'"
-                ))?
-            }
-        } else {
-            Error::invalid(format!("XML tag '{tag_name}' has no 'type' attribute"))?
-        }
-        DateTime::from_node(&tag)
+    let tag = find_tag(parent_node, tag_name, "Structure");
+    let tag = with_path(parent_node, tag_name, tag)?;
+    if let Some(tag) = tag {
+        with_path(parent_node, tag_name, DateTime::from_node(&tag))
     } else {
         Ok(None)
     }
 }
 
+#[cfg(feature = "read")]
 pub fn opt_transform(parent_node: &Node, tag_name: &str) -> Result<Option<Transform>> {
     let node = parent_node.children().find(|n| n.has_tag_name(tag_name));
     if let Some(node) = node {
-        Ok(Some(Transform::from_node(&node)?))
+        let transform = with_path(parent_node, tag_name, Transform::from_node(&node))?;
+        Ok(Some(transform))
     } else {
         Ok(None)
     }
 }
 
+#[cfg(feature = "write")]
 pub fn gen_string<T: Display>(tag_name: &str, value: &T) -> String {
     format!("<{tag_name} type=\"String\"><![CDATA[{value}]]></{tag_name}>\n")
 }
 
+#[cfg(feature = "write")]
 pub fn gen_float<T: Display>(tag_name: &str, value: T) -> String {
     format!("<{tag_name} type=\"Float\">{value}</{tag_name}>\n")
 }
 
+#[cfg(feature = "write")]
 pub fn gen_int<T: Display>(tag_name: &str, value: T) -> String {
     format!("<{tag_name} type=\"Integer\">{value}</{tag_name}>\n")
 }
+
+#[cfg(feature = "write")]
+pub fn gen_custom(custom_xml: &[String]) -> String {
+    custom_xml.iter().map(|xml| format!("{xml}\n")).collect()
+}
+
+#[cfg(feature = "write")]
+pub fn gen_attributes(custom_attributes: &[String]) -> String {
+    custom_attributes
+        .iter()
+        .map(|attr| format!(" {attr}"))
+        .collect()
+}
+
+/// Formats an XML document with one element per line and two spaces of indentation per level.
+/// Character data is escaped instead of using CDATA sections, which does not change its meaning.
+/// Comments and processing instructions are not included.
+#[cfg(feature = "read")]
+pub fn pretty_print(xml: &str) -> Result<String> {
+    let document = Document::parse(xml).invalid_err("Failed to parse XML document")?;
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    pretty_print_element(&document.root_element(), 0, &mut output);
+    Ok(output)
+}
+
+#[cfg(feature = "read")]
+fn pretty_print_element(node: &Node, level: usize, output: &mut String) {
+    let indent = "  ".repeat(level);
+    let name = qualified_name(node, node.tag_name().namespace(), node.tag_name().name());
+    output.push_str(&format!("{indent}<{name}"));
+
+    // Namespaces are only declared on the element that introduces them
+    let inherited: Vec<(Option<&str>, &str)> = node
+        .parent_element()
+        .map(|p| p.namespaces().map(|ns| (ns.name(), ns.uri())).collect())
+        .unwrap_or_default();
+    for ns in node.namespaces() {
+        if !inherited.contains(&(ns.name(), ns.uri())) {
+            let uri = escape(ns.uri(), true);
+            match ns.name() {
+                Some(prefix) => output.push_str(&format!(" xmlns:{prefix}=\"{uri}\"")),
+                None => output.push_str(&format!(" xmlns=\"{uri}\"")),
+            }
+        }
+    }
+    for attr in node.attributes() {
+        let attr_name = qualified_name(node, attr.namespace(), attr.name());
+        let value = escape(attr.value(), true);
+        output.push_str(&format!(" {attr_name}=\"{value}\""));
+    }
+
+    let elements = node.children().filter(|n| n.is_element()).count();
+    let text: String = node
+        .children()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    let text = if elements > 0 { text.trim() } else { &text };
+    if elements == 0 && text.is_empty() {
+        output.push_str("/>\n");
+    } else if elements == 0 {
+        output.push_str(&format!(">{}</{name}>\n", escape(text, false)));
+    } else {
+        output.push_str(">\n");
+        if !text.is_empty() {
+            output.push_str(&format!("{indent}  {}\n", escape(text, false)));
+        }
+        for child in node.children().filter(|n| n.is_element()) {
+            pretty_print_element(&child, level + 1, output);
+        }
+        output.push_str(&format!("{indent}</{name}>\n"));
+    }
+}
+
+/// Returns the tag or attribute name including the prefix of its namespace.
+#[cfg(feature = "read")]
+fn qualified_name(node: &Node, namespace: Option<&str>, name: &str) -> String {
+    match namespace.and_then(|uri| node.lookup_prefix(uri)) {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix}:{name}"),
+        _ => name.to_owned(),
+    }
+}
+
+#[cfg(feature = "read")]
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_contains_xml_path() {
+        let xml = r#"<e57Root type="Structure"><data3D type="Vector"><vectorChild type="Structure"/><vectorChild type="Structure"><pose type="Structure"><rotation type="Structure"><w type="Integer">1</w></rotation></pose></vectorChild></data3D></e57Root>"#;
+        let document = Document::parse(xml).unwrap();
+        let pose = document
+            .descendants()
+            .find(|n| n.has_tag_name("pose"))
+            .unwrap();
+        assert_eq!(node_path(&pose), "/data3D/1/pose");
+
+        let error = opt_transform(&pose.parent().unwrap(), "pose").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Xml);
+        assert_eq!(error.xml_path(), Some("/data3D/1/pose/rotation/w"));
+
+        let root = document.root_element();
+        let error = req_string(&root, "guid").unwrap_err();
+        assert_eq!(error.xml_path(), Some("/guid"));
+    }
+
+    #[test]
+    fn pretty_print_document() {
+        let xml = r#"<?xml version="1.0"?><e57Root type="Structure" xmlns="http://www.astm.org/COMMIT/E57/2010-e57-v1.0" xmlns:ext="http://example.com"><guid type="String"><![CDATA[{a&b}]]></guid><ext:empty ext:flag="1"/><data3D type="Vector" allowHeterogeneousChildren="1"></data3D></e57Root>"#;
+        let pretty = pretty_print(xml).unwrap();
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<e57Root xmlns="http://www.astm.org/COMMIT/E57/2010-e57-v1.0" xmlns:ext="http://example.com" type="Structure">
+  <guid type="String">{a&amp;b}</guid>
+  <ext:empty ext:flag="1"/>
+  <data3D type="Vector" allowHeterogeneousChildren="1"/>
+</e57Root>
+"#;
+        assert_eq!(pretty, expected);
+        assert!(pretty_print("<broken>").is_err());
+    }
+}
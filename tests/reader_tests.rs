@@ -1,8 +1,12 @@
 use e57::{
-    CartesianCoordinate, E57Reader, ImageFormat, Point, Projection, RawValues, Record, RecordName,
-    RecordValue, Result, SphericalCoordinate,
+    downsample_voxel, CartesianCoordinate, CsvExporter, E57Reader, ErrorKind, ExportOptions,
+    Exporter, Header, ImageFormat, PlyExporter, Point, PointCloud, Projection, QuaternionAction,
+    QuaternionHandling, RawPacket, RawValues, ReaderOptions, Record, RecordName, RecordValue,
+    ResourceLimits, Result, SphericalCoordinate, SpillPolicy, Subsampling, VoxelPoint,
+    HISTOGRAM_BINS,
 };
 use std::fs::File;
+use std::io::{BufReader, Cursor};
 
 #[test]
 fn header() {
@@ -18,6 +22,31 @@ fn header() {
     assert_eq!(header.xml_length, 2172);
 }
 
+#[test]
+fn read_header() {
+    let file = File::open("testdata/bunnyDouble.e57").unwrap();
+    let header = E57Reader::read_header(file).unwrap();
+    assert_eq!(header.page_size, 1024);
+    assert_eq!(header.phys_xml_offset, 740736);
+    assert_eq!(header.xml_length, 2172);
+
+    let data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+    assert!(Header::has_signature(&data[..8]));
+    assert!(!Header::has_signature(b"ASTM"));
+    assert!(!Header::has_signature(b"PK\x03\x04 not an E57 file"));
+
+    let mut broken = data[..Header::SIZE].to_vec();
+    broken[0] = b'X';
+    let error = E57Reader::read_header(Cursor::new(broken)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Invalid);
+
+    let mut broken = data[..Header::SIZE].to_vec();
+    broken[40] = 0;
+    broken[41] = 8;
+    let error = E57Reader::read_header(Cursor::new(broken)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Unsupported);
+}
+
 #[test]
 fn validate_crc() {
     let file = File::open("testdata/bunnyDouble.e57").unwrap();
@@ -37,6 +66,12 @@ fn raw_xml() {
 
     assert_eq!(xml.len(), 2172);
     assert_eq!(xml.len(), header.xml_length as usize);
+
+    let mut limits = ResourceLimits::default();
+    limits.max_xml_size = 2000;
+    let reader = File::open("testdata/bunnyDouble.e57").unwrap();
+    let err = E57Reader::raw_xml_with_limits(reader, limits).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Limit);
 }
 
 #[test]
@@ -44,7 +79,7 @@ fn xml() {
     let reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
     let header = reader.header();
     let xml = reader.xml();
-    let xml_len = xml.as_bytes().len();
+    let xml_len = xml.len();
 
     assert_eq!(xml_len, 2172);
     assert_eq!(xml_len, header.xml_length as usize);
@@ -69,7 +104,7 @@ fn creation() {
     let reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
     let creation = reader.creation().unwrap();
     assert_eq!(creation.gps_time, 987369380.8049808);
-    assert_eq!(creation.atomic_reference, false);
+    assert!(!creation.atomic_reference);
 }
 
 #[test]
@@ -84,6 +119,7 @@ fn coord_metadata() {
     let reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
     let metadata = reader.coordinate_metadata();
     assert_eq!(metadata, Some(""));
+    assert!(reader.coordinate_reference_system().unwrap().is_none());
 }
 
 #[test]
@@ -239,6 +275,44 @@ fn iterator_size_hint() {
     assert_eq!(points.unwrap().len(), 2089);
 }
 
+#[test]
+fn iterator_stops_after_error() {
+    // Corrupt a byte in the middle of the binary point data to trigger a CRC error
+    let mut data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+    data[100_000] ^= 0xFF;
+    let mut reader = E57Reader::new(Cursor::new(data)).unwrap();
+    let pcs = reader.pointclouds();
+    let pc = pcs.first().unwrap();
+
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    let ok = iter.by_ref().take_while(|p| p.is_ok()).count();
+    assert!(ok < pc.records as usize);
+    assert!(iter.next().is_none());
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+
+    let mut iter = reader.pointcloud_raw(pc).unwrap();
+    let errors = iter.by_ref().filter(|p| p.is_err()).count();
+    assert_eq!(errors, 1);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn error_kind_and_context() {
+    // Corrupt a byte in the binary point data to trigger a CRC error
+    let mut data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+    data[100_000] ^= 0xFF;
+    let mut reader = E57Reader::new(Cursor::new(data)).unwrap();
+    let pcs = reader.pointclouds();
+    let pc = pcs.first().unwrap();
+    let iter = reader.pointcloud_raw(pc).unwrap();
+    let error = iter.filter_map(|p| p.err()).next().unwrap();
+    assert_eq!(error.kind(), ErrorKind::Checksum);
+    assert_eq!(error.checksum_page(), Some(97));
+    assert_eq!(error.physical_offset(), Some(97 * 1024));
+    assert!(error.packet_index().is_some());
+    assert!(error.logical_offset().is_some());
+}
+
 #[test]
 fn empty_e57_file() {
     let file = "testdata/empty.e57";
@@ -373,6 +447,7 @@ fn spherical_coordinates() {
             elevation: 0.0
         }
     );
+    #[allow(clippy::approx_constant)]
     let angle = 359.0 * (3.14 / 360.0);
     assert_eq!(
         points[359].spherical,
@@ -565,6 +640,21 @@ fn integer_intensity() {
         assert_eq!(p[0].intensity.unwrap(), 0.0);
         assert_eq!(p[1].intensity.unwrap(), 1.0);
 
+        let limits = pc.intensity_limits.clone().unwrap();
+        assert!(matches!(
+            limits.intensity_min,
+            Some(RecordValue::Integer(-66))
+        ));
+        assert!(matches!(
+            limits.intensity_max,
+            Some(RecordValue::Integer(66))
+        ));
+        let mut iter = e57.pointcloud_simple(&pc).unwrap();
+        iter.use_intensity_limits(true);
+        let p: Vec<Point> = iter.collect::<Result<_>>().unwrap();
+        assert_eq!(p[0].intensity.unwrap(), 0.0);
+        assert_eq!(p[1].intensity.unwrap(), 1.0);
+
         // Order of raw values: X, Y, Z, I, CIS
         let iter = e57.pointcloud_raw(&pc).unwrap();
         let points: Result<Vec<RawValues>> = iter.collect();
@@ -630,3 +720,510 @@ fn scaled_integer_intensity() {
         assert_eq!(p[1][4].to_i64(&proto[4].data_type).unwrap(), 0);
     }
 }
+
+#[test]
+fn single_precision_iterator() {
+    let file = "testdata/bunnyDouble.e57";
+    let mut reader = E57Reader::from_file(file).unwrap();
+    let pcs = reader.pointclouds();
+    let pc = pcs.first().unwrap();
+
+    let points: Result<Vec<Point>> = reader.pointcloud_simple(pc).unwrap().collect();
+    let points = points.unwrap();
+
+    let iter = reader.pointcloud_simple(pc).unwrap().single_precision();
+    assert_eq!(iter.size_hint(), (30571, Some(30571)));
+    let single: Result<Vec<Point<f32>>> = iter.collect();
+    let single = single.unwrap();
+    assert_eq!(single.len(), points.len());
+
+    for (d, s) in points.iter().zip(single.iter()) {
+        match (&d.cartesian, &s.cartesian) {
+            (
+                CartesianCoordinate::Valid { x, y, z },
+                CartesianCoordinate::Valid {
+                    x: sx,
+                    y: sy,
+                    z: sz,
+                },
+            ) => {
+                assert_eq!(*x as f32, *sx);
+                assert_eq!(*y as f32, *sy);
+                assert_eq!(*z as f32, *sz);
+            }
+            _ => panic!("Expected two valid Cartesian coordinates"),
+        }
+    }
+}
+
+#[test]
+fn subsampling() {
+    let file = "testdata/bunnyDouble.e57";
+    let mut reader = E57Reader::from_file(file).unwrap();
+    let pcs = reader.pointclouds();
+    let pc = pcs.first().unwrap();
+    let all: Result<Vec<Point>> = reader.pointcloud_simple(pc).unwrap().collect();
+    let all = all.unwrap();
+
+    // Every Nth point
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    iter.subsampling(Subsampling::EveryNth(100)).unwrap();
+    assert_eq!(iter.size_hint(), (306, Some(306)));
+    let points: Result<Vec<Point>> = iter.collect();
+    let points = points.unwrap();
+    assert_eq!(points.len(), 306);
+    for (i, p) in points.iter().enumerate() {
+        assert_eq!(p.cartesian, all[i * 100].cartesian);
+    }
+
+    // Target count
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    iter.subsampling(Subsampling::TargetCount(1000)).unwrap();
+    assert_eq!(iter.size_hint(), (1000, Some(1000)));
+    let points: Result<Vec<Point>> = iter.collect();
+    assert_eq!(points.unwrap().len(), 1000);
+
+    // Random selection is reproducible with the same seed
+    let mode = Subsampling::Random {
+        probability: 0.1,
+        seed: 1337,
+    };
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    iter.subsampling(mode.clone()).unwrap();
+    assert_eq!(iter.size_hint(), (0, Some(30571)));
+    let first: Result<Vec<Point>> = iter.collect();
+    let first = first.unwrap();
+    assert!(first.len() > 2500 && first.len() < 3500);
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    iter.subsampling(mode).unwrap();
+    let second: Result<Vec<Point>> = iter.collect();
+    let second = second.unwrap();
+    assert_eq!(first.len(), second.len());
+    for (a, b) in first.iter().zip(second.iter()) {
+        assert_eq!(a.cartesian, b.cartesian);
+    }
+
+    // Invalid settings are rejected
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    assert!(iter.subsampling(Subsampling::EveryNth(0)).is_err());
+}
+
+#[test]
+fn voxel_downsampling() {
+    let file = "testdata/bunnyDouble.e57";
+    let mut reader = E57Reader::from_file(file).unwrap();
+    let pcs = reader.pointclouds();
+    let pc = pcs.first().unwrap();
+
+    let iter = reader.pointcloud_simple(pc).unwrap();
+    let first = downsample_voxel(iter, 0.01, VoxelPoint::First).unwrap();
+    assert!(!first.is_empty());
+    assert!(first.len() < pc.records as usize);
+
+    let iter = reader.pointcloud_simple(pc).unwrap();
+    let centroids = downsample_voxel(iter, 0.01, VoxelPoint::Centroid).unwrap();
+    assert_eq!(first.len(), centroids.len());
+
+    // Huge voxels around the origin contain the whole point cloud
+    let iter = reader.pointcloud_simple(pc).unwrap();
+    let huge = downsample_voxel(iter, 1000.0, VoxelPoint::Centroid).unwrap();
+    assert!(!huge.is_empty() && huge.len() <= 8);
+}
+
+#[test]
+fn pointcloud_statistics() {
+    let file = "testdata/tinyCartesianFloatRgb.e57";
+    let mut reader = E57Reader::from_file(file).unwrap();
+    let pcs = reader.pointclouds();
+    let pc = pcs.first().unwrap();
+    let stats = reader.pointcloud_statistics(pc).unwrap();
+    assert_eq!(stats.points, 2090);
+    assert_eq!(stats.invalid_cartesian, 0);
+    assert_eq!(stats.invalid_spherical, 2090);
+    assert_eq!(stats.invalid_intensity, 2090);
+    assert!(stats.spherical_bounds.is_none());
+    assert!(stats.intensity_histogram.is_none());
+
+    // Compare bounds with the values calculated from the simple iterator
+    let mut iter = reader.pointcloud_simple(pc).unwrap();
+    iter.apply_pose(false);
+    let (mut x_min, mut x_max) = (f64::MAX, f64::MIN);
+    let (mut z_min, mut z_max) = (f64::MAX, f64::MIN);
+    for p in iter {
+        if let CartesianCoordinate::Valid { x, z, .. } = p.unwrap().cartesian {
+            x_min = x_min.min(x);
+            x_max = x_max.max(x);
+            z_min = z_min.min(z);
+            z_max = z_max.max(z);
+        }
+    }
+    let bounds = stats.cartesian_bounds.unwrap();
+    assert_eq!(bounds.x_min, Some(x_min));
+    assert_eq!(bounds.x_max, Some(x_max));
+    assert_eq!(bounds.z_min, Some(z_min));
+    assert_eq!(bounds.z_max, Some(z_max));
+
+    // All valid colors are counted in the histograms
+    let histograms = stats.color_histograms.unwrap();
+    for histogram in histograms {
+        assert_eq!(histogram.len(), HISTOGRAM_BINS);
+        let sum: u64 = histogram.iter().sum();
+        assert_eq!(sum, stats.points - stats.invalid_color);
+    }
+}
+
+#[test]
+fn quaternion_handling() {
+    let path = "testdata/tiny_pc_and_images.e57";
+
+    // Raw mode is the default and keeps the quaternions unchanged
+    let reader = E57Reader::from_file(path).unwrap();
+    assert!(reader.pose_corrections().is_empty());
+
+    let mut options = ReaderOptions::default();
+    options.quaternions = QuaternionHandling::Normalize { tolerance: 1e-6 };
+    let file = File::open(path).unwrap();
+    let reader = E57Reader::with_options(BufReader::new(file), options).unwrap();
+    let corrections = reader.pose_corrections();
+    assert_eq!(corrections.len(), 1);
+    assert_eq!(corrections[0].path, "/images2D/1/pose");
+    assert_eq!(corrections[0].action, QuaternionAction::Normalized);
+    assert_eq!(corrections[0].original.w, 0.5);
+    let images = reader.images();
+    let rotation = &images[1].transform.as_ref().unwrap().rotation;
+    assert!((rotation.norm() - 1.0).abs() < 1e-12);
+
+    let mut options = ReaderOptions::default();
+    options.quaternions = QuaternionHandling::Strict { tolerance: 1e-6 };
+    let file = File::open(path).unwrap();
+    assert!(E57Reader::with_options(BufReader::new(file), options).is_err());
+}
+
+#[test]
+fn resource_limits() {
+    let path = "testdata/bunnyDouble.e57";
+    let open = |options: ReaderOptions| {
+        let file = File::open(path).unwrap();
+        E57Reader::with_options(BufReader::new(file), options)
+    };
+    let read_all = |options: ReaderOptions| {
+        let mut reader = open(options).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        let result = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>();
+        result.map(|points| points.len())
+    };
+
+    let mut options = ReaderOptions::default();
+    options.limits.max_xml_size = 100;
+    assert_eq!(open(options).err().unwrap().kind(), ErrorKind::Limit);
+
+    let mut options = ReaderOptions::default();
+    options.limits.max_prototype_len = 2;
+    assert_eq!(open(options).err().unwrap().kind(), ErrorKind::Limit);
+
+    let mut options = ReaderOptions::default();
+    options.limits.max_packet_count = 1;
+    assert_eq!(read_all(options).unwrap_err().kind(), ErrorKind::Limit);
+
+    let mut options = ReaderOptions::default();
+    options.limits.max_bytestream_buffer = 1024;
+    assert_eq!(read_all(options).unwrap_err().kind(), ErrorKind::Limit);
+
+    assert_eq!(read_all(ReaderOptions::default()).unwrap(), 30571);
+}
+
+#[test]
+fn checksum_verification_toggle() {
+    let mut data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+    data[100_000] ^= 0xFF;
+
+    let mut options = ReaderOptions::default();
+    options.verify_checksums = false;
+    let mut reader = E57Reader::with_options(Cursor::new(data.clone()), options).unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let points = reader.pointcloud_raw(&pc).unwrap();
+    assert_eq!(points.filter(|p| p.is_ok()).count(), 30571);
+    let error = reader.verify_checksums().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Checksum);
+    assert_eq!(error.checksum_page(), Some(97));
+
+    let mut reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
+    assert_eq!(reader.verify_checksums().unwrap(), 726);
+}
+
+#[test]
+fn raw_packets() {
+    let mut reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let packets = reader
+        .pointcloud_packets(&pc)
+        .unwrap()
+        .collect::<Result<Vec<RawPacket>>>()
+        .unwrap();
+    assert!(packets.len() > 1);
+
+    let mut bytes = vec![0_usize; pc.prototype.len()];
+    for packet in &packets {
+        match packet {
+            RawPacket::Data {
+                packet_length,
+                bytestreams,
+                ..
+            } => {
+                assert_eq!(bytestreams.len(), pc.prototype.len());
+                assert_eq!(packet_length % 4, 0);
+                for (sum, stream) in bytes.iter_mut().zip(bytestreams) {
+                    *sum += stream.len();
+                }
+            }
+            _ => panic!("Expected only data packets"),
+        }
+    }
+    // Coordinates are stored as double values
+    assert_eq!(bytes[0], 30571 * 8);
+
+    // Byte streams that do not fit into the packet are detected
+    let RawPacket::Data {
+        physical_offset, ..
+    } = packets[0]
+    else {
+        panic!("Expected data packet");
+    };
+    let mut data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+    let size_offset = physical_offset as usize + 6;
+    data[size_offset..size_offset + 2].copy_from_slice(&u16::MAX.to_le_bytes());
+    let mut options = ReaderOptions::default();
+    options.verify_checksums = false;
+    let mut reader = E57Reader::with_options(Cursor::new(data), options).unwrap();
+    let err = reader
+        .pointcloud_packets(&pc)
+        .unwrap()
+        .collect::<Result<Vec<RawPacket>>>()
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Format);
+}
+
+#[test]
+fn exporters() {
+    let mut reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
+
+    let mut csv = Vec::new();
+    let mut exporter = CsvExporter::new(&mut csv);
+    exporter.set_separator(';');
+    assert_eq!(reader.export(&mut exporter).unwrap(), 30571);
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 30571);
+    assert_eq!(csv.lines().next().unwrap().split(';').count(), 3);
+
+    let mut ply = Cursor::new(Vec::new());
+    let mut exporter = PlyExporter::new(&mut ply, false);
+    assert_eq!(reader.export(&mut exporter).unwrap(), 30571);
+    let ply = ply.into_inner();
+    let header = "ply\nformat binary_little_endian 1.0\nelement vertex 00000000000000030571\n";
+    assert!(ply.starts_with(header.as_bytes()));
+    let end = b"end_header\n";
+    let pos = ply.windows(end.len()).position(|w| w == end).unwrap();
+    assert_eq!(ply.len() - pos - end.len(), 30571 * 24);
+
+    struct SkipAll;
+    impl Exporter for SkipAll {
+        fn begin_pointcloud(&mut self, _: &PointCloud) -> Result<Option<ExportOptions>> {
+            Ok(None)
+        }
+        fn add_point(&mut self, _: &Point) -> Result<()> {
+            panic!("No points expected")
+        }
+        fn finish(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+    assert_eq!(reader.export(&mut SkipAll).unwrap(), 0);
+}
+
+#[test]
+fn resource_estimate() {
+    let reader = E57Reader::from_file("testdata/bunnyInt19.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let estimate = pc.resource_estimate();
+    assert_eq!(estimate.points, 30571);
+    assert_eq!(estimate.records.len(), pc.prototype.len());
+    let bits: Vec<u64> = estimate.records.iter().map(|r| r.bit_size).collect();
+    assert_eq!(bits, [19, 19, 19, 1]);
+    assert_eq!(estimate.bits_per_point, 58);
+    assert_eq!(estimate.encoded_bytes, (30571 * 58_u64).div_ceil(8));
+    assert_eq!(estimate.columns_f64_bytes, 30571 * 4 * 8);
+    assert_eq!(estimate.columns_f32_bytes, 30571 * 4 * 4);
+    assert!(estimate.simple_f32_bytes < estimate.simple_f64_bytes);
+    assert!(estimate.raw_bytes > estimate.columns_f64_bytes);
+}
+
+#[test]
+fn pages_per_read() {
+    let mut options = ReaderOptions::default();
+    options.pages_per_read = 0;
+    let file = File::open("testdata/bunnyDouble.e57").unwrap();
+    assert!(E57Reader::with_options(BufReader::new(file), options).is_err());
+
+    // Corrupt page in the middle of a batch of fetched pages
+    let mut data = std::fs::read("testdata/bunnyDouble.e57").unwrap();
+    data[100_000] ^= 0xFF;
+    let mut options = ReaderOptions::default();
+    options.pages_per_read = 64;
+    let mut reader = E57Reader::with_options(Cursor::new(data), options.clone()).unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let iter = reader.pointcloud_raw(&pc).unwrap();
+    let error = iter.filter_map(|p| p.err()).next().unwrap();
+    assert_eq!(error.kind(), ErrorKind::Checksum);
+    assert_eq!(error.checksum_page(), Some(97));
+
+    let file = File::open("testdata/bunnyDouble.e57").unwrap();
+    let mut reader = E57Reader::with_options(BufReader::new(file), options).unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let points = reader.pointcloud_simple(&pc).unwrap();
+    assert_eq!(points.filter(|p| p.is_ok()).count(), 30571);
+    assert_eq!(reader.verify_checksums().unwrap(), 726);
+}
+
+#[test]
+fn raw_iterator_next_into() {
+    let mut reader = E57Reader::from_file("testdata/bunnyInt19.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let expected = reader
+        .pointcloud_raw(&pc)
+        .unwrap()
+        .collect::<Result<Vec<RawValues>>>()
+        .unwrap();
+
+    let mut iter = reader.pointcloud_raw(&pc).unwrap();
+    let mut values = RawValues::new();
+    let mut count = 0;
+    while let Some(result) = iter.next_into(&mut values) {
+        result.unwrap();
+        let e = &expected[count];
+        assert_eq!(values.len(), e.len());
+        assert!(values.iter().zip(e).all(|(a, b)| a == b));
+        count += 1;
+    }
+    assert_eq!(count, expected.len());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn pointcloud_lookup() {
+    let mut reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
+    let guid = "{9CA24C38-C93E-40E8-A366-F49977C7E3EB}";
+    let pc = reader.pointcloud_by_guid(guid).unwrap();
+    assert_eq!(pc.guid.as_deref(), Some(guid));
+    let by_name = reader.pointcloud_by_name("bunny").unwrap();
+    assert_eq!(by_name.guid.as_deref(), Some(guid));
+    assert!(reader.pointcloud_by_guid("unknown").is_none());
+    assert!(reader.pointcloud_by_name("unknown").is_none());
+
+    let points = reader.pointcloud_simple(&pc).unwrap().count();
+    assert_eq!(points as u64, pc.records);
+}
+
+#[test]
+fn xml_pretty_and_structure() {
+    let mut reader = E57Reader::from_file("testdata/tiny_pc_and_images.e57").unwrap();
+    let pretty = reader.xml_pretty().unwrap();
+    assert!(pretty.starts_with("<?xml"));
+    assert!(pretty.contains("\n  <guid type=\"String\">"));
+    assert!(pretty.lines().all(|l| l.trim_start().starts_with('<')));
+
+    let structure = reader.structure().unwrap();
+    let pc = reader.pointclouds().remove(0);
+    assert!(structure.starts_with(&format!("E57 file {}\n", reader.guid())));
+    assert!(structure.contains(&format!(
+        "Compressed vector section: offset {}",
+        pc.file_offset
+    )));
+    assert!(structure.contains("0: cartesianX"));
+    assert_eq!(
+        structure
+            .lines()
+            .filter(|l| l.starts_with("Image "))
+            .count(),
+        reader.images().len()
+    );
+}
+
+#[test]
+fn pointcloud_preview() {
+    let mut reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let preview = reader.pointcloud_preview(&pc, 1000).unwrap();
+    assert_eq!(preview.len(), 1000);
+    assert_eq!(preview.y.len(), 1000);
+    assert_eq!(preview.z.len(), 1000);
+    assert!(!preview.has_color());
+
+    let mut reader = E57Reader::from_file("testdata/tinyCartesianFloatRgb.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let preview = reader.pointcloud_preview(&pc, pc.records * 2).unwrap();
+    assert_eq!(preview.len() as u64, pc.records);
+    assert!(preview.has_color());
+    assert_eq!(preview.red.len(), preview.len());
+    let first = reader
+        .pointcloud_simple(&pc)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(preview.red[0], first.color.unwrap().to_rgb8().0);
+}
+
+#[test]
+fn read_chunks() {
+    let mut reader = E57Reader::from_file("testdata/bunnyDouble.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let iter = reader.pointcloud_simple(&pc).unwrap();
+    assert!(iter.read_chunks(0).is_err());
+
+    let chunks = reader
+        .pointcloud_simple(&pc)
+        .unwrap()
+        .read_chunks(10000)
+        .unwrap();
+    let expected = (pc.records as usize).div_ceil(10000);
+    assert_eq!(chunks.size_hint(), (expected, Some(expected)));
+    let chunks = chunks.collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(chunks.len(), expected);
+    assert!(chunks[..expected - 1].iter().all(|c| c.len() == 10000));
+    let total: usize = chunks.iter().map(|c| c.len()).sum();
+    assert_eq!(total as u64, pc.records);
+
+    let mut iter = reader.pointcloud_simple(&pc).unwrap().single_precision();
+    let mut buffer = Vec::new();
+    assert_eq!(iter.read_chunk(&mut buffer, 5).unwrap(), 5);
+    assert_eq!(iter.read_chunk(&mut buffer, 3).unwrap(), 3);
+    assert_eq!(buffer.len(), 8);
+}
+
+#[test]
+fn read_from_stream() {
+    let bytes = std::fs::read("testdata/tinyCartesianFloatRgb.e57").unwrap();
+    let policy = SpillPolicy::TempFile {
+        memory_limit: 1024,
+        directory: None,
+    };
+    let mut reader = E57Reader::from_stream(bytes.as_slice(), policy).unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let streamed = reader
+        .pointcloud_simple(&pc)
+        .unwrap()
+        .collect::<Result<Vec<Point>>>()
+        .unwrap();
+
+    let mut reader = E57Reader::from_file("testdata/tinyCartesianFloatRgb.e57").unwrap();
+    let points = reader
+        .pointcloud_simple(&pc)
+        .unwrap()
+        .collect::<Result<Vec<Point>>>()
+        .unwrap();
+    assert_eq!(streamed.len(), points.len());
+    assert_eq!(streamed[7].cartesian, points[7].cartesian);
+}
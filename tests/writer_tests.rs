@@ -1,11 +1,15 @@
 use e57::{
-    CartesianCoordinate, DateTime, E57Reader, E57Writer, Extension, ImageFormat, Point, Projection,
-    Quaternion, RawValues, Record, RecordDataType, RecordName, RecordValue, Result,
-    SphericalImageProperties, Transform, Translation, VisualReferenceImageProperties,
+    diff, scan_poses, Blob, CartesianBounds, CartesianCoordinate, Color,
+    CylindricalImageProperties, DateTime, DiffOptions, DuplicateRemoval, E57Reader, E57Writer,
+    Extension, ExtensionDecoder, ExtensionDefinition, ExtensionRecordDefinition, ExtensionValue,
+    ExtensionValueKind, Image, ImageFormat, LineGroup, MortonOrder, Point, Projection, Quaternion,
+    RawPacket, RawValues, ReaderOptions, Record, RecordDataType, RecordName, RecordValue,
+    RegistrationFrame, Result, SphericalCoordinate, SphericalImageProperties, Subsampling,
+    Transform, Translation, VisualReferenceImageProperties,
 };
 use std::f32::consts::PI;
 use std::fs::{remove_file, File};
-use std::io::Seek;
+use std::io::{BufReader, Cursor, Seek};
 use std::path::Path;
 
 #[test]
@@ -112,6 +116,14 @@ fn write_read_cycle_image() {
     img_writer
         .add_spherical(ImageFormat::Jpeg, &mut reader, props, None)
         .unwrap();
+    reader.rewind().unwrap();
+    let props = VisualReferenceImageProperties {
+        width: 100,
+        height: 100,
+    };
+    assert!(img_writer
+        .add_visual_reference(ImageFormat::Jpeg, &mut reader, props, None)
+        .is_err());
     img_writer.finalize().unwrap();
     e57_writer.finalize().unwrap();
     drop(e57_writer);
@@ -134,6 +146,11 @@ fn write_read_cycle_image() {
     assert_eq!(img.sensor_model.unwrap(), "model");
     assert_eq!(img.sensor_serial.unwrap(), "serial");
     assert_eq!(img.sensor_vendor.unwrap(), "vendor");
+    let transform = img.transform.unwrap();
+    assert_eq!(transform.rotation.w, 2.2);
+    assert_eq!(transform.rotation.z, 5.5);
+    assert_eq!(transform.translation.x, 6.6);
+    assert_eq!(transform.translation.z, 8.8);
 
     let vis_ref = img.visual_reference.unwrap();
     assert_eq!(vis_ref.properties.width, 100);
@@ -458,10 +475,11 @@ fn spherical_coordinates() {
 
         let incr = (2.0 * PI) / 99.0;
         for i in 0..100 {
-            let mut values = RawValues::with_capacity(3);
-            values.push(RecordValue::Single(incr * i as f32));
-            values.push(RecordValue::Single(PI));
-            values.push(RecordValue::Single(1.0));
+            let values = vec![
+                RecordValue::Single(incr * i as f32),
+                RecordValue::Single(PI),
+                RecordValue::Single(1.0),
+            ];
             pc_writer.add_point(values).unwrap();
         }
 
@@ -606,7 +624,7 @@ fn write_read_meta_data() {
         let e57_reader = E57Reader::from_file(out_path).unwrap();
         let creation = e57_reader.creation().unwrap();
         assert_eq!(creation.gps_time, 12.34);
-        assert_eq!(creation.atomic_reference, true);
+        assert!(creation.atomic_reference);
         assert_eq!(e57_reader.coordinate_metadata(), Some("coord meta"));
         let library_version = e57_reader.library_version().unwrap();
         assert!(library_version.contains("Rust E57 Library"));
@@ -625,10 +643,10 @@ fn write_read_meta_data() {
         assert_eq!(pc.original_guids, Some(guids));
         let start = pc.acquisition_start.unwrap();
         assert_eq!(start.gps_time, 0.0);
-        assert_eq!(start.atomic_reference, false);
+        assert!(!start.atomic_reference);
         let end = pc.acquisition_end.unwrap();
         assert_eq!(end.gps_time, 1.23);
-        assert_eq!(start.atomic_reference, false);
+        assert!(!start.atomic_reference);
         assert_eq!(pc.temperature, Some(23.0));
         assert_eq!(pc.humidity, Some(66.6));
         assert_eq!(pc.atmospheric_pressure, Some(1337.0));
@@ -886,3 +904,2503 @@ fn write_read_int_min_max_equal() {
 
     remove_file(path).unwrap();
 }
+
+#[test]
+fn write_read_line_groups() {
+    let path = Path::new("write_read_line_groups.e57");
+
+    // Many lines require multiple data packets for the groups
+    let lines = 1000;
+    let points_per_line = 2;
+    let mut e57 = E57Writer::from_file(path, "guid_file").unwrap();
+    let prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+        Record {
+            name: RecordName::ColumnIndex,
+            data_type: RecordDataType::Integer {
+                min: 0,
+                max: lines - 1,
+            },
+        },
+    ];
+    let mut pc_writer = e57.add_pointcloud("guid_pointcloud", prototype).unwrap();
+    let mut groups = Vec::new();
+    for line in 0..lines {
+        for i in 0..points_per_line {
+            pc_writer
+                .add_point(vec![
+                    RecordValue::Double(line as f64),
+                    RecordValue::Double(i as f64),
+                    RecordValue::Double(0.0),
+                    RecordValue::Integer(line),
+                ])
+                .unwrap();
+        }
+        groups.push(LineGroup {
+            id_element_value: line,
+            start_point_index: line * points_per_line,
+            point_count: points_per_line,
+            cartesian_bounds: Some(CartesianBounds {
+                x_min: Some(line as f64),
+                x_max: Some(line as f64),
+                y_min: Some(0.0),
+                y_max: Some((points_per_line - 1) as f64),
+                z_min: Some(0.0),
+                z_max: Some(0.0),
+            }),
+            spherical_bounds: None,
+        });
+    }
+    assert!(pc_writer
+        .set_line_groups("rowIndex", groups.clone())
+        .is_err());
+    pc_writer.set_line_groups("columnIndex", groups).unwrap();
+    pc_writer.finalize().unwrap();
+    e57.finalize().unwrap();
+    drop(e57);
+
+    let mut e57 = E57Reader::from_file(path).unwrap();
+    let pc = e57.pointclouds().first().unwrap().clone();
+    let grouping = pc.line_grouping.as_ref().unwrap();
+    assert_eq!(grouping.id_element_name, "columnIndex");
+    assert_eq!(grouping.records, lines as u64);
+    let groups = e57.line_groups(&pc).unwrap();
+    assert_eq!(groups.len(), lines as usize);
+    for (i, g) in groups.iter().enumerate() {
+        let line = i as i64;
+        assert_eq!(g.id_element_value, line);
+        assert_eq!(g.start_point_index, line * points_per_line);
+        assert_eq!(g.point_count, points_per_line);
+        let bounds = g.cartesian_bounds.as_ref().unwrap();
+        assert_eq!(bounds.x_min, Some(line as f64));
+        assert_eq!(bounds.y_max, Some((points_per_line - 1) as f64));
+        assert!(g.spherical_bounds.is_none());
+    }
+
+    // Points are still readable
+    let points: Result<Vec<Point>> = e57.pointcloud_simple(&pc).unwrap().collect();
+    assert_eq!(points.unwrap().len(), (lines * points_per_line) as usize);
+
+    // A wrong group count from the XML must not cause huge allocations
+    let mut broken = pc.clone();
+    broken.line_grouping.as_mut().unwrap().records = u64::MAX;
+    assert!(e57.line_groups(&broken).is_err());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn write_read_custom_blob() {
+    let path = Path::new("write_read_custom_blob.e57");
+    let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+
+    let mut writer = E57Writer::from_file(path, "guid_file").unwrap();
+    let blob = writer.add_blob(&mut data.as_slice()).unwrap();
+    assert_eq!(blob.length, data.len() as u64);
+
+    // Custom XML requires a registered extension namespace
+    let xml = blob.xml_string("traj:trajectory");
+    assert!(writer.add_custom_xml(&xml).is_err());
+    assert!(writer.add_custom_xml("<unknown/>").is_err());
+    let ext = Extension::new("traj", "https://example.com/trajectory");
+    writer.register_extesion(ext).unwrap();
+    assert!(writer.add_custom_xml("<traj:broken>").is_err());
+    writer.add_custom_xml(&xml).unwrap();
+    writer.finalize().unwrap();
+    drop(writer);
+
+    // Find blob referenced by custom XML element
+    let mut reader = E57Reader::from_file(path).unwrap();
+    let custom_xml = reader.custom_xml().to_vec();
+    assert!(reader
+        .custom_blob(&custom_xml, "traj:other")
+        .unwrap()
+        .is_none());
+    assert!(reader
+        .custom_blob(&custom_xml, "ven:trajectory")
+        .unwrap()
+        .is_none());
+    assert!(reader.custom_blob(&custom_xml, "trajectory").is_err());
+    let blob = reader
+        .custom_blob(&custom_xml, "traj:trajectory")
+        .unwrap()
+        .unwrap();
+    assert_eq!(blob.length, data.len() as u64);
+
+    let mut read_data = Vec::new();
+    let size = reader.blob(&blob, &mut read_data).unwrap();
+    assert_eq!(size, data.len() as u64);
+    assert_eq!(read_data, data);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn image_only_file() {
+    let jpeg = std::fs::read("testdata/castle.jpg").unwrap();
+    let props = SphericalImageProperties {
+        width: 100,
+        height: 100,
+        pixel_width: 3.6,
+        pixel_height: 1.8,
+    };
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut file, "file_guid").unwrap();
+        for i in 0..2 {
+            let guid = format!("pano_{i}");
+            let mut img_writer = writer.add_image(guid.as_str()).unwrap();
+            img_writer.set_transform(Transform {
+                rotation: Quaternion::default(),
+                translation: Translation {
+                    x: i as f64,
+                    y: 0.0,
+                    z: 1.5,
+                },
+            });
+            img_writer
+                .add_spherical(ImageFormat::Jpeg, &mut jpeg.as_slice(), props.clone(), None)
+                .unwrap();
+            img_writer.finalize().unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    file.rewind().unwrap();
+    assert!(E57Reader::validate_crc(&mut file).is_ok());
+    file.rewind().unwrap();
+    let mut reader = E57Reader::new(file).unwrap();
+    assert!(reader.pointclouds().is_empty());
+    let images = reader.images();
+    assert_eq!(images.len(), 2);
+    for (i, img) in images.iter().enumerate() {
+        assert_eq!(img.guid.as_deref(), Some(format!("pano_{i}").as_str()));
+        assert!(img.pointcloud_guid.is_none());
+        let transform = img.transform.as_ref().unwrap();
+        assert_eq!(transform.translation.x, i as f64);
+        let Some(Projection::Spherical(rep)) = &img.projection else {
+            panic!("Expected spherical image");
+        };
+        let mut data = Vec::new();
+        reader.blob(&rep.blob.data, &mut data).unwrap();
+        assert_eq!(data, jpeg);
+    }
+}
+
+#[test]
+fn write_read_unit_scale() {
+    let path = Path::new("write_read_unit_scale.e57");
+    let feet = 0.3048;
+
+    {
+        let mut e57_writer = E57Writer::from_file(path, "guid_file").unwrap();
+        e57_writer.set_unit_scale(feet).unwrap();
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record {
+                name: RecordName::CartesianZ,
+                data_type: RecordDataType::ScaledInteger {
+                    min: -1000,
+                    max: 1000,
+                    scale: 0.01,
+                    offset: 0.0,
+                },
+            },
+        ];
+        let mut pc_writer = e57_writer.add_pointcloud("guid_pc", prototype).unwrap();
+        pc_writer.set_transform(Some(Transform {
+            translation: Translation {
+                x: 10.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            ..Default::default()
+        }));
+        for i in 0..10 {
+            let v = i as f64;
+            pc_writer
+                .add_point(vec![
+                    RecordValue::Double(v),
+                    RecordValue::Double(-v),
+                    RecordValue::ScaledInteger(i * 100),
+                ])
+                .unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        e57_writer.finalize().unwrap();
+    }
+
+    {
+        // File contains meters
+        let mut e57 = E57Reader::from_file(path).unwrap();
+        let pc = &e57.pointclouds()[0];
+        let bounds = pc.cartesian_bounds.as_ref().unwrap();
+        assert!((bounds.x_max.unwrap() - 9.0 * feet).abs() < 1e-9);
+        assert!((bounds.z_max.unwrap() - 9.0 * feet).abs() < 1e-9);
+        let translation = &pc.transform.as_ref().unwrap().translation;
+        assert!((translation.x - 10.0 * feet).abs() < 1e-9);
+        let mut reader = e57.pointcloud_simple(pc).unwrap();
+        reader.apply_pose(false);
+        let points: Vec<Point> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(
+            points[9].cartesian,
+            CartesianCoordinate::Valid {
+                x: 9.0 * feet,
+                y: -9.0 * feet,
+                z: 9.0 * feet
+            }
+        );
+    }
+
+    {
+        // Convert back to feet while reading
+        let mut options = ReaderOptions::default();
+        options.unit_scale = 1.0 / feet;
+        let file = File::open(path).unwrap();
+        let mut e57 = E57Reader::with_options(BufReader::new(file), options).unwrap();
+        let pc = &e57.pointclouds()[0];
+        let bounds = pc.cartesian_bounds.as_ref().unwrap();
+        assert!((bounds.y_min.unwrap() + 9.0).abs() < 1e-9);
+        let points: Vec<Point> = e57
+            .pointcloud_simple(pc)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        if let CartesianCoordinate::Valid { x, y, z } = points[9].cartesian {
+            assert!((x - 19.0).abs() < 1e-9);
+            assert!((y + 9.0).abs() < 1e-9);
+            assert!((z - 9.0).abs() < 1e-9);
+        } else {
+            panic!("Expected valid Cartesian coordinate");
+        }
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn invalid_unit_scale() {
+    let path = Path::new("invalid_unit_scale.e57");
+    {
+        let mut e57_writer = E57Writer::from_file(path, "guid_file").unwrap();
+        assert!(e57_writer.set_unit_scale(0.0).is_err());
+        assert!(e57_writer.set_unit_scale(f64::NAN).is_err());
+        e57_writer.set_unit_scale(2.0).unwrap();
+        let prototype = vec![
+            Record {
+                name: RecordName::CartesianX,
+                data_type: RecordDataType::Integer { min: 0, max: 10 },
+            },
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        assert!(e57_writer.add_pointcloud("guid_pc", prototype).is_err());
+        e57_writer.finalize().unwrap();
+    }
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn write_pointcloud_from_iter() {
+    let path = Path::new("write_pointcloud_from_iter.e57");
+    let points: Vec<Point> = (0..100)
+        .map(|i| Point {
+            cartesian: CartesianCoordinate::Valid {
+                x: i as f64,
+                y: 0.5,
+                z: -1.0,
+            },
+            spherical: SphericalCoordinate::Invalid,
+            color: Some(Color {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.2,
+            }),
+            intensity: if i % 2 == 0 { Some(0.5) } else { None },
+            row: 0,
+            column: i,
+        })
+        .collect();
+
+    {
+        let mut e57_writer = E57Writer::from_file(path, "guid_file").unwrap();
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+            Record::COLOR_RED_U8,
+            Record::COLOR_GREEN_U8,
+            Record::COLOR_BLUE_U8,
+            Record::INTENSITY_UNIT_F32,
+            Record {
+                name: RecordName::IsIntensityInvalid,
+                data_type: RecordDataType::Integer { min: 0, max: 1 },
+            },
+            Record {
+                name: RecordName::ColumnIndex,
+                data_type: RecordDataType::Integer { min: 0, max: 99 },
+            },
+        ];
+        let count = e57_writer
+            .write_pointcloud_from_iter("guid_pc", prototype, points.clone())
+            .unwrap();
+        assert_eq!(count, 100);
+
+        // Invalid intensities cannot be stored without the invalid state record
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+            Record::INTENSITY_UNIT_F32,
+        ];
+        let result = e57_writer.write_pointcloud_from_iter("guid_pc2", prototype, points.clone());
+        assert!(result.is_err());
+        e57_writer.finalize().unwrap();
+    }
+
+    {
+        let mut e57 = E57Reader::from_file(path).unwrap();
+        let pcs = e57.pointclouds();
+        assert_eq!(pcs.len(), 1);
+        let mut reader = e57.pointcloud_simple(&pcs[0]).unwrap();
+        reader.intensity_to_color(false);
+        let read: Vec<Point> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(read.len(), 100);
+        for (a, b) in read.iter().zip(points.iter()) {
+            assert_eq!(a.cartesian, b.cartesian);
+            assert_eq!(a.intensity, b.intensity);
+            assert_eq!(a.column, b.column);
+            let color = a.color.as_ref().unwrap();
+            assert_eq!(color.red, 1.0);
+            assert_eq!(color.green, 0.0);
+            assert!((color.blue - 0.2).abs() < 0.002);
+        }
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn abort_and_drop_point_cloud_writer() {
+    let path = Path::new("abort_and_drop_point_cloud_writer.e57");
+    let prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+    ];
+    let point = |i: u64| {
+        vec![
+            RecordValue::Double(i as f64),
+            RecordValue::Double(0.0),
+            RecordValue::Double(0.0),
+        ]
+    };
+
+    {
+        let mut e57_writer = E57Writer::from_file(path, "guid_file").unwrap();
+
+        // Explicitly aborted point cloud
+        let mut pc_writer = e57_writer
+            .add_pointcloud("aborted", prototype.clone())
+            .unwrap();
+        for i in 0..100_000 {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        pc_writer.abort().unwrap();
+        assert!(pc_writer.add_point(point(0)).is_err());
+        assert!(pc_writer.finalize().is_err());
+        drop(pc_writer);
+
+        // Dropped point cloud without finalize
+        let mut pc_writer = e57_writer
+            .add_pointcloud("dropped", prototype.clone())
+            .unwrap();
+        for i in 0..50_000 {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        drop(pc_writer);
+
+        // Finalized point cloud
+        let mut pc_writer = e57_writer.add_pointcloud("kept", prototype).unwrap();
+        for i in 0..10 {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        assert!(pc_writer.finalize().is_err());
+        drop(pc_writer);
+
+        e57_writer.finalize().unwrap();
+    }
+
+    {
+        let mut e57 = E57Reader::from_file(path).unwrap();
+        let pcs = e57.pointclouds();
+        assert_eq!(pcs.len(), 1);
+        assert_eq!(pcs[0].guid.as_deref(), Some("kept"));
+        let points: Vec<Point> = e57
+            .pointcloud_simple(&pcs[0])
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(points.len(), 10);
+    }
+    let file = File::open(path).unwrap();
+    assert!(E57Reader::validate_crc(file).is_ok());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn zero_bit_prototype() {
+    let path = Path::new("zero_bit_prototype.e57");
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        const CONSTANT: RecordDataType = RecordDataType::ScaledInteger {
+            min: 5,
+            max: 5,
+            scale: 0.5,
+            offset: 0.0,
+        };
+        let prototype = vec![
+            Record {
+                name: RecordName::CartesianX,
+                data_type: CONSTANT,
+            },
+            Record {
+                name: RecordName::CartesianY,
+                data_type: CONSTANT,
+            },
+            Record {
+                name: RecordName::CartesianZ,
+                data_type: CONSTANT,
+            },
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        for _ in 0..10 {
+            pc_writer
+                .add_point(vec![RecordValue::ScaledInteger(5); 3])
+                .unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        let points = reader
+            .pointcloud_simple(&pc)
+            .unwrap()
+            .collect::<Result<Vec<Point>>>()
+            .unwrap();
+        assert_eq!(points.len(), 10);
+        assert_eq!(
+            points[9].cartesian,
+            CartesianCoordinate::Valid {
+                x: 2.5,
+                y: 2.5,
+                z: 2.5
+            }
+        );
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn compressor_restart_flag() {
+    let path = Path::new("compressor_restart_flag.e57");
+    let count = 40000_i64;
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        // Bit size of 11 is not byte aligned and leaves incomplete bytes at the end of packets
+        const INT: RecordDataType = RecordDataType::ScaledInteger {
+            min: 0,
+            max: 2047,
+            scale: 0.001,
+            offset: 0.0,
+        };
+        let prototype = vec![
+            Record {
+                name: RecordName::CartesianX,
+                data_type: INT,
+            },
+            Record {
+                name: RecordName::CartesianY,
+                data_type: INT,
+            },
+            Record {
+                name: RecordName::CartesianZ,
+                data_type: INT,
+            },
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        pc_writer.set_compressor_restart_interval(1);
+        for i in 0..count {
+            let v = RecordValue::ScaledInteger(i % 2048);
+            pc_writer.add_point(vec![v.clone(), v.clone(), v]).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        let flags: Vec<bool> = reader
+            .pointcloud_packets(&pc)
+            .unwrap()
+            .map(|p| match p.unwrap() {
+                RawPacket::Data {
+                    comp_restart_flag, ..
+                } => comp_restart_flag,
+                _ => panic!("Expected only data packets"),
+            })
+            .collect();
+        assert!(flags.len() > 2);
+        assert!(!flags[0]);
+        assert!(flags[1..].iter().all(|f| *f));
+
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        assert_eq!(points.len(), count as usize);
+        for (i, p) in points.iter().enumerate() {
+            assert_eq!(p[0], RecordValue::ScaledInteger(i as i64 % 2048));
+        }
+    }
+
+    remove_file(path).unwrap();
+}
+
+/// Writes small integer records with the given compressor restart interval and compares all values.
+fn restart_round_trip(path: &Path, max: i64, extra_double: bool, count: usize, interval: u64) {
+    let int = RecordDataType::Integer { min: 0, max };
+    let record = |name| Record {
+        name,
+        data_type: int.clone(),
+    };
+    let mut prototype = vec![
+        record(RecordName::CartesianX),
+        record(RecordName::CartesianY),
+        record(RecordName::CartesianZ),
+    ];
+    if extra_double {
+        prototype.push(Record {
+            name: RecordName::Intensity,
+            data_type: RecordDataType::F64,
+        });
+    }
+    let point = |i: usize| {
+        let i = i as i64;
+        let mut values = vec![
+            RecordValue::Integer(i % (max + 1)),
+            RecordValue::Integer((i / 3) % (max + 1)),
+            RecordValue::Integer((i / 7) % (max + 1)),
+        ];
+        if extra_double {
+            values.push(RecordValue::Double(i as f64));
+        }
+        values
+    };
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        pc_writer.set_compressor_restart_interval(interval);
+        for i in 0..count {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        let mut iter = reader.pointcloud_raw(&pc).unwrap();
+        let mut values = Vec::new();
+        let mut read = 0;
+        while let Some(result) = iter.next_into(&mut values) {
+            result.unwrap();
+            assert_eq!(values, point(read), "Point {read} differs");
+            read += 1;
+        }
+        assert_eq!(read, count);
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn compressor_restart_small_bit_sizes() {
+    let path = Path::new("compressor_restart_small_bit_sizes.e57");
+    restart_round_trip(path, 1, true, 60_000, 3);
+    restart_round_trip(path, 1, true, 30_000, 1);
+    restart_round_trip(path, 3, false, 600_000, 5);
+    restart_round_trip(path, 1, false, 500_000, 2);
+    restart_round_trip(path, 3, true, 100_000, 2);
+}
+
+#[test]
+fn values_spanning_packets() {
+    let path = Path::new("values_spanning_packets.e57");
+    // Bit size of 11 is not byte aligned and values will continue in the next packet.
+    // The second count fills exactly two packets and leaves only incomplete bytes for the end.
+    const INT: RecordDataType = RecordDataType::Integer { min: 0, max: 2047 };
+    let packet_points = 64000 * 8 / 33;
+
+    for count in [40000, packet_points * 2] {
+        {
+            let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+            let prototype = vec![
+                Record {
+                    name: RecordName::CartesianX,
+                    data_type: INT,
+                },
+                Record {
+                    name: RecordName::CartesianY,
+                    data_type: INT,
+                },
+                Record {
+                    name: RecordName::CartesianZ,
+                    data_type: INT,
+                },
+            ];
+            let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+            for i in 0..count {
+                let x = RecordValue::Integer(i % 2048);
+                let y = RecordValue::Integer((i / 2) % 2048);
+                let z = RecordValue::Integer(2047 - i % 2048);
+                pc_writer.add_point(vec![x, y, z]).unwrap();
+            }
+            pc_writer.finalize().unwrap();
+            writer.finalize().unwrap();
+        }
+
+        {
+            let mut reader = E57Reader::from_file(path).unwrap();
+            let pc = reader.pointclouds().remove(0);
+            let spanning = reader
+                .pointcloud_packets(&pc)
+                .unwrap()
+                .map(|p| match p.unwrap() {
+                    RawPacket::Data { bytestreams, .. } => bytestreams[0].len() * 8 % 11 != 0,
+                    _ => panic!("Expected only data packets"),
+                })
+                .filter(|s| *s)
+                .count();
+            assert!(spanning > 0);
+
+            let points = reader
+                .pointcloud_raw(&pc)
+                .unwrap()
+                .collect::<Result<Vec<RawValues>>>()
+                .unwrap();
+            assert_eq!(points.len(), count as usize);
+            for (i, p) in points.iter().enumerate() {
+                let i = i as i64;
+                assert_eq!(p[0], RecordValue::Integer(i % 2048));
+                assert_eq!(p[1], RecordValue::Integer((i / 2) % 2048));
+                assert_eq!(p[2], RecordValue::Integer(2047 - i % 2048));
+            }
+        }
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn arbitrary_integer_bit_widths() {
+    let path = Path::new("arbitrary_integer_bit_widths.e57");
+    let widths = [1_u32, 2, 3, 5, 7, 9, 13, 17, 31, 32, 33, 48, 63, 64];
+    let range = |bits: u32| -> (i64, i64) {
+        if bits == 64 {
+            (i64::MIN, i64::MAX)
+        } else {
+            (-7, (-7 + (1_i128 << bits) - 1) as i64)
+        }
+    };
+    let value = |bits: u32, i: i64| -> i64 {
+        let (min, max) = range(bits);
+        match i % 3 {
+            0 => min,
+            1 => max,
+            _ => min + (i % (max as i128 - min as i128 + 1).min(1000) as i64),
+        }
+    };
+    let count = 50000;
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let ext = Extension {
+            namespace: "bits".to_owned(),
+            url: "https://www.corp.com/bits".to_owned(),
+        };
+        writer.register_extesion(ext).unwrap();
+        let mut prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+        ];
+        for bits in widths {
+            let (min, max) = range(bits);
+            prototype.push(Record {
+                name: RecordName::Unknown {
+                    namespace: "bits".to_owned(),
+                    name: format!("int{bits}"),
+                },
+                data_type: RecordDataType::Integer { min, max },
+            });
+        }
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        for i in 0..count {
+            let mut values = vec![RecordValue::Single(i as f32); 3];
+            for bits in widths {
+                values.push(RecordValue::Integer(value(bits, i)));
+            }
+            pc_writer.add_point(values).unwrap();
+        }
+
+        // Values outside of the range are rejected
+        let mut values = vec![RecordValue::Single(0.0); 3];
+        for bits in widths {
+            values.push(RecordValue::Integer(range(bits).1));
+        }
+        values[3] = RecordValue::Integer(range(1).1 + 1);
+        assert!(pc_writer.add_point(values).is_err());
+
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        assert_eq!(points.len(), count as usize);
+        for (i, p) in points.iter().enumerate() {
+            for (j, bits) in widths.iter().enumerate() {
+                assert_eq!(p[j + 3], RecordValue::Integer(value(*bits, i as i64)));
+            }
+        }
+    }
+
+    // Tightly packed: 32 bits per single coordinate plus the sum of all widths
+    let bits_per_point: u32 = 96 + widths.iter().sum::<u32>();
+    let file_size = std::fs::metadata(path).unwrap().len();
+    assert!(file_size < count as u64 * bits_per_point as u64 / 8 * 11 / 10);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn bounded_float_records() {
+    let path = Path::new("bounded_float_records.e57");
+    let timestamp = Record {
+        name: RecordName::TimeStamp,
+        data_type: RecordDataType::Double {
+            min: Some(-10.0),
+            max: Some(10.0),
+        },
+    };
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+
+        // Maximum smaller than minimum is rejected
+        let invalid = Record {
+            name: RecordName::TimeStamp,
+            data_type: RecordDataType::Double {
+                min: Some(1.0),
+                max: Some(-1.0),
+            },
+        };
+        let prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+            invalid,
+        ];
+        assert!(writer.add_pointcloud("invalid", prototype).is_err());
+
+        let prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+            Record::INTENSITY_UNIT_F32,
+            timestamp,
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        let point = |intensity: f32, time: f64| {
+            let mut values = vec![RecordValue::Single(1.0); 3];
+            values.push(RecordValue::Single(intensity));
+            values.push(RecordValue::Double(time));
+            values
+        };
+
+        // Values outside of the bounds are accepted by default
+        pc_writer.add_point(point(1.5, 0.0)).unwrap();
+
+        pc_writer.set_strict_float_bounds(true);
+        pc_writer.add_point(point(0.0, -10.0)).unwrap();
+        pc_writer.add_point(point(1.0, 10.0)).unwrap();
+        assert!(pc_writer.add_point(point(1.5, 0.0)).is_err());
+        assert!(pc_writer.add_point(point(0.5, -10.5)).is_err());
+        assert!(pc_writer.add_point(point(f32::NAN, 0.0)).is_err());
+
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        assert_eq!(pc.records, 3);
+        assert!(matches!(
+            pc.prototype[3].data_type,
+            RecordDataType::Single {
+                min: Some(0.0),
+                max: Some(1.0)
+            }
+        ));
+        assert!(matches!(
+            pc.prototype[4].data_type,
+            RecordDataType::Double {
+                min: Some(-10.0),
+                max: Some(10.0)
+            }
+        ));
+    }
+
+    // Inverted bounds in existing files are only rejected on request
+    let mut data = std::fs::read(path).unwrap();
+    let needle = b"minimum=\"-10\"";
+    let pos = data
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .unwrap();
+    data[pos..pos + needle.len()].copy_from_slice(b"minimum=\"99\" ");
+    let mut options = ReaderOptions::default();
+    options.verify_checksums = false;
+    let reader = E57Reader::with_options(Cursor::new(data.clone()), options.clone()).unwrap();
+    let pc = reader.pointclouds().remove(0);
+    assert!(matches!(
+        pc.prototype[4].data_type,
+        RecordDataType::Double {
+            min: Some(99.0),
+            max: Some(10.0)
+        }
+    ));
+    options.strict_float_bounds = true;
+    assert!(E57Reader::with_options(Cursor::new(data), options).is_err());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn empty_pointcloud() {
+    let path = Path::new("empty_pointcloud.e57");
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+            Record::INTENSITY_U16,
+        ];
+        let mut pc_writer = writer.add_pointcloud("empty", prototype).unwrap();
+        pc_writer.set_name(Some("Placeholder".to_owned()));
+        pc_writer.finalize().unwrap();
+
+        // Regular point cloud following the empty one
+        let prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+        ];
+        let mut pc_writer = writer.add_pointcloud("filled", prototype).unwrap();
+        pc_writer
+            .add_point(vec![RecordValue::Single(1.0); 3])
+            .unwrap();
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pcs = reader.pointclouds();
+        assert_eq!(pcs.len(), 2);
+        let pc = &pcs[0];
+        assert_eq!(pc.records, 0);
+        assert_eq!(pc.name.as_deref(), Some("Placeholder"));
+        assert!(pc.cartesian_bounds.is_none());
+        assert_eq!(pc.prototype.len(), 4);
+        assert_eq!(reader.pointcloud_simple(pc).unwrap().count(), 0);
+        assert_eq!(reader.pointcloud_raw(pc).unwrap().count(), 0);
+        let packets = reader
+            .pointcloud_packets(pc)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(packets.is_empty());
+        let stats = reader.pointcloud_statistics(pc).unwrap();
+        assert!(stats.cartesian_bounds.is_none());
+
+        let pc = &pcs[1];
+        assert_eq!(pc.records, 1);
+        assert_eq!(reader.pointcloud_simple(pc).unwrap().count(), 1);
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn interleaved_sections() {
+    let path = Path::new("interleaved_sections.e57");
+    let prototype = vec![
+        Record::CARTESIAN_X_F32,
+        Record::CARTESIAN_Y_F32,
+        Record::CARTESIAN_Z_F32,
+    ];
+    let image = std::fs::read("testdata/castle.jpg").unwrap();
+    let props = VisualReferenceImageProperties {
+        width: 100,
+        height: 100,
+    };
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        for i in 0..3 {
+            let guid = format!("pc{i}");
+            let mut pc_writer = writer.add_pointcloud(&guid, prototype.clone()).unwrap();
+            for _ in 0..=i {
+                pc_writer
+                    .add_point(vec![RecordValue::Single(i as f32); 3])
+                    .unwrap();
+            }
+            pc_writer.finalize().unwrap();
+            drop(pc_writer);
+
+            // Dropped image writer is discarded
+            let mut img_writer = writer.add_image("dropped").unwrap();
+            img_writer
+                .add_visual_reference(
+                    ImageFormat::Jpeg,
+                    &mut image.as_slice(),
+                    props.clone(),
+                    None,
+                )
+                .unwrap();
+            drop(img_writer);
+
+            // Aborted image writer is discarded
+            let mut img_writer = writer.add_image("aborted").unwrap();
+            img_writer
+                .add_visual_reference(
+                    ImageFormat::Jpeg,
+                    &mut image.as_slice(),
+                    props.clone(),
+                    None,
+                )
+                .unwrap();
+            img_writer.abort().unwrap();
+            assert!(img_writer.finalize().is_err());
+            drop(img_writer);
+
+            let mut img_writer = writer.add_image(&format!("img{i}")).unwrap();
+            img_writer.set_pointcloud_guid(&guid);
+            img_writer
+                .add_visual_reference(
+                    ImageFormat::Jpeg,
+                    &mut image.as_slice(),
+                    props.clone(),
+                    None,
+                )
+                .unwrap();
+            img_writer.finalize().unwrap();
+            assert!(img_writer.finalize().is_err());
+            assert!(img_writer.abort().is_err());
+        }
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pcs = reader.pointclouds();
+        let images = reader.images();
+        assert_eq!(pcs.len(), 3);
+        assert_eq!(images.len(), 3);
+        for (i, (pc, img)) in pcs.iter().zip(&images).enumerate() {
+            assert_eq!(pc.guid, Some(format!("pc{i}")));
+            assert_eq!(img.guid, Some(format!("img{i}")));
+            assert_eq!(img.pointcloud_guid, pc.guid);
+            let points = reader
+                .pointcloud_simple(pc)
+                .unwrap()
+                .collect::<Result<Vec<Point>>>()
+                .unwrap();
+            assert_eq!(points.len(), i + 1);
+            let blob = &img.visual_reference.as_ref().unwrap().blob.data;
+            let mut data = Vec::new();
+            reader.blob(blob, &mut data).unwrap();
+            assert_eq!(data, image);
+        }
+    }
+    let file = File::open(path).unwrap();
+    assert!(E57Reader::validate_crc(file).is_ok());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn duplicate_guids() {
+    let path = Path::new("duplicate_guids.e57");
+    let prototype = vec![
+        Record::CARTESIAN_X_F32,
+        Record::CARTESIAN_Y_F32,
+        Record::CARTESIAN_Z_F32,
+    ];
+
+    let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+    writer
+        .write_pointcloud_from_iter("guid", prototype, Vec::new())
+        .unwrap();
+    let mut img_writer = writer.add_image("guid").unwrap();
+    let props = VisualReferenceImageProperties {
+        width: 100,
+        height: 100,
+    };
+    let mut image = File::open("testdata/castle.jpg").unwrap();
+    img_writer
+        .add_visual_reference(ImageFormat::Jpeg, &mut image, props, None)
+        .unwrap();
+    img_writer.finalize().unwrap();
+    assert!(writer.finalize().is_err());
+    drop(writer);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+#[cfg(feature = "uuid")]
+fn generated_guids() {
+    let path = Path::new("generated_guids.e57");
+    let prototype = vec![
+        Record::CARTESIAN_X_F32,
+        Record::CARTESIAN_Y_F32,
+        Record::CARTESIAN_Z_F32,
+    ];
+
+    let mut writer = E57Writer::from_file_with_random_guid(path).unwrap();
+    for _ in 0..2 {
+        let mut pc_writer = writer
+            .add_pointcloud_with_random_guid(prototype.clone())
+            .unwrap();
+        pc_writer
+            .add_point(vec![RecordValue::Single(1.0); 3])
+            .unwrap();
+        pc_writer.finalize().unwrap();
+    }
+    writer.finalize().unwrap();
+    drop(writer);
+
+    let reader = E57Reader::from_file(path).unwrap();
+    let is_guid = |g: &str| g.len() == 38 && g.starts_with('{') && g.ends_with('}');
+    assert!(is_guid(reader.guid()));
+    let pcs = reader.pointclouds();
+    let first = pcs[0].guid.as_deref().unwrap();
+    let second = pcs[1].guid.as_deref().unwrap();
+    assert!(is_guid(first));
+    assert!(is_guid(second));
+    assert_ne!(first, second);
+    assert_ne!(first, reader.guid());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn custom_xml_round_trip() {
+    let path = Path::new("custom_xml_round_trip.e57");
+    let copy_path = Path::new("custom_xml_round_trip_copy.e57");
+    let prototype = vec![
+        Record::CARTESIAN_X_F32,
+        Record::CARTESIAN_Y_F32,
+        Record::CARTESIAN_Z_F32,
+    ];
+    let root_xml = "<ven:project type=\"String\" ven:id=\"42\"><![CDATA[Site A]]></ven:project>";
+    let pc_xml =
+        "<ven:scanner type=\"Structure\"><ven:mode type=\"Integer\">3</ven:mode></ven:scanner>";
+    let img_xml = "<ven:exposure type=\"Float\">0.25</ven:exposure>";
+    let root_attr = "ven:project=\"A &amp; B\"";
+    let pc_attr = "ven:id=\"42\"";
+    let img_attr = "ven:camera=\"front\"";
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        writer
+            .register_extesion(Extension::new("ven", "https://www.vendor.com/e57"))
+            .unwrap();
+        writer.add_custom_xml(root_xml).unwrap();
+        writer.add_custom_attribute(root_attr).unwrap();
+
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        assert!(pc_writer.add_custom_xml("<unknown/>").is_err());
+        assert!(pc_writer.add_custom_xml("<other:tag/>").is_err());
+        pc_writer.add_custom_xml(pc_xml).unwrap();
+        assert!(pc_writer.add_custom_attribute("id=\"1\"").is_err());
+        assert!(pc_writer.add_custom_attribute("other:id=\"1\"").is_err());
+        assert!(pc_writer
+            .add_custom_attribute("ven:a=\"1\" ven:b=\"2\"")
+            .is_err());
+        assert!(pc_writer.add_custom_attribute("ven:id=\"1").is_err());
+        pc_writer.add_custom_attribute(pc_attr).unwrap();
+        pc_writer
+            .add_point(vec![RecordValue::Single(1.0); 3])
+            .unwrap();
+        pc_writer.finalize().unwrap();
+
+        let mut img_writer = writer.add_image("img_guid").unwrap();
+        img_writer.add_custom_xml(img_xml).unwrap();
+        img_writer.add_custom_attribute(img_attr).unwrap();
+        let props = VisualReferenceImageProperties {
+            width: 100,
+            height: 100,
+        };
+        let mut image = File::open("testdata/castle.jpg").unwrap();
+        img_writer
+            .add_visual_reference(ImageFormat::Jpeg, &mut image, props, None)
+            .unwrap();
+        img_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    // Carry all custom XML through into a new file
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        assert_eq!(reader.custom_xml(), [root_xml]);
+        assert_eq!(reader.custom_attributes(), [root_attr]);
+        let pc = reader.pointclouds().remove(0);
+        assert_eq!(pc.custom_xml, [pc_xml]);
+        assert_eq!(pc.custom_attributes, [pc_attr]);
+        let img = reader.images().remove(0);
+        assert_eq!(img.custom_xml, [img_xml]);
+        assert_eq!(img.custom_attributes, [img_attr]);
+
+        let mut writer = E57Writer::from_file(copy_path, reader.guid()).unwrap();
+        for ext in reader.extensions() {
+            writer.register_extesion(ext).unwrap();
+        }
+        for xml in reader.custom_xml() {
+            writer.add_custom_xml(xml).unwrap();
+        }
+        for attr in reader.custom_attributes() {
+            writer.add_custom_attribute(attr).unwrap();
+        }
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        let mut pc_writer = writer
+            .add_pointcloud(pc.guid.as_deref().unwrap(), pc.prototype.clone())
+            .unwrap();
+        for xml in &pc.custom_xml {
+            pc_writer.add_custom_xml(xml).unwrap();
+        }
+        for attr in &pc.custom_attributes {
+            pc_writer.add_custom_attribute(attr).unwrap();
+        }
+        for p in points {
+            pc_writer.add_point(p).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let reader = E57Reader::from_file(copy_path).unwrap();
+        assert_eq!(reader.custom_xml(), [root_xml]);
+        assert_eq!(reader.custom_attributes(), [root_attr]);
+        let pc = reader.pointclouds().remove(0);
+        assert_eq!(pc.records, 1);
+        assert_eq!(pc.custom_xml, [pc_xml]);
+        assert_eq!(pc.custom_attributes, [pc_attr]);
+    }
+
+    remove_file(path).unwrap();
+    remove_file(copy_path).unwrap();
+}
+
+#[test]
+fn write_read_cylindrical_image() {
+    let path = Path::new("write_read_cylindrical_image.e57");
+    let jpeg = std::fs::read("testdata/castle.jpg").unwrap();
+    let props = CylindricalImageProperties {
+        width: 100,
+        height: 100,
+        radius: 2.5,
+        principal_y: 50.0,
+        pixel_width: 0.01,
+        pixel_height: 0.02,
+    };
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let mut img_writer = writer.add_image("image_guid").unwrap();
+        img_writer
+            .add_cylindrical(ImageFormat::Jpeg, &mut jpeg.as_slice(), props, None)
+            .unwrap();
+        img_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let reader = E57Reader::from_file(path).unwrap();
+        let images = reader.images();
+        assert_eq!(images.len(), 1);
+        let Some(Projection::Cylindrical(rep)) = &images[0].projection else {
+            panic!("Expected cylindrical image");
+        };
+        assert_eq!(rep.properties.radius, 2.5);
+        assert_eq!(rep.properties.principal_y, 50.0);
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn copy_sections() {
+    let path = Path::new("copy_sections.e57");
+    let image_blob = |img: &Image| -> Blob {
+        match (&img.visual_reference, &img.projection) {
+            (Some(vr), _) => vr.blob.data.clone(),
+            (None, Some(Projection::Pinhole(p))) => p.blob.data.clone(),
+            (None, Some(Projection::Spherical(p))) => p.blob.data.clone(),
+            (None, Some(Projection::Cylindrical(p))) => p.blob.data.clone(),
+            (None, None) => panic!("Image without any blob"),
+        }
+    };
+    let sources = ["testdata/bunnyInt19.e57", "testdata/tiny_pc_and_images.e57"];
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        for source in sources {
+            let mut reader = E57Reader::from_file(source).unwrap();
+            for pc in reader.pointclouds() {
+                writer.copy_pointcloud(&mut reader, &pc).unwrap();
+            }
+            for img in reader.images() {
+                writer.copy_image(&mut reader, &img).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    let mut copy = E57Reader::from_file(path).unwrap();
+    let mut copied_pcs = copy.pointclouds().into_iter();
+    let mut copied_imgs = copy.images().into_iter();
+    for source in sources {
+        let mut reader = E57Reader::from_file(source).unwrap();
+        for pc in reader.pointclouds() {
+            let copied = copied_pcs.next().unwrap();
+            assert_eq!(copied.guid, pc.guid);
+            assert_eq!(copied.records, pc.records);
+            let original_points = reader
+                .pointcloud_raw(&pc)
+                .unwrap()
+                .collect::<Result<Vec<RawValues>>>()
+                .unwrap();
+            let copied_points = copy
+                .pointcloud_raw(&copied)
+                .unwrap()
+                .collect::<Result<Vec<RawValues>>>()
+                .unwrap();
+            assert_eq!(copied_points, original_points);
+        }
+        for img in reader.images() {
+            let copied = copied_imgs.next().unwrap();
+            assert_eq!(copied.guid, img.guid);
+            let mut original_data = Vec::new();
+            reader.blob(&image_blob(&img), &mut original_data).unwrap();
+            let mut copied_data = Vec::new();
+            copy.blob(&image_blob(&copied), &mut copied_data).unwrap();
+            assert_eq!(copied_data, original_data);
+        }
+    }
+    assert!(copied_pcs.next().is_none());
+    assert!(copied_imgs.next().is_none());
+    drop(copy);
+
+    let file = File::open(path).unwrap();
+    assert!(E57Reader::validate_crc(file).is_ok());
+
+    // Unit scale cannot be applied to copied data
+    let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+    writer.set_unit_scale(0.3048).unwrap();
+    let mut reader = E57Reader::from_file(sources[0]).unwrap();
+    let pc = reader.pointclouds().remove(0);
+    assert!(writer.copy_pointcloud(&mut reader, &pc).is_err());
+    let mut reader = E57Reader::from_file(sources[1]).unwrap();
+    let img = reader.images().remove(0);
+    assert!(writer.copy_image(&mut reader, &img).is_err());
+    drop(writer);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn baked_transform() {
+    let path = Path::new("baked_transform.e57");
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let spherical = vec![
+            Record {
+                name: RecordName::SphericalRange,
+                data_type: RecordDataType::F64,
+            },
+            Record {
+                name: RecordName::SphericalAzimuth,
+                data_type: RecordDataType::F64,
+            },
+            Record {
+                name: RecordName::SphericalElevation,
+                data_type: RecordDataType::F64,
+            },
+        ];
+        let mut pc_writer = writer.add_pointcloud("spherical", spherical).unwrap();
+        let identity = Transform::default().to_matrix();
+        assert!(pc_writer.set_baked_transform(Some(identity)).is_err());
+        pc_writer.abort().unwrap();
+
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        let mut projective = identity;
+        projective[3][0] = 1.0;
+        assert!(pc_writer.set_baked_transform(Some(projective)).is_err());
+
+        // Rotate by 90 degrees around Z and move along X
+        let matrix = [
+            [0.0, -1.0, 0.0, 10.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        pc_writer.set_baked_transform(Some(matrix)).unwrap();
+        let point = |x: f64, y: f64| {
+            vec![
+                RecordValue::Double(x),
+                RecordValue::Double(y),
+                RecordValue::Double(0.0),
+            ]
+        };
+        pc_writer.add_point(point(1.0, 0.0)).unwrap();
+        pc_writer.add_point(point(0.0, 2.0)).unwrap();
+        assert!(pc_writer.set_baked_transform(None).is_err());
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        assert!(pc.transform.is_none());
+        let bounds = pc.cartesian_bounds.clone().unwrap();
+        assert_eq!(bounds.x_min, Some(8.0));
+        assert_eq!(bounds.x_max, Some(10.0));
+        assert_eq!(bounds.y_min, Some(0.0));
+        assert_eq!(bounds.y_max, Some(1.0));
+
+        let points = reader
+            .pointcloud_simple(&pc)
+            .unwrap()
+            .collect::<Result<Vec<Point>>>()
+            .unwrap();
+        let coords: Vec<CartesianCoordinate> = points.into_iter().map(|p| p.cartesian).collect();
+        assert_eq!(
+            coords,
+            vec![
+                CartesianCoordinate::Valid {
+                    x: 10.0,
+                    y: 1.0,
+                    z: 0.0
+                },
+                CartesianCoordinate::Valid {
+                    x: 8.0,
+                    y: 0.0,
+                    z: 0.0
+                }
+            ]
+        );
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+#[cfg(feature = "generator")]
+fn generator() {
+    use e57::{generate, GeneratorOptions, RawPacket};
+    use std::io::Cursor;
+
+    let mut options = GeneratorOptions::default();
+    options.seed = 42;
+    options.pointclouds = 3;
+    options.points = 30000;
+    options.spherical = true;
+    options.color = true;
+    options.intensity = true;
+    options.row_column = true;
+    options.returns = true;
+    options.time_stamp = true;
+    options.invalid_states = true;
+    options.all_record_types = true;
+    options.compressor_restart_interval = 2;
+    options.images = 2;
+    options.extension = true;
+
+    let mut first = Cursor::new(Vec::new());
+    generate(&mut first, &options).unwrap();
+    let mut second = Cursor::new(Vec::new());
+    generate(&mut second, &options).unwrap();
+    assert_eq!(first.get_ref(), second.get_ref());
+
+    first.rewind().unwrap();
+    let mut reader = E57Reader::new(first).unwrap();
+    assert_eq!(reader.extensions().len(), 1);
+    assert_eq!(reader.images().len(), 2);
+    let pcs = reader.pointclouds();
+    assert_eq!(pcs.len(), 3);
+    assert!(matches!(
+        pcs[0].prototype[0].data_type,
+        RecordDataType::Double { .. }
+    ));
+    assert!(matches!(
+        pcs[1].prototype[0].data_type,
+        RecordDataType::Single { .. }
+    ));
+    assert!(matches!(
+        pcs[2].prototype[0].data_type,
+        RecordDataType::ScaledInteger { .. }
+    ));
+    for pc in &pcs {
+        assert_eq!(pc.records, 30000);
+        assert_eq!(pc.prototype.len(), 21);
+
+        let packets = reader
+            .pointcloud_packets(pc)
+            .unwrap()
+            .collect::<Result<Vec<RawPacket>>>()
+            .unwrap();
+        assert!(packets.len() > 1);
+
+        let mut iter = reader.pointcloud_simple(pc).unwrap();
+        iter.intensity_to_color(false);
+        let points = iter.collect::<Result<Vec<Point>>>().unwrap();
+        assert_eq!(points.len(), 30000);
+        assert!(matches!(
+            points[0].cartesian,
+            CartesianCoordinate::Valid { .. }
+        ));
+        assert!(matches!(points[3].cartesian, CartesianCoordinate::Invalid));
+        assert!(matches!(
+            points[5].cartesian,
+            CartesianCoordinate::Direction { .. }
+        ));
+        assert!(points[4].color.is_none());
+        assert!(points[6].intensity.is_none());
+    }
+}
+
+#[test]
+fn transcode_pointcloud() {
+    let path = Path::new("transcode_pointcloud.e57");
+    let mut reader = E57Reader::from_file("testdata/tinyCartesianFloatRgb.e57").unwrap();
+    let pc = reader.pointclouds().remove(0);
+    let original = reader
+        .pointcloud_raw(&pc)
+        .unwrap()
+        .collect::<Result<Vec<RawValues>>>()
+        .unwrap();
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let mut index = 0;
+        let count = writer
+            .transcode_pointcloud(
+                &mut reader,
+                &pc,
+                "pc_guid",
+                pc.prototype.clone(),
+                |values| {
+                    index += 1;
+                    if let RecordValue::Single(x) = &mut values[0] {
+                        *x += 1.0;
+                    }
+                    Ok(index % 2 == 0)
+                },
+            )
+            .unwrap();
+        assert_eq!(count, pc.records / 2);
+
+        // Errors of the closure are passed through
+        let result =
+            writer.transcode_pointcloud(&mut reader, &pc, "pc2", pc.prototype.clone(), |_| {
+                e57::Error::invalid("Stop")
+            });
+        assert!(result.is_err());
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pcs = reader.pointclouds();
+        assert_eq!(pcs.len(), 1);
+        let copy = &pcs[0];
+        assert_eq!(copy.guid.as_deref(), Some("pc_guid"));
+        assert_eq!(copy.name, pc.name);
+        assert_eq!(copy.records, pc.records / 2);
+        let points = reader
+            .pointcloud_raw(copy)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        for (copied, org) in points.iter().zip(original.iter().skip(1).step_by(2)) {
+            let (RecordValue::Single(a), RecordValue::Single(b)) = (&copied[0], &org[0]) else {
+                panic!("Unexpected value types");
+            };
+            assert_eq!(*a, *b + 1.0);
+            assert_eq!(copied[1..], org[1..]);
+        }
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn copy_pointcloud_line_groups() {
+    let lines = 3;
+    let points_per_line = 4;
+    let prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+        Record {
+            name: RecordName::ColumnIndex,
+            data_type: RecordDataType::Integer {
+                min: 0,
+                max: lines - 1,
+            },
+        },
+    ];
+    let mut source = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut source, "file_guid").unwrap();
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype.clone()).unwrap();
+        let mut groups = Vec::new();
+        for line in 0..lines {
+            for i in 0..points_per_line {
+                pc_writer
+                    .add_point(vec![
+                        RecordValue::Double(i as f64),
+                        RecordValue::Double(line as f64),
+                        RecordValue::Double(0.0),
+                        RecordValue::Integer(line),
+                    ])
+                    .unwrap();
+            }
+            groups.push(LineGroup {
+                id_element_value: line,
+                start_point_index: line * points_per_line,
+                point_count: points_per_line,
+                cartesian_bounds: None,
+                spherical_bounds: None,
+            });
+        }
+        pc_writer.set_line_groups("columnIndex", groups).unwrap();
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+    source.rewind().unwrap();
+    let mut reader = E57Reader::new(source).unwrap();
+    let pc = reader.pointclouds().remove(0);
+
+    // Skip every second point and the complete last line
+    let mut target = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut target, "copy_guid").unwrap();
+        let count = e57::copy_pointcloud(
+            &mut reader,
+            &mut writer,
+            &pc,
+            "copy1",
+            prototype.clone(),
+            |values| {
+                let keep_x =
+                    values[0] == RecordValue::Double(0.0) || values[0] == RecordValue::Double(2.0);
+                Ok(keep_x && values[3] != RecordValue::Integer(lines - 1))
+            },
+        )
+        .unwrap();
+        assert_eq!(count, 4);
+
+        // Without the ID element the line groups are not copied
+        let count = e57::copy_pointcloud(
+            &mut reader,
+            &mut writer,
+            &pc,
+            "copy2",
+            prototype[..3].to_vec(),
+            |values| {
+                values.truncate(3);
+                Ok(true)
+            },
+        )
+        .unwrap();
+        assert_eq!(count, (lines * points_per_line) as u64);
+        writer.finalize().unwrap();
+    }
+    target.rewind().unwrap();
+    let mut reader = E57Reader::new(target).unwrap();
+    let pcs = reader.pointclouds();
+    assert_eq!(pcs.len(), 2);
+    assert!(pcs[1].line_grouping.is_none());
+    let grouping = pcs[0].line_grouping.as_ref().unwrap();
+    assert_eq!(grouping.id_element_name, "columnIndex");
+    let groups = reader.line_groups(&pcs[0]).unwrap();
+    let ranges: Vec<_> = groups
+        .iter()
+        .map(|g| (g.id_element_value, g.start_point_index, g.point_count))
+        .collect();
+    assert_eq!(ranges, [(0, 0, 2), (1, 2, 2), (2, 4, 0)]);
+}
+
+#[test]
+fn crs_and_reprojection() {
+    let path = Path::new("crs_and_reprojection.e57");
+    let wkt = r#"PROJCS["ETRS89 / UTM zone 32N",GEOGCS["ETRS89",DATUM["European_Terrestrial_Reference_System_1989",SPHEROID["GRS 1980",6378137,298.257222101]]],PROJECTION["Transverse_Mercator"],UNIT["metre",1],AUTHORITY["EPSG","25832"]]"#;
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        writer.set_coordinate_metadata(Some(wkt.to_owned()));
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        for i in 0..10 {
+            let v = RecordValue::Double(i as f64);
+            pc_writer.add_point(vec![v.clone(), v.clone(), v]).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let crs = reader.coordinate_reference_system().unwrap().unwrap();
+        assert_eq!(crs.wkt, wkt);
+        assert_eq!(crs.name.as_deref(), Some("ETRS89 / UTM zone 32N"));
+        assert_eq!(crs.epsg_code(), Some(25832));
+
+        let pc = reader.pointclouds().remove(0);
+        let mut iter = reader.pointcloud_simple(&pc).unwrap();
+        iter.set_reprojection(Some(Box::new(|[x, y, z]| {
+            Ok([x + 32000000.0, y + 5000000.0, z])
+        })));
+        let points = iter.collect::<Result<Vec<Point>>>().unwrap();
+        assert_eq!(points.len(), 10);
+        assert_eq!(
+            points[3].cartesian,
+            CartesianCoordinate::Valid {
+                x: 32000003.0,
+                y: 5000003.0,
+                z: 3.0
+            }
+        );
+
+        let mut iter = reader.pointcloud_simple(&pc).unwrap();
+        iter.set_reprojection(Some(Box::new(|_| {
+            e57::Error::invalid("Outside of projection area")
+        })));
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        // Disabling the reprojection does not require any type annotations
+        let mut iter = reader.pointcloud_simple(&pc).unwrap();
+        iter.set_reprojection(None);
+        assert_eq!(iter.count(), 10);
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn known_extensions() {
+    let path = Path::new("known_extensions.e57");
+    let quality = ExtensionDefinition::new(
+        Extension::new("qa", "https://example.com/quality"),
+        vec![
+            ExtensionRecordDefinition::new("confidence", ExtensionValueKind::Unit),
+            ExtensionRecordDefinition::new("outlier", ExtensionValueKind::Boolean),
+        ],
+    );
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let normals = ExtensionDefinition::normals();
+        writer.register_known_extension(normals.clone()).unwrap();
+        writer.register_known_extension(quality.clone()).unwrap();
+        assert!(writer.register_known_extension(quality.clone()).is_err());
+        assert!(normals.record("normalW", RecordDataType::F32).is_err());
+
+        let prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+            normals.record("normalX", RecordDataType::F32).unwrap(),
+            normals.record("normalY", RecordDataType::F32).unwrap(),
+            normals.record("normalZ", RecordDataType::F32).unwrap(),
+            quality.record("confidence", RecordDataType::U8).unwrap(),
+            quality.record("outlier", RecordDataType::U8).unwrap(),
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        for i in 0..4 {
+            pc_writer
+                .add_point(vec![
+                    RecordValue::Single(i as f32),
+                    RecordValue::Single(0.0),
+                    RecordValue::Single(0.0),
+                    RecordValue::Single(0.0),
+                    RecordValue::Single(0.0),
+                    RecordValue::Single(1.0),
+                    RecordValue::Integer(255),
+                    RecordValue::Integer(i % 2),
+                ])
+                .unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let extensions = reader.extensions();
+        assert!(extensions
+            .iter()
+            .any(|e| e.url == ExtensionDefinition::NORMALS_URL));
+        let pc = reader.pointclouds().remove(0);
+        let definitions = [ExtensionDefinition::normals(), quality];
+        let decoder = ExtensionDecoder::new(&pc, &extensions, &definitions);
+        assert_eq!(
+            decoder.names(),
+            ["normalX", "normalY", "normalZ", "confidence", "outlier"]
+        );
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        let values = decoder.decode(&points[1]).unwrap();
+        assert_eq!(
+            values,
+            [
+                ExtensionValue::Float(0.0),
+                ExtensionValue::Float(0.0),
+                ExtensionValue::Float(1.0),
+                ExtensionValue::Unit(1.0),
+                ExtensionValue::Boolean(true),
+            ]
+        );
+
+        let decoder = ExtensionDecoder::new(&pc, &extensions, &[]);
+        assert!(decoder.is_empty());
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn known_extension_conflicts() {
+    let quality = ExtensionDefinition::new(
+        Extension::new("qa", "https://example.com/quality"),
+        vec![ExtensionRecordDefinition::new(
+            "confidence",
+            ExtensionValueKind::Unit,
+        )],
+    );
+    let other = Extension::new("qa", "https://example.com/other");
+    let prototype = |records: Vec<Record>| {
+        let mut prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+        ];
+        prototype.extend(records);
+        prototype
+    };
+    let confidence = quality.record("confidence", RecordDataType::U8).unwrap();
+
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut cursor, "file_guid").unwrap();
+        writer.register_extesion(other.clone()).unwrap();
+        assert!(writer.register_known_extension(quality.clone()).is_err());
+    }
+
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut cursor, "file_guid").unwrap();
+        writer.register_known_extension(quality.clone()).unwrap();
+        writer.register_extesion(other).unwrap();
+        assert!(writer
+            .add_pointcloud("pc1", prototype(vec![confidence.clone()]))
+            .is_err());
+    }
+
+    // Invalid prototypes do not declare the known extensions they use
+    let unknown = Record {
+        name: RecordName::Unknown {
+            namespace: "unknown".to_owned(),
+            name: "value".to_owned(),
+        },
+        data_type: RecordDataType::U8,
+    };
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut cursor, "file_guid").unwrap();
+        writer.register_known_extension(quality).unwrap();
+        assert!(writer
+            .add_pointcloud("pc1", prototype(vec![confidence, unknown]))
+            .is_err());
+        writer.finalize().unwrap();
+    }
+    cursor.rewind().unwrap();
+    let reader = E57Reader::new(cursor).unwrap();
+    assert!(reader.extensions().is_empty());
+}
+
+#[test]
+fn duplicate_removal() {
+    let path = Path::new("duplicate_removal.e57");
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mut pc_writer = writer.add_pointcloud("exact", prototype.clone()).unwrap();
+        pc_writer
+            .set_duplicate_removal(DuplicateRemoval::Consecutive)
+            .unwrap();
+        for x in [0.0, 0.0, 1.0, 1.0, 1.0, 0.0] {
+            let values = vec![RecordValue::Double(x); 3];
+            pc_writer.add_point(values).unwrap();
+        }
+        assert!(pc_writer
+            .set_duplicate_removal(DuplicateRemoval::Disabled)
+            .is_err());
+        assert_eq!(pc_writer.dropped_duplicates(), 3);
+        pc_writer.finalize().unwrap();
+
+        let mut pc_writer = writer.add_pointcloud("tolerance", prototype).unwrap();
+        assert!(pc_writer
+            .set_duplicate_removal(DuplicateRemoval::Tolerance(-1.0))
+            .is_err());
+        pc_writer
+            .set_duplicate_removal(DuplicateRemoval::Tolerance(0.01))
+            .unwrap();
+        for x in [0.0, 1.0, 0.005, 1.001, 0.02] {
+            let values = vec![RecordValue::Double(x); 3];
+            pc_writer.add_point(values).unwrap();
+        }
+        assert_eq!(pc_writer.dropped_duplicates(), 2);
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let reader = E57Reader::from_file(path).unwrap();
+        let pcs = reader.pointclouds();
+        assert_eq!(pcs[0].records, 3);
+        assert_eq!(pcs[1].records, 3);
+        let bounds = pcs[1].cartesian_bounds.as_ref().unwrap();
+        assert_eq!(bounds.x_max, Some(1.0));
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn morton_order() {
+    let path = Path::new("morton_order.e57");
+    let prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+    ];
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        let mut order = MortonOrder::default();
+        order.resolution = 1.0;
+        order.memory_budget = 4096;
+        pc_writer.set_morton_order(Some(order)).unwrap();
+        assert!(pc_writer.set_line_groups("cartesianX", Vec::new()).is_err());
+        for i in (0..1000).rev() {
+            let v = (i % 10) as f64 * 10.0 + (i / 10) as f64 * 0.01;
+            let values = vec![RecordValue::Double(v); 3];
+            pc_writer.add_point(values).unwrap();
+        }
+        assert!(pc_writer.set_morton_order(None).is_err());
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        assert_eq!(pc.records, 1000);
+        assert_eq!(pc.morton_resolution(), Some(1.0));
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        let x: Vec<f64> = points
+            .iter()
+            .map(|p| p[0].to_f64(&RecordDataType::F64).unwrap())
+            .collect();
+        assert!(x.windows(2).all(|w| w[0].floor() <= w[1].floor()));
+        assert!(x[..100].iter().all(|v| *v < 1.0));
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn diff_files() {
+    let path = Path::new("diff_files.e57");
+    let mut original = E57Reader::from_file("testdata/tinyCartesianFloatRgb.e57").unwrap();
+    let pc = original.pointclouds().remove(0);
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let mut index = 0;
+        let guid = pc.guid.as_deref().unwrap();
+        let _ = writer
+            .transcode_pointcloud(&mut original, &pc, guid, pc.prototype.clone(), |values| {
+                index += 1;
+                if let (3, RecordValue::Single(x)) = (index, &mut values[0]) {
+                    *x += 0.5;
+                }
+                Ok(true)
+            })
+            .unwrap();
+        writer.finalize().unwrap();
+    }
+
+    // Comparing a file with itself finds no differences
+    let mut options = DiffOptions::default();
+    options.points = Some(Subsampling::All);
+    let mut same = E57Reader::from_file("testdata/tinyCartesianFloatRgb.e57").unwrap();
+    let report = diff(&mut original, &mut same, &options).unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.pointclouds[0].compared_points, pc.records);
+
+    // The modified copy has a different file GUID and one changed point
+    let mut copy = E57Reader::from_file(path).unwrap();
+    let report = diff(&mut original, &mut copy, &options).unwrap();
+    assert!(!report.is_empty());
+    assert!(report.metadata.iter().any(|d| d.path == "/guid"));
+    let pc_diff = &report.pointclouds[0];
+    assert_eq!(pc_diff.differing_points, 1);
+    assert_eq!(pc_diff.points.len(), 1);
+    assert_eq!(pc_diff.points[0].index, 2);
+    assert_eq!(pc_diff.points[0].record, RecordName::CartesianX);
+
+    // The change is ignored with a large enough tolerance
+    options.tolerance = 0.6;
+    let report = diff(&mut original, &mut copy, &options).unwrap();
+    assert_eq!(report.pointclouds[0].differing_points, 0);
+
+    // Point data is only compared when requested
+    let report = diff(&mut original, &mut copy, &DiffOptions::default()).unwrap();
+    assert_eq!(report.pointclouds[0].compared_points, 0);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn diff_prototypes_and_poses() {
+    use std::io::Cursor;
+
+    let write = |x: Record, offset: f64| {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut e57 = E57Writer::new(&mut cursor, "file").unwrap();
+        let value = match x.data_type {
+            RecordDataType::Single { .. } => RecordValue::Single(1.0),
+            _ => RecordValue::Double(1.0),
+        };
+        let prototype = vec![x, Record::CARTESIAN_Y_F64, Record::CARTESIAN_Z_F64];
+        let mut pc_writer = e57.add_pointcloud("pc", prototype).unwrap();
+        pc_writer.set_transform(Some(Transform {
+            translation: Translation {
+                x: 1.0 + offset,
+                y: 0.0,
+                z: 0.0,
+            },
+            ..Default::default()
+        }));
+        let values = vec![value, RecordValue::Double(2.0), RecordValue::Double(3.0)];
+        pc_writer.add_point(values).unwrap();
+        pc_writer.finalize().unwrap();
+        e57.finalize().unwrap();
+        drop(e57);
+        cursor.rewind().unwrap();
+        E57Reader::new(cursor).unwrap()
+    };
+    let mut original = write(Record::CARTESIAN_X_F64, 0.0);
+    let mut options = DiffOptions::default();
+    options.points = Some(Subsampling::All);
+
+    // Records with the same name, but a different data type are reported
+    let mut single = write(Record::CARTESIAN_X_F32, 0.0);
+    let report = diff(&mut original, &mut single, &options).unwrap();
+    let paths: Vec<_> = report.pointclouds[0]
+        .descriptor
+        .iter()
+        .map(|d| d.path.as_str())
+        .collect();
+    assert_eq!(paths, ["/data3D/0/points/prototype"]);
+    assert_eq!(report.pointclouds[0].differing_points, 0);
+
+    // Poses are compared component wise using the tolerance
+    let mut shifted = write(Record::CARTESIAN_X_F64, 1e-9);
+    let report = diff(&mut original, &mut shifted, &options).unwrap();
+    assert_eq!(report.pointclouds[0].descriptor.len(), 1);
+    assert_eq!(report.pointclouds[0].descriptor[0].path, "/data3D/0/pose");
+    options.tolerance = 1e-6;
+    let report = diff(&mut original, &mut shifted, &options).unwrap();
+    assert!(report.is_empty());
+}
+
+#[test]
+fn diff_reordered_pointclouds() {
+    let write = |guids: &[&str]| {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut e57 = E57Writer::new(&mut cursor, "file").unwrap();
+        for guid in guids {
+            let prototype = vec![
+                Record::CARTESIAN_X_F64,
+                Record::CARTESIAN_Y_F64,
+                Record::CARTESIAN_Z_F64,
+            ];
+            let mut pc_writer = e57.add_pointcloud(guid, prototype).unwrap();
+            let x = guid.len() as f64;
+            pc_writer
+                .add_point(vec![RecordValue::Double(x); 3])
+                .unwrap();
+            pc_writer.finalize().unwrap();
+        }
+        e57.finalize().unwrap();
+        drop(e57);
+        cursor.rewind().unwrap();
+        E57Reader::new(cursor).unwrap()
+    };
+    let mut left = write(&["x", "aa"]);
+    let mut right = write(&["aa", "yyy"]);
+    let mut options = DiffOptions::default();
+    options.points = Some(Subsampling::All);
+
+    // The shared point cloud is matched by GUID, so the others cannot be matched by position
+    let report = diff(&mut left, &mut right, &options).unwrap();
+    assert_eq!(report.pointclouds.len(), 1);
+    assert_eq!(report.pointclouds[0].left, 1);
+    assert_eq!(report.pointclouds[0].right, 0);
+    assert!(report.pointclouds[0].is_empty());
+    let missing: Vec<_> = report.metadata.iter().map(|d| d.path.as_str()).collect();
+    assert_eq!(missing, ["/data3D/0/guid", "/data3D/1/guid"]);
+    assert_eq!(report.metadata[0].right, "\"missing\"");
+    assert_eq!(report.metadata[1].left, "\"missing\"");
+
+    // Remaining point clouds at the same position are matched
+    let mut right = write(&["zzzz", "aa"]);
+    let report = diff(&mut left, &mut right, &options).unwrap();
+    assert_eq!(report.pointclouds.len(), 2);
+    assert_eq!(report.pointclouds[0].right, 0);
+    assert!(!report.pointclouds[0].is_empty());
+    assert!(report.pointclouds[1].is_empty());
+    assert!(report.metadata.is_empty());
+}
+
+#[test]
+fn copy_with_poses() {
+    let path = Path::new("copy_with_poses.e57");
+    let mut reader = E57Reader::from_file("testdata/tiny_pc_and_images.e57").unwrap();
+    let pcs = reader.pointclouds();
+    let images = reader.images();
+
+    // Pretend an external registration moved all scans by one meter
+    let shift = Transform {
+        translation: Translation {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        ..Default::default()
+    };
+    let poses: Vec<_> = scan_poses(&pcs, RegistrationFrame::World)
+        .unwrap()
+        .into_iter()
+        .map(|p| Some(shift.compose(&p.transform)))
+        .collect();
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        assert!(writer.copy_with_poses(&mut reader, &[]).is_err());
+        writer.copy_with_poses(&mut reader, &poses).unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let copy = E57Reader::from_file(path).unwrap();
+        let copied = copy.pointclouds();
+        assert_eq!(copied.len(), pcs.len());
+        assert_eq!(copy.images().len(), images.len());
+        for (pc, org) in copied.iter().zip(&pcs) {
+            let old = org.transform.clone().unwrap_or_default().translation.x;
+            let new = pc.transform.as_ref().unwrap().translation.x;
+            assert!((new - old - 1.0).abs() < 1e-9);
+            assert_eq!(pc.records, org.records);
+        }
+        let poses = scan_poses(&copied, RegistrationFrame::Scan(0)).unwrap();
+        assert!(poses[0].transform.is_identity(1e-9));
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn copy_with_poses_custom_xml() {
+    let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    let trajectory = Extension::new("traj", "https://example.com/trajectory");
+    let mut source = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut source, "source_guid").unwrap();
+        writer.register_extesion(trajectory.clone()).unwrap();
+        // Shift the blob so that the offset changes when copying
+        writer.add_blob(&mut [0_u8; 100].as_slice()).unwrap();
+        let blob = writer.add_blob(&mut data.as_slice()).unwrap();
+        let xml = format!(
+            "<traj:trajectory type=\"Structure\">{}{}</traj:trajectory>",
+            blob.xml_string("traj:data").trim_end(),
+            blob.xml_string("traj:backup").trim_end()
+        );
+        writer.add_custom_xml(&xml).unwrap();
+        writer
+            .add_custom_xml("<traj:note>no blob</traj:note>")
+            .unwrap();
+        writer.finalize().unwrap();
+    }
+    source.rewind().unwrap();
+    let mut reader = E57Reader::new(source).unwrap();
+
+    let mut target = Cursor::new(Vec::new());
+    {
+        let mut writer = E57Writer::new(&mut target, "target_guid").unwrap();
+        writer.copy_with_poses(&mut reader, &[]).unwrap();
+        writer.finalize().unwrap();
+    }
+    target.rewind().unwrap();
+    let mut copy = E57Reader::new(target).unwrap();
+    let custom_xml = copy.custom_xml().to_vec();
+    assert_eq!(custom_xml.len(), 2);
+    assert_eq!(custom_xml[1], "<traj:note>no blob</traj:note>");
+    let blob = copy.custom_blob(&custom_xml, "traj:data").unwrap().unwrap();
+    let backup = copy
+        .custom_blob(&custom_xml, "traj:backup")
+        .unwrap()
+        .unwrap();
+    assert_eq!(blob.offset, backup.offset);
+    assert_eq!(blob.length, data.len() as u64);
+    let mut copied = Vec::new();
+    copy.blob(&blob, &mut copied).unwrap();
+    assert_eq!(copied, data);
+
+    // Namespaces must not be registered with another URL
+    let mut target = Cursor::new(Vec::new());
+    let mut writer = E57Writer::new(&mut target, "target_guid").unwrap();
+    writer
+        .register_extesion(Extension::new("traj", "https://example.com/other"))
+        .unwrap();
+    assert!(writer.copy_with_poses(&mut reader, &[]).is_err());
+}
+
+#[test]
+fn flush_points() {
+    let path = Path::new("flush_points.e57");
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        let point = |i: usize| vec![RecordValue::Double(i as f64); 3];
+        for i in 0..10 {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        assert_eq!(pc_writer.buffered_points(), 10);
+        assert!(pc_writer.buffered_bytes() > 0);
+        let size = std::fs::metadata(path).unwrap().len();
+
+        pc_writer.flush().unwrap();
+        assert_eq!(pc_writer.buffered_points(), 0);
+        assert_eq!(pc_writer.buffered_bytes(), 0);
+        assert!(std::fs::metadata(path).unwrap().len() > size);
+
+        for i in 10..20 {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        assert!(pc_writer.flush().is_err());
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let pc = reader.pointclouds().remove(0);
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        assert_eq!(points.len(), 20);
+        for (i, p) in points.iter().enumerate() {
+            assert_eq!(p[2], RecordValue::Double(i as f64));
+        }
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn las_attributes() {
+    let path = Path::new("las_attributes.e57");
+    let las = ExtensionDefinition::las();
+    assert!(las.default_record("returnNumber").is_err());
+    assert!(ExtensionDefinition::normals()
+        .default_record("normalX")
+        .is_err());
+
+    {
+        let mut writer = E57Writer::from_file(path, "file_guid").unwrap();
+        writer.register_known_extension(las.clone()).unwrap();
+        let prototype = vec![
+            Record::CARTESIAN_X_F32,
+            Record::CARTESIAN_Y_F32,
+            Record::CARTESIAN_Z_F32,
+            las.default_record("classification").unwrap(),
+            las.default_record("scanAngle").unwrap(),
+            las.default_record("pointSourceId").unwrap(),
+        ];
+        let mut pc_writer = writer.add_pointcloud("pc_guid", prototype).unwrap();
+        for (class, angle) in [(2, -1000), (6, 5000)] {
+            pc_writer
+                .add_point(vec![
+                    RecordValue::Single(1.0),
+                    RecordValue::Single(2.0),
+                    RecordValue::Single(3.0),
+                    RecordValue::Integer(class),
+                    RecordValue::ScaledInteger(angle),
+                    RecordValue::Integer(42),
+                ])
+                .unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        writer.finalize().unwrap();
+    }
+
+    {
+        let mut reader = E57Reader::from_file(path).unwrap();
+        let extensions = reader.extensions();
+        assert!(extensions
+            .iter()
+            .any(|e| e.url == ExtensionDefinition::LAS_URL));
+        let pc = reader.pointclouds().remove(0);
+        let decoder = ExtensionDecoder::new(&pc, &extensions, &[las]);
+        assert_eq!(
+            decoder.names(),
+            ["classification", "scanAngle", "pointSourceId"]
+        );
+        let points = reader
+            .pointcloud_raw(&pc)
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        let values = decoder.decode(&points[1]).unwrap();
+        assert_eq!(values[0], ExtensionValue::Integer(6));
+        let ExtensionValue::Float(angle) = values[1] else {
+            panic!("Unexpected scan angle value");
+        };
+        assert!((angle - 30.0).abs() < 1e-9);
+        assert_eq!(values[2], ExtensionValue::Integer(42));
+    }
+
+    // The prefix cannot be shared with another extension
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = E57Writer::new(&mut cursor, "file_guid").unwrap();
+    writer
+        .register_extesion(Extension::new("las", "https://example.com/las"))
+        .unwrap();
+    assert!(writer
+        .register_known_extension(ExtensionDefinition::las())
+        .is_err());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn section_header_crossing_page_boundary() {
+    use std::io::Cursor;
+
+    let prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+    ];
+    let point = |i: usize| {
+        vec![
+            RecordValue::Double(i as f64),
+            RecordValue::Double(1.0),
+            RecordValue::Double(2.0),
+        ]
+    };
+
+    // Grow the first point cloud until the section header of the second one
+    // starts less than 32 bytes before the CRC at the end of a 1024 byte page
+    let mut crossed = false;
+    for first_points in 1..100 {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut e57 = E57Writer::new(&mut cursor, "file").unwrap();
+        let mut pc_writer = e57.add_pointcloud("first", prototype.clone()).unwrap();
+        for i in 0..first_points {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        let mut pc_writer = e57.add_pointcloud("second", prototype.clone()).unwrap();
+        for i in 0..10 {
+            pc_writer.add_point(point(i)).unwrap();
+        }
+        pc_writer.finalize().unwrap();
+        e57.finalize().unwrap();
+        drop(e57);
+
+        cursor.rewind().unwrap();
+        let mut e57 = E57Reader::new(cursor).unwrap();
+        let pcs = e57.pointclouds();
+        if pcs[1].file_offset % 1024 + 32 <= 1020 {
+            continue;
+        }
+        crossed = true;
+        let points = e57
+            .pointcloud_raw(&pcs[1])
+            .unwrap()
+            .collect::<Result<Vec<RawValues>>>()
+            .unwrap();
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[9][0], RecordValue::Double(9.0));
+        break;
+    }
+    assert!(crossed);
+}
+
+#[test]
+fn record_aliases_reader_options() {
+    use std::io::Cursor;
+
+    let amplitude = Record {
+        name: RecordName::Unknown {
+            namespace: "vnd".to_owned(),
+            name: "amplitude".to_owned(),
+        },
+        data_type: RecordDataType::Single {
+            min: Some(0.0),
+            max: Some(1.0),
+        },
+    };
+    let write = |prototype: Vec<Record>| {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut e57 = E57Writer::new(&mut cursor, "file").unwrap();
+        e57.register_extesion(Extension::new("vnd", "http://example.com/vnd"))
+            .unwrap();
+        let values: Vec<RecordValue> = prototype
+            .iter()
+            .map(|r| match r.name {
+                RecordName::Unknown { .. } => RecordValue::Single(0.5),
+                RecordName::Intensity => RecordValue::Single(0.25),
+                _ => RecordValue::Double(1.0),
+            })
+            .collect();
+        let mut pc_writer = e57.add_pointcloud("pc", prototype).unwrap();
+        pc_writer.add_point(values).unwrap();
+        pc_writer.finalize().unwrap();
+        e57.finalize().unwrap();
+        drop(e57);
+        cursor.rewind().unwrap();
+        cursor
+    };
+    let mut options = ReaderOptions::default();
+    options
+        .record_aliases
+        .insert("vnd:amplitude".to_owned(), RecordName::Intensity);
+
+    // Aliased vendor record is read as standard intensity
+    let prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+        amplitude.clone(),
+    ];
+    let mut e57 = E57Reader::with_options(write(prototype.clone()), options.clone()).unwrap();
+    let pcs = e57.pointclouds();
+    assert_eq!(pcs[0].prototype[3].name, RecordName::Intensity);
+    let points = e57
+        .pointcloud_simple(&pcs[0])
+        .unwrap()
+        .collect::<Result<Vec<Point>>>()
+        .unwrap();
+    assert_eq!(points[0].intensity, Some(0.5));
+
+    // Alias collides with a real intensity record that follows the aliased one
+    let mut prototype = prototype;
+    prototype.push(Record {
+        name: RecordName::Intensity,
+        data_type: RecordDataType::Single {
+            min: Some(0.0),
+            max: Some(1.0),
+        },
+    });
+    assert!(E57Reader::with_options(write(prototype), options).is_err());
+}
@@ -9,9 +9,62 @@
  */
 
 use anyhow::{ensure, Context, Result};
-use e57::{CartesianCoordinate, E57Reader};
+use e57::{CartesianCoordinate, E57Reader, ExportOptions, Exporter, PointCloud};
 use las::{Builder, Color, Point, Version, Write, Writer};
 use std::env::args;
+use std::fs::File;
+use std::io::BufWriter;
+
+/// Exporter that writes all points into a LAS or LAZ writer.
+struct LasExporter {
+    writer: Writer<BufWriter<File>>,
+}
+
+impl Exporter for LasExporter {
+    fn begin_pointcloud(&mut self, _pointcloud: &PointCloud) -> e57::Result<Option<ExportOptions>> {
+        let mut options = ExportOptions::default();
+        options.intensity_to_color = false;
+        Ok(Some(options))
+    }
+
+    fn add_point(&mut self, p: &e57::Point) -> e57::Result<()> {
+        let mut point = Point::default();
+        if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
+            point.x = x;
+            point.y = y;
+            point.z = z;
+        } else {
+            return Ok(());
+        }
+        if let Some(color) = &p.color {
+            point.color = Some(Color {
+                red: (color.red * u16::MAX as f32) as u16,
+                green: (color.green * u16::MAX as f32) as u16,
+                blue: (color.blue * u16::MAX as f32) as u16,
+            })
+        }
+        if let Some(intensity) = p.intensity {
+            point.intensity = (intensity * u16::MAX as f32) as u16;
+        }
+        self.writer
+            .write(point)
+            .map_err(|e| write_error("Failed to write LAZ point", e))
+    }
+
+    fn finish(&mut self) -> e57::Result<()> {
+        self.writer
+            .close()
+            .map_err(|e| write_error("Failed to finish LAZ file", e))
+    }
+}
+
+fn write_error(desc: &str, source: las::Error) -> e57::Error {
+    e57::Error::Write {
+        desc: desc.to_string(),
+        source: Some(Box::new(source)),
+        context: None,
+    }
+}
 
 fn main() -> Result<()> {
     // Check command line arguments and show usage
@@ -37,46 +90,13 @@ fn main() -> Result<()> {
         .context("Failed to build LAZ header")?;
 
     // Prepare writing to output file
-    let mut writer =
+    let writer =
         Writer::from_path(out_file, header).context("Failed to open new LAZ file for writing")?;
 
-    // Loop over all point clouds in the E57 file
-    let pointclouds = file.pointclouds();
-    for pointcloud in pointclouds {
-        let mut iter = file
-            .pointcloud_simple(&pointcloud)
-            .context("Unable to get point cloud iterator")?;
-
-        // Set point iterator options
-        iter.spherical_to_cartesian(true);
-        iter.cartesian_to_spherical(false);
-        iter.intensity_to_color(false);
-        iter.apply_pose(true);
-
-        // Iterate over all points in point cloud
-        for p in iter {
-            let p = p.context("Unable to read next point")?;
-            let mut point = Point::default();
-            if let CartesianCoordinate::Valid { x, y, z } = p.cartesian {
-                point.x = x;
-                point.y = y;
-                point.z = z;
-            } else {
-                continue;
-            }
-            if let Some(color) = p.color {
-                point.color = Some(Color {
-                    red: (color.red * u16::MAX as f32) as u16,
-                    green: (color.green * u16::MAX as f32) as u16,
-                    blue: (color.blue * u16::MAX as f32) as u16,
-                })
-            }
-            if let Some(intensity) = p.intensity {
-                point.intensity = (intensity * u16::MAX as f32) as u16;
-            }
-            writer.write(point).context("Failed to write LAZ point")?;
-        }
-    }
+    // Export all point clouds into the output file
+    let mut exporter = LasExporter { writer };
+    file.export(&mut exporter)
+        .context("Failed to export points")?;
 
     Ok(())
 }